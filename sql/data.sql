@@ -42,3 +42,13 @@ INSERT INTO stock.market_time (id, exchange, start_time, end_time)
 VALUES (6, 'HKEX', '13:00:00', '16:00:00');
 INSERT INTO stock.market_time (id, exchange, start_time, end_time)
 VALUES (7, 'NASDAQ', '09:30:00', '16:00:00');
+INSERT INTO stock.market_time (id, exchange, start_time, end_time)
+VALUES (8, 'BSE', '09:30:00', '11:30:00');
+INSERT INTO stock.market_time (id, exchange, start_time, end_time)
+VALUES (9, 'BSE', '13:00:00', '15:00:00');
+INSERT INTO stock.market_time (id, exchange, start_time, end_time)
+VALUES (10, 'NYSE', '09:30:00', '16:00:00');
+INSERT INTO stock.market_time (id, exchange, start_time, end_time)
+VALUES (11, 'AMEX', '09:30:00', '16:00:00');
+
+-- exchange 表的内置交易所在服务启动时写入，见 exchange_registry::BUILT_IN_EXCHANGES
@@ -33,10 +33,152 @@ create table stock.stock
     name       varchar(100) not null,
     exchange   varchar(10)                 null,
     stock_type varchar(10) default 'Stock' not null comment '股票类型：Stock/Index',
-    stock_code varchar(10)  null comment '股票在交易所的代码'
+    stock_code varchar(10)  null comment '股票在交易所的代码',
+    delisted_at datetime    null comment '退市时间，上市中的股票为空'
 )
     comment '股市列表';
 
+create table stock.stock_listing_history
+(
+    id          bigint unsigned auto_increment
+        primary key,
+    code        varchar(10)  not null comment '股票代码',
+    name        varchar(100) not null comment '股票名称',
+    exchange    varchar(10)  not null comment '交易所',
+    stock_type  varchar(10)  not null comment '股票类型：Stock/Fund',
+    change_type varchar(10)  not null comment '变化类型：Listed/Delisted/Renamed',
+    old_name    varchar(100) null comment '更名前的名称',
+    changed_at  datetime     not null comment '变化时间'
+)
+    comment '股票上市退市历史';
+
+create index stock_listing_history_code_index
+    on stock.stock_listing_history (code);
+
+create table stock.stock_daily_price
+(
+    code   varchar(20)    not null comment '股票代码',
+    date   date           not null comment '交易日期',
+    open   decimal(20, 4) not null comment '开盘价',
+    close  decimal(20, 4) not null comment '收盘价',
+    high   decimal(20, 4) not null comment '最高价',
+    low    decimal(20, 4) not null comment '最低价',
+    volume decimal(24, 4) null comment '成交量',
+    amount decimal(24, 4) null comment '成交额',
+    source varchar(20)    not null comment '数据来源',
+    primary key (code, date)
+)
+    comment '股票日线价格（不复权）';
+
+create table stock.corporate_action
+(
+    code          varchar(20)    not null comment '股票代码',
+    ex_date       date           not null comment '除权除息日',
+    action_type   varchar(20)    not null comment '行动类型：Dividend/Bonus/Split/Rights',
+    cash          decimal(20, 6) null comment '每股现金分红（税前）',
+    ratio         decimal(20, 6) null comment '每股送转、拆分或配股的股数',
+    price         decimal(20, 4) null comment '配股价格',
+    announce_date date           null comment '公告日期',
+    source        varchar(20)    not null comment '数据来源',
+    primary key (code, ex_date, action_type)
+)
+    comment '公司行动';
+
+create table stock.earnings_event
+(
+    code             varchar(20)  not null comment '股票代码',
+    report_date      date         not null comment '财报发布日期',
+    name             varchar(100) not null comment '股票名称',
+    fiscal_quarter   varchar(20)  not null comment '财季截止月份',
+    report_time      varchar(20)  null comment '发布时段：PreMarket/AfterHours',
+    eps_estimate     double       null comment '一致预期 EPS',
+    eps_actual       double       null comment '实际 EPS',
+    surprise_percent double       null comment '超预期百分比',
+    primary key (code, report_date)
+)
+    comment '美股财报发布记录';
+
+create index earnings_event_report_date_index
+    on stock.earnings_event (report_date);
+
+create table stock.alert_rule
+(
+    id                bigint unsigned auto_increment
+        primary key,
+    code              varchar(20)      not null comment '股票代码',
+    rule_type         varchar(20)      not null comment '规则类型：PriceAbove/PriceBelow/ChangePercent/CrossAboveMa/CrossBelowMa/VolumeSpike',
+    threshold         decimal(20, 4)   null comment '阈值：价格、涨跌幅百分比或成交量倍数',
+    period            int unsigned     null comment '均线或平均成交量的交易日数',
+    cooldown          int unsigned     not null default 3600 comment '冷却时间（秒）',
+    enabled           tinyint(1)       not null default 1 comment '是否启用',
+    active            tinyint(1)       null comment '上次评估时条件是否满足',
+    last_triggered_at datetime         null comment '上次提醒时间（UTC）'
+)
+    comment '价格提醒规则';
+
+create index alert_rule_code_index
+    on stock.alert_rule (code);
+
+create table stock.stock_fundamental
+(
+    code             varchar(20) not null comment '股票代码'
+        primary key,
+    date             date        not null comment '快照日期',
+    currency         varchar(10) not null comment '市值的货币',
+    total_shares     double      null comment '总股本',
+    float_shares     double      null comment '流通股本',
+    market_cap       double      null comment '总市值',
+    float_market_cap double      null comment '流通市值',
+    pe_ttm           double      null comment '滚动市盈率',
+    pb               double      null comment '市净率',
+    dividend_yield   double      null comment '近 12 个月股息率（%）',
+    week52_high      double      null comment '52 周最高价（前复权）',
+    week52_low       double      null comment '52 周最低价（前复权）',
+    source           varchar(20) not null comment '数据来源'
+)
+    comment '股票基本面快照';
+
+create table stock.limit_up_stock
+(
+    date                      date           not null comment '交易日期',
+    code                      varchar(20)    not null comment '股票代码',
+    exchange                  varchar(10)    not null comment '交易所',
+    name                      varchar(100)   not null comment '股票名称',
+    change_percent            double         not null comment '涨跌幅',
+    latest_price              double         not null comment '收盘价',
+    turnover_amount           double         not null comment '成交额',
+    circulating_market_cap    double         not null comment '流通市值',
+    total_market_cap          double         not null comment '总市值',
+    turnover_rate             double         not null comment '换手率',
+    limit_up_order_fund       double         not null comment '封板资金',
+    first_limit_up_time       varchar(10)    not null comment '首次封板时间',
+    last_limit_up_time        varchar(10)    not null comment '最后封板时间',
+    limit_up_break_count      int unsigned   not null comment '炸板次数',
+    limit_up_stats            varchar(20)    not null comment '涨停统计',
+    consecutive_limit_up_days int unsigned   not null comment '连板数',
+    industry                  varchar(50)    not null comment '所属行业',
+    primary key (date, code)
+)
+    comment '每日涨停池';
+
+create index limit_up_stock_exchange_index
+    on stock.limit_up_stock (exchange);
+
+create table stock.exchange
+(
+    code            varchar(10)  not null comment '交易所代码'
+        primary key,
+    name            varchar(50)  not null comment '交易所名称',
+    suffix          varchar(5)   not null comment '股票代码后缀',
+    int_code        int unsigned not null comment '内部代码，作为休市日期 id 的后两位',
+    time_zone       varchar(50)  not null comment '时区',
+    currency        varchar(3)   not null comment '计价货币',
+    country         varchar(2)   not null comment '国家或地区代码',
+    calendar_source varchar(20)  not null comment '交易日历来源：szse/gov_hk/nasdaq',
+    enabled         tinyint(1)   not null default 1 comment '是否参与同步'
+)
+    comment '交易所';
+
 create table stock.market_time
 (
     id         bigint unsigned auto_increment
@@ -0,0 +1,57 @@
+use crate::alert::alert_job::EvaluateAlertsJob;
+use crate::alert::alert_model::CreateAlertRule;
+use crate::alert::alert_svc;
+use crate::error;
+use application_core::lang::runnable::Runnable;
+use application_web::response::RespBody;
+use application_web_macros::{get, post};
+use axum::Json;
+use axum::extract::Path;
+use axum::response::IntoResponse;
+use tokio::spawn;
+use tracing::info;
+
+/// 获取全部价格提醒规则
+#[get("/alert/rules")]
+async fn rules() -> impl IntoResponse {
+    let r = alert_svc::get_rules().await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+/// 新建价格提醒规则
+///
+/// `rule_type` 可选 PriceAbove、PriceBelow、ChangePercent、CrossAboveMa、CrossBelowMa、VolumeSpike：
+///
+/// ```text
+/// POST /alert/rule
+/// {"code": "600000.SH", "rule_type": "CrossAboveMa", "period": 20, "cooldown": 3600}
+/// {"code": "AAPL.NS", "rule_type": "VolumeSpike", "threshold": 3, "period": 5}
+/// ```
+#[post("/alert/rule")]
+async fn create_rule(Json(body): Json<CreateAlertRule>) -> impl IntoResponse {
+    info!(
+        "Create alert rule, code = {}, type = {}",
+        body.code, body.rule_type
+    );
+    let r = alert_svc::create_rule(&body).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+/// 删除价格提醒规则
+#[post("/alert/rule/{id}/delete")]
+async fn delete_rule(Path(id): Path<u64>) -> impl IntoResponse {
+    info!("Delete alert rule {}", id);
+    let r = alert_svc::delete_rule(id).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+/// 在后台立即评估一次全部提醒规则
+#[get("/alert/evaluate")]
+async fn evaluate() -> impl IntoResponse {
+    spawn(async {
+        let job = EvaluateAlertsJob;
+        job.run().await;
+    });
+
+    RespBody::<()>::success_info("Evaluate alert rules in background")
+}
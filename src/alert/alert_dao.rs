@@ -0,0 +1,79 @@
+use crate::alert::alert_model;
+use crate::alert::alert_model::{ActiveModel, Model};
+use application_beans::factory::bean_factory::BeanFactory;
+use application_context::context::application_context::APPLICATION_CONTEXT;
+use database_mysql_seaorm::Dao;
+use sea_orm::ActiveValue::Set;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, DbErr, EntityTrait, QueryFilter, QueryOrder};
+
+/// 按 id 升序查询全部提醒规则
+pub async fn find_rules() -> Result<Vec<Model>, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    alert_model::Entity::find()
+        .order_by_asc(alert_model::Column::Id)
+        .all(&dao.connection)
+        .await
+}
+
+/// 查询启用的提醒规则
+pub async fn find_enabled_rules() -> Result<Vec<Model>, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    alert_model::Entity::find()
+        .filter(alert_model::Column::Enabled.eq(true))
+        .all(&dao.connection)
+        .await
+}
+
+/// 保存新的提醒规则，返回规则 id
+pub async fn insert_rule(rule: ActiveModel) -> Result<u64, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    let result = alert_model::Entity::insert(rule)
+        .exec(&dao.connection)
+        .await?;
+    Ok(result.last_insert_id)
+}
+
+/// 删除提醒规则，返回删除的行数
+pub async fn delete_rule(id: u64) -> Result<u64, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    let result = alert_model::Entity::delete_by_id(id)
+        .exec(&dao.connection)
+        .await?;
+    Ok(result.rows_affected)
+}
+
+/// 更新规则的评估状态和上次提醒时间
+pub async fn update_state(rule: &Model) -> Result<(), DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    alert_model::Entity::update_many()
+        .col_expr(alert_model::Column::Active, Expr::value(rule.active))
+        .col_expr(
+            alert_model::Column::LastTriggeredAt,
+            Expr::value(rule.last_triggered_at),
+        )
+        .filter(alert_model::Column::Id.eq(rule.id))
+        .exec(&dao.connection)
+        .await?;
+    Ok(())
+}
+
+/// 新规则的 `ActiveModel`，id 由数据库生成
+pub fn new_rule(rule: &Model) -> ActiveModel {
+    ActiveModel {
+        code: Set(rule.code.clone()),
+        rule_type: Set(rule.rule_type.clone()),
+        threshold: Set(rule.threshold.clone()),
+        period: Set(rule.period),
+        cooldown: Set(rule.cooldown),
+        enabled: Set(rule.enabled),
+        active: Set(None),
+        last_triggered_at: Set(None),
+        ..Default::default()
+    }
+}
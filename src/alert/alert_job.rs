@@ -0,0 +1,22 @@
+use crate::alert::alert_svc::evaluate_rules;
+use application_core::lang::runnable::Runnable;
+use async_trait::async_trait;
+use tracing::{error, info};
+
+pub struct EvaluateAlertsJob;
+
+#[async_trait]
+impl Runnable for EvaluateAlertsJob {
+    async fn run(&self) {
+        info!("EvaluateAlertsJob run ...");
+        let r = evaluate_rules().await;
+        match r {
+            Ok(_) => {
+                info!("EvaluateAlertsJob end success")
+            }
+            Err(e) => {
+                error!("Evaluate alert rules error {}", e)
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use bigdecimal::BigDecimal;
+use sea_orm::entity::prelude::*;
+use sea_orm::{ActiveModelBehavior, DeriveEntityModel, DeriveRelation, EnumIter};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// 价格提醒规则
+///
+/// 条件由不满足变为满足时提醒一次，条件持续满足期间不重复提醒，
+/// 两次提醒的间隔不少于冷却时间。
+#[derive(Debug, Serialize, Deserialize, Clone, DeriveEntityModel)]
+#[sea_orm(table_name = "alert_rule")]
+pub struct Model {
+    /// 规则 id
+    #[sea_orm(primary_key)]
+    pub id: u64,
+    /// 股票代码
+    pub code: String,
+    /// 规则类型：PriceAbove/PriceBelow/ChangePercent/CrossAboveMa/CrossBelowMa/VolumeSpike
+    pub rule_type: String,
+    /// 阈值：价格、涨跌幅百分比或成交量相对均量的倍数
+    pub threshold: Option<BigDecimal>,
+    /// 均线或平均成交量的交易日数
+    pub period: Option<u32>,
+    /// 冷却时间（秒）
+    pub cooldown: u32,
+    /// 是否启用
+    pub enabled: bool,
+    /// 上次评估时条件是否满足，未评估过时为空
+    pub active: Option<bool>,
+    /// 上次提醒时间（UTC）
+    pub last_triggered_at: Option<DateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+/// 提醒规则类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertRuleType {
+    /// 价格高于阈值
+    PriceAbove,
+    /// 价格低于阈值
+    PriceBelow,
+    /// 相对上一交易日收盘价的涨跌幅绝对值达到阈值（百分比）
+    ChangePercent,
+    /// 价格向上穿越均线
+    CrossAboveMa,
+    /// 价格向下穿越均线
+    CrossBelowMa,
+    /// 成交量达到最近若干交易日平均成交量的阈值倍数
+    VolumeSpike,
+}
+
+impl AlertRuleType {
+    /// 是否为穿越类规则，穿越类规则需要知道上一次评估时价格所在的一侧
+    pub fn is_cross(&self) -> bool {
+        matches!(
+            self,
+            AlertRuleType::CrossAboveMa | AlertRuleType::CrossBelowMa
+        )
+    }
+
+    /// 是否需要阈值
+    pub fn need_threshold(&self) -> bool {
+        !self.is_cross()
+    }
+
+    /// 是否需要交易日数
+    pub fn need_period(&self) -> bool {
+        matches!(
+            self,
+            AlertRuleType::CrossAboveMa | AlertRuleType::CrossBelowMa | AlertRuleType::VolumeSpike
+        )
+    }
+}
+
+impl Display for AlertRuleType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            AlertRuleType::PriceAbove => "PriceAbove",
+            AlertRuleType::PriceBelow => "PriceBelow",
+            AlertRuleType::ChangePercent => "ChangePercent",
+            AlertRuleType::CrossAboveMa => "CrossAboveMa",
+            AlertRuleType::CrossBelowMa => "CrossBelowMa",
+            AlertRuleType::VolumeSpike => "VolumeSpike",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl FromStr for AlertRuleType {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "PriceAbove" => Ok(AlertRuleType::PriceAbove),
+            "PriceBelow" => Ok(AlertRuleType::PriceBelow),
+            "ChangePercent" => Ok(AlertRuleType::ChangePercent),
+            "CrossAboveMa" => Ok(AlertRuleType::CrossAboveMa),
+            "CrossBelowMa" => Ok(AlertRuleType::CrossBelowMa),
+            "VolumeSpike" => Ok(AlertRuleType::VolumeSpike),
+            _ => Err(format!("Invalid alert rule type: {}", s).into()),
+        }
+    }
+}
+
+/// 新建提醒规则的参数
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateAlertRule {
+    /// 股票代码
+    pub code: String,
+    /// 规则类型
+    pub rule_type: String,
+    /// 阈值
+    pub threshold: Option<BigDecimal>,
+    /// 均线或平均成交量的交易日数
+    pub period: Option<u32>,
+    /// 冷却时间（秒），默认 3600 秒
+    pub cooldown: Option<u32>,
+}
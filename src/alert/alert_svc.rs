@@ -0,0 +1,410 @@
+use crate::alert::alert_dao;
+use crate::alert::alert_model::{AlertRuleType, CreateAlertRule, Model};
+use crate::error::AppError;
+use crate::exchange::exchange_model::Exchange;
+use crate::exchange::exchange_svc;
+use crate::notifier;
+use crate::stock::stock_model::{Model as Stock, StockPrice};
+use crate::stock::stock_price_api::StockDailyPrice;
+use crate::stock::stock_svc;
+use bigdecimal::ToPrimitive;
+use chrono::{Local, NaiveDateTime, TimeDelta, Utc};
+use std::collections::HashMap;
+use std::error::Error;
+use std::str::FromStr;
+use tracing::{error, info};
+
+/// 默认冷却时间（秒）
+const DEFAULT_COOLDOWN_SECONDS: u32 = 3600;
+
+/// 规则条件的评估结果
+struct Evaluation {
+    /// 条件是否满足
+    met: bool,
+    /// 提醒内容
+    message: String,
+}
+
+/// 获取全部提醒规则
+pub async fn get_rules() -> Result<Vec<Model>, Box<dyn Error>> {
+    Ok(alert_dao::find_rules().await?)
+}
+
+/// 新建提醒规则
+///
+/// 价格、涨跌幅和放量规则需要阈值，均线和放量规则需要交易日数，
+/// 规则类型不需要的参数会被忽略。
+pub async fn create_rule(params: &CreateAlertRule) -> Result<Model, Box<dyn Error>> {
+    let rule_type = AlertRuleType::from_str(&params.rule_type)
+        .map_err(|e| AppError::InvalidParam(e.to_string()))?;
+    if rule_type.need_threshold() && params.threshold.is_none() {
+        return Err(AppError::InvalidParam(format!("{} requires threshold", rule_type)).into());
+    }
+    if rule_type.need_period() && params.period.is_none_or(|period| period == 0) {
+        return Err(AppError::InvalidParam(format!("{} requires period", rule_type)).into());
+    }
+    let stock = stock_svc::get_stock(&params.code).await?;
+
+    let mut rule = Model {
+        id: 0,
+        code: stock.code,
+        rule_type: rule_type.to_string(),
+        threshold: params
+            .threshold
+            .clone()
+            .filter(|_| rule_type.need_threshold()),
+        period: params.period.filter(|_| rule_type.need_period()),
+        cooldown: params.cooldown.unwrap_or(DEFAULT_COOLDOWN_SECONDS),
+        enabled: true,
+        active: None,
+        last_triggered_at: None,
+    };
+    rule.id = alert_dao::insert_rule(alert_dao::new_rule(&rule)).await?;
+    info!("Create alert rule {}, code = {}", rule.id, rule.code);
+    Ok(rule)
+}
+
+/// 删除提醒规则
+pub async fn delete_rule(id: u64) -> Result<(), Box<dyn Error>> {
+    if alert_dao::delete_rule(id).await? == 0 {
+        return Err(AppError::NotFound(format!("Alert rule {} not found", id)).into());
+    }
+    Ok(())
+}
+
+/// 评估全部启用的提醒规则
+///
+/// 按股票分组，每只股票只查询一次最新价格，所属交易所不在交易时段时跳过；
+/// 单只股票评估失败不影响其他股票。
+pub async fn evaluate_rules() -> Result<(), Box<dyn Error>> {
+    let rules = alert_dao::find_enabled_rules().await?;
+    let mut groups: HashMap<String, Vec<Model>> = HashMap::new();
+    for rule in rules {
+        groups.entry(rule.code.clone()).or_default().push(rule);
+    }
+    for (code, rules) in groups {
+        if let Err(e) = evaluate_stock_rules(&code, rules).await {
+            error!("Evaluate alert rules of {} error {}", code, e);
+        }
+    }
+    Ok(())
+}
+
+async fn evaluate_stock_rules(code: &str, rules: Vec<Model>) -> Result<(), Box<dyn Error>> {
+    let stock = stock_svc::get_stock(code).await?;
+    let status = exchange_svc::get_exchange_market_status(&stock.exchange).await?;
+    if status != "MarketTrading" {
+        return Ok(());
+    }
+    let price = stock_svc::get_latest_price(&stock).await?;
+    let need_history = rules.iter().any(|rule| {
+        !matches!(
+            AlertRuleType::from_str(&rule.rule_type),
+            Ok(AlertRuleType::PriceAbove | AlertRuleType::PriceBelow)
+        )
+    });
+    let history = if need_history {
+        get_history_prices(&stock).await?
+    } else {
+        Vec::new()
+    };
+
+    let now = Utc::now().naive_utc();
+    for mut rule in rules {
+        let evaluated = AlertRuleType::from_str(&rule.rule_type)
+            .and_then(|rule_type| Ok((rule_type, evaluate(&rule_type, &rule, &price, &history)?)));
+        let (rule_type, evaluation) = match evaluated {
+            Ok(evaluated) => evaluated,
+            Err(e) => {
+                error!("Evaluate alert rule {} error {}", rule.id, e);
+                continue;
+            }
+        };
+
+        let previous = (rule.active, rule.last_triggered_at);
+        let notify = should_notify(&rule_type, &rule, evaluation.met, &now);
+        rule.active = Some(evaluation.met);
+        if notify {
+            let title = format!("价格提醒-{}", stock.name);
+            notifier::send(&title, &evaluation.message).await;
+            rule.last_triggered_at = Some(now);
+        }
+        if (rule.active, rule.last_triggered_at) != previous {
+            alert_dao::update_state(&rule).await?;
+        }
+    }
+    Ok(())
+}
+
+/// 获取当前交易日之前的日线，按时间升序排列
+async fn get_history_prices(stock: &Stock) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
+    let exchange = Exchange::from_str(&stock.exchange)?;
+    let today = Local::now()
+        .with_timezone(&exchange.time_zone())
+        .format("%Y%m%d")
+        .to_string()
+        .parse::<u64>()?;
+    let prices = stock_svc::get_stock_daily_price(&stock.code).await?;
+    Ok(prices
+        .into_iter()
+        .filter(|price| price.time / 1_000_000 < today)
+        .collect())
+}
+
+/// 判断是否需要提醒
+///
+/// 条件由不满足变为满足时才提醒，穿越类规则要求上一次评估时价格在均线的另一侧，
+/// 距上次提醒不足冷却时间时不提醒。
+fn should_notify(rule_type: &AlertRuleType, rule: &Model, met: bool, now: &NaiveDateTime) -> bool {
+    if !met || rule.active == Some(true) {
+        return false;
+    }
+    if rule_type.is_cross() && rule.active.is_none() {
+        return false;
+    }
+    match rule.last_triggered_at {
+        Some(last) => *now - last >= TimeDelta::seconds(rule.cooldown as i64),
+        None => true,
+    }
+}
+
+/// 评估规则条件，`history` 为当前交易日之前的日线
+fn evaluate(
+    rule_type: &AlertRuleType,
+    rule: &Model,
+    price: &StockPrice,
+    history: &[StockDailyPrice],
+) -> Result<Evaluation, Box<dyn Error>> {
+    let close = price.close.to_f64().ok_or("Invalid price")?;
+    let threshold = || -> Result<f64, Box<dyn Error>> {
+        rule.threshold
+            .as_ref()
+            .and_then(|threshold| threshold.to_f64())
+            .ok_or_else(|| format!("Alert rule {} missing threshold", rule.id).into())
+    };
+    let period = || -> Result<usize, Box<dyn Error>> {
+        match rule.period {
+            Some(period) if period > 0 && period as usize <= history.len() + 1 => {
+                Ok(period as usize)
+            }
+            _ => Err(format!("Not enough daily prices for alert rule {}", rule.id).into()),
+        }
+    };
+
+    let evaluation = match rule_type {
+        AlertRuleType::PriceAbove | AlertRuleType::PriceBelow => {
+            let threshold = threshold()?;
+            let (met, label) = if *rule_type == AlertRuleType::PriceAbove {
+                (close > threshold, "高于")
+            } else {
+                (close < threshold, "低于")
+            };
+            Evaluation {
+                met,
+                message: format!("{} 当前价 {} {} {}", rule.code, close, label, threshold),
+            }
+        }
+        AlertRuleType::ChangePercent => {
+            let threshold = threshold()?;
+            let pre_close = history
+                .last()
+                .and_then(|price| price.close.to_f64())
+                .filter(|pre_close| *pre_close != 0.0)
+                .ok_or("Missing previous close")?;
+            let change = (close - pre_close) / pre_close * 100.0;
+            Evaluation {
+                met: change.abs() >= threshold,
+                message: format!("{} 涨跌幅 {:.2}% 达到 ±{}%", rule.code, change, threshold),
+            }
+        }
+        AlertRuleType::CrossAboveMa | AlertRuleType::CrossBelowMa => {
+            // 均线由最近 period - 1 个交易日的收盘价和当前价计算
+            let period = period()?;
+            let mut sum = close;
+            for price in &history[history.len() + 1 - period..] {
+                sum += price.close.to_f64().ok_or("Invalid close price")?;
+            }
+            let ma = sum / period as f64;
+            let (met, label) = if *rule_type == AlertRuleType::CrossAboveMa {
+                (close > ma, "上穿")
+            } else {
+                (close < ma, "下穿")
+            };
+            Evaluation {
+                met,
+                message: format!(
+                    "{} 当前价 {} {} MA{} {:.4}",
+                    rule.code, close, label, period, ma
+                ),
+            }
+        }
+        AlertRuleType::VolumeSpike => {
+            let threshold = threshold()?;
+            let period = period()?;
+            let volume = price
+                .volume
+                .as_ref()
+                .and_then(|volume| volume.to_f64())
+                .ok_or("Missing volume")?;
+            let volumes = history[history.len().saturating_sub(period)..]
+                .iter()
+                .filter_map(|price| price.volume.as_ref().and_then(|volume| volume.to_f64()))
+                .collect::<Vec<_>>();
+            if volumes.is_empty() {
+                return Err("Missing history volume".into());
+            }
+            let average = volumes.iter().sum::<f64>() / volumes.len() as f64;
+            let ratio = if average > 0.0 { volume / average } else { 0.0 };
+            Evaluation {
+                met: ratio >= threshold,
+                message: format!(
+                    "{} 成交量 {} 达到 {} 日均量的 {:.2} 倍",
+                    rule.code, volume, period, ratio
+                ),
+            }
+        }
+    };
+    Ok(evaluation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bigdecimal::BigDecimal;
+    use chrono::NaiveDate;
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, 4)
+            .and_then(|date| date.and_hms_opt(10, 0, 0))
+            .unwrap()
+    }
+
+    fn rule(rule_type: AlertRuleType, active: Option<bool>) -> Model {
+        Model {
+            id: 1,
+            code: "600519.SH".to_string(),
+            rule_type: rule_type.to_string(),
+            threshold: BigDecimal::from_str("1500").ok(),
+            period: Some(3),
+            cooldown: 3600,
+            enabled: true,
+            active,
+            last_triggered_at: None,
+        }
+    }
+
+    fn price(close: &str) -> StockPrice {
+        StockPrice {
+            code: "600519.SH".to_string(),
+            open: None,
+            close: BigDecimal::from_str(close).unwrap(),
+            low: None,
+            high: None,
+            volume: None,
+            time: "2024-03-04 10:00:00".to_string(),
+        }
+    }
+
+    fn daily(time: u64, close: &str) -> StockDailyPrice {
+        let close = BigDecimal::from_str(close).unwrap();
+        StockDailyPrice {
+            time,
+            open: close.clone(),
+            close: close.clone(),
+            high: close.clone(),
+            low: close,
+            volume: None,
+            amount: None,
+        }
+    }
+
+    #[test]
+    fn test_first_evaluation() {
+        // 穿越类规则第一次评估时不知道上一次在均线的哪一侧，不提醒
+        let cross = rule(AlertRuleType::CrossAboveMa, None);
+        assert!(!should_notify(
+            &AlertRuleType::CrossAboveMa,
+            &cross,
+            true,
+            &now()
+        ));
+        let above = rule(AlertRuleType::PriceAbove, None);
+        assert!(should_notify(
+            &AlertRuleType::PriceAbove,
+            &above,
+            true,
+            &now()
+        ));
+        assert!(!should_notify(
+            &AlertRuleType::PriceAbove,
+            &above,
+            false,
+            &now()
+        ));
+    }
+
+    #[test]
+    fn test_no_repeat_while_met() {
+        let rule_type = AlertRuleType::CrossAboveMa;
+        assert!(should_notify(
+            &rule_type,
+            &rule(rule_type, Some(false)),
+            true,
+            &now()
+        ));
+        assert!(!should_notify(
+            &rule_type,
+            &rule(rule_type, Some(true)),
+            true,
+            &now()
+        ));
+    }
+
+    #[test]
+    fn test_cooldown_boundary() {
+        let rule_type = AlertRuleType::PriceAbove;
+        let mut rule = rule(rule_type, Some(false));
+        rule.last_triggered_at = Some(now() - TimeDelta::seconds(3600));
+        assert!(should_notify(&rule_type, &rule, true, &now()));
+        rule.last_triggered_at = Some(now() - TimeDelta::seconds(3599));
+        assert!(!should_notify(&rule_type, &rule, true, &now()));
+    }
+
+    #[test]
+    fn test_evaluate_price() {
+        let rule_type = AlertRuleType::PriceAbove;
+        let rule = rule(rule_type, None);
+        assert!(
+            evaluate(&rule_type, &rule, &price("1500.5"), &[])
+                .unwrap()
+                .met
+        );
+        assert!(
+            !evaluate(&rule_type, &rule, &price("1500"), &[])
+                .unwrap()
+                .met
+        );
+    }
+
+    #[test]
+    fn test_evaluate_cross_ma_period() {
+        let rule_type = AlertRuleType::CrossAboveMa;
+        let history = vec![daily(20240229093000, "9"), daily(20240301093000, "10")];
+        // period 等于历史日线数加 1 时，均线由全部历史日线和当前价计算
+        let rule = rule(rule_type, Some(false));
+        let evaluation = evaluate(&rule_type, &rule, &price("11"), &history).unwrap();
+        assert!(evaluation.met);
+        assert!(evaluation.message.contains("MA3 10.0000"));
+        assert!(
+            !evaluate(&rule_type, &rule, &price("10"), &history)
+                .unwrap()
+                .met
+        );
+
+        let rule = Model {
+            period: Some(4),
+            ..rule
+        };
+        assert!(evaluate(&rule_type, &rule, &price("11"), &history).is_err());
+    }
+}
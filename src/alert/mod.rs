@@ -0,0 +1,5 @@
+mod alert_ctrl;
+pub mod alert_dao;
+pub mod alert_job;
+pub mod alert_model;
+pub mod alert_svc;
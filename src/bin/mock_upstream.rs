@@ -0,0 +1,248 @@
+//! 模拟上游接口服务
+//!
+//! 返回 `tests/fixtures` 中录制的上交所、深交所、港交所、纳斯达克、akshare、中证指数、恒生指数、
+//! 香港政府、维基百科、招商银行和 Yahoo Finance 接口响应。将 `stock.api.*` 全部指向该服务后，股票同步、行情和
+//! 节假日流程都可以在没有网络的环境下运行，参考 `mock/config.toml`。
+//!
+//! 监听地址通过 `MOCK_UPSTREAM_ADDR` 设置，默认 `127.0.0.1:18081`；录制数据目录通过
+//! `MOCK_UPSTREAM_FIXTURES` 设置，默认为仓库中的 `tests/fixtures`。
+use axum::Router;
+use axum::extract::{Path, Query, State};
+use axum::http::{StatusCode, Uri, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{MethodRouter, get};
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+type Fixtures = Arc<PathBuf>;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    tracing_subscriber::fmt::init();
+    let addr = env::var("MOCK_UPSTREAM_ADDR").unwrap_or_else(|_| "127.0.0.1:18081".to_string());
+    let fixtures = env::var("MOCK_UPSTREAM_FIXTURES")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures")));
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    info!(
+        "Mock upstream listening on http://{}, fixtures = {}",
+        addr,
+        fixtures.display()
+    );
+    axum::serve(listener, router(Arc::new(fixtures))).await?;
+    Ok(())
+}
+
+fn router(fixtures: Fixtures) -> Router {
+    Router::new()
+        // 上交所行情 stock.api.sh.baseurl
+        .route("/v1/sh1/snap/{code}", fixture("sse/snap.json"))
+        .route("/v1/shb1/snap/{code}", fixture("sse/snap.json"))
+        .route("/v1/sh1/dayk/{code}", fixture("sse/dayk.json"))
+        .route("/v1/sh1/line/{code}", fixture("sse/line.json"))
+        .route(
+            "/v1/sh1/list/exchange/equity",
+            fixture("sse/equity_list.json"),
+        )
+        // 上交所查询 stock.api.sh.query
+        .route("/sseQuery/commonExcelDd.do", fixture("sse/stock_list.xlsx"))
+        .route("/commonSoaQuery.do", fixture("sse/fund_list.json"))
+        // 深交所 stock.api.sz.baseurl
+        .route("/api/report/ShowReport", get(szse_report))
+        .route(
+            "/api/report/exchange/onepersistenthour/monthList",
+            fixture("szse/month_list.json"),
+        )
+        .route(
+            "/api/market/ssjjhq/getTimeData",
+            fixture("szse/time_data.json"),
+        )
+        .route(
+            "/api/market/ssjjhq/getHistoryData",
+            fixture("szse/history_data.json"),
+        )
+        // 港交所 stock.api.hk.baseurl、stock.api.hk.website
+        .route("/hkexwidget/data/getchartdata2", get(hkex_chart_data))
+        .route(
+            "/hkexwidget/data/getequityquote",
+            fixture("hkex/equity_quote.jsonp"),
+        )
+        .route(
+            "/hkexwidget/data/getetpfilter",
+            fixture("hkex/etp_filter.jsonp"),
+        )
+        .route(
+            "/Market-Data/Securities-Prices/Equities/Equities-Quote",
+            fixture("hkex/equities_quote.html"),
+        )
+        // 纳斯达克 stock.api.nasdaq.baseurl、charting、website
+        .route("/api/quote/watchlist", fixture("nasdaq/watchlist.json"))
+        .route("/api/quote/{code}/info", fixture("nasdaq/quote_info.json"))
+        .route(
+            "/api/quote/{code}/summary",
+            fixture("nasdaq/quote_summary.json"),
+        )
+        .route(
+            "/api/quote/list-type/{name}",
+            fixture("nasdaq/list_type_nasdaq100.json"),
+        )
+        .route("/api/screener/etf", fixture("nasdaq/etf_screener.json"))
+        .route("/api/screener/stocks", get(nasdaq_stock_screener))
+        .route(
+            "/api/company/{code}/earnings-surprise",
+            fixture("nasdaq/earnings_surprise.json"),
+        )
+        .route(
+            "/api/calendar/earnings",
+            fixture("nasdaq/earnings_calendar.json"),
+        )
+        .route(
+            "/data/charting/historical",
+            fixture("nasdaq/charting_historical.json"),
+        )
+        .route(
+            "/market-activity/stock-market-holiday-schedule",
+            fixture("nasdaq/holiday_schedule.html"),
+        )
+        // akshare stock.api.akshare.baseurl
+        .route("/api/public/{name}", get(akshare))
+        // 中证指数 stock.api.csindex.baseurl
+        .route(
+            "/static/html/csindex/public/uploads/file/autofile/cons/{file}",
+            fixture("csindex/000300cons.xlsx"),
+        )
+        // 恒生指数 stock.api.hsi.baseurl
+        .route(
+            "/data/schi/rt/index-series/{series}/constituents.do",
+            fixture("hsi/constituents.json"),
+        )
+        // 香港政府公众假期 stock.api.govhk.baseurl
+        .route(
+            "/sc/about/abouthk/holiday/{file}",
+            fixture("gov_hk/holiday.html"),
+        )
+        // 维基百科 stock.api.wikipedia.baseurl
+        .route("/wiki/{page}", fixture("wikipedia/spx.html"))
+        // 招商银行汇率 stock.api.cmbchina.baseurl
+        .route("/api/v1/fx/rate", fixture("cmbchina/fx_rate.json"))
+        // Yahoo Finance 图表 stock.api.yahoo.baseurl
+        .route("/v8/finance/chart/{symbol}", get(yahoo_chart))
+        .fallback(not_found)
+        .with_state(fixtures)
+}
+
+/// 任意参数都返回同一份录制数据
+fn fixture(name: &'static str) -> MethodRouter<Fixtures> {
+    get(move |State(fixtures): State<Fixtures>| async move { serve(&fixtures, name) })
+}
+
+/// 深交所报表按 CATALOGID 区分股票列表和基金列表
+async fn szse_report(
+    State(fixtures): State<Fixtures>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    match params.get("CATALOGID").map(String::as_str) {
+        Some("1110") => serve(&fixtures, "szse/stock_list.xlsx"),
+        Some("1105") => serve(&fixtures, "szse/fund_list.xlsx"),
+        _ => (StatusCode::NOT_FOUND, "Unknown CATALOGID").into_response(),
+    }
+}
+
+/// 纳斯达克股票筛选器按 exchange 区分纳斯达克、纽交所和美交所上市的股票
+async fn nasdaq_stock_screener(
+    State(fixtures): State<Fixtures>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    match params.get("exchange").map(String::as_str) {
+        Some("nasdaq") => serve(&fixtures, "nasdaq/screener_nasdaq.json"),
+        Some("nyse") => serve(&fixtures, "nasdaq/screener_nyse.json"),
+        Some("amex") => serve(&fixtures, "nasdaq/screener_amex.json"),
+        _ => (StatusCode::NOT_FOUND, "Unknown exchange").into_response(),
+    }
+}
+
+/// 港交所图表接口 span=6 为日 K 线，其余为当日分时
+async fn hkex_chart_data(
+    State(fixtures): State<Fixtures>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    match params.get("span").map(String::as_str) {
+        Some("6") => serve(&fixtures, "hkex/chart_data.jsonp"),
+        _ => serve(&fixtures, "hkex/chart_intraday.jsonp"),
+    }
+}
+
+/// Yahoo Finance 图表接口 interval=1m 为当日分钟线，其余为日线、分红和拆股
+async fn yahoo_chart(
+    State(fixtures): State<Fixtures>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    match params.get("interval").map(String::as_str) {
+        Some("1m") => serve(&fixtures, "yahoo/chart_intraday.json"),
+        _ => serve(&fixtures, "yahoo/chart_daily.json"),
+    }
+}
+
+async fn akshare(
+    State(fixtures): State<Fixtures>,
+    Path(name): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    let fixture = match name.as_str() {
+        "stock_zh_a_daily" | "stock_us_daily" | "index_us_stock_sina" => {
+            "akshare/stock_zh_a_daily.json"
+        }
+        "stock_history_dividend_detail" => match params.get("indicator").map(String::as_str) {
+            Some("配股") => "akshare/rights_detail.json",
+            _ => "akshare/dividend_detail.json",
+        },
+        "stock_zt_pool_em" => "akshare/zt_pool.json",
+        "stock_zt_pool_dtgc_em" => "akshare/dtgc_pool.json",
+        "stock_zt_pool_zbgc_em" => "akshare/zbgc_pool.json",
+        "stock_zt_pool_previous_em" => "akshare/previous_zt_pool.json",
+        "stock_value_em" => "akshare/stock_value_em.json",
+        "stock_hk_financial_indicator_em" => "akshare/hk_financial_indicator.json",
+        "stock_info_bj_name_code" => "akshare/stock_info_bj_name_code.json",
+        "stock_bid_ask_em" => "akshare/stock_bid_ask_em.json",
+        _ => return not_found_response(&format!("/api/public/{}", name)),
+    };
+    serve(&fixtures, fixture)
+}
+
+async fn not_found(uri: Uri) -> Response {
+    not_found_response(uri.path())
+}
+
+fn not_found_response(path: &str) -> Response {
+    warn!("No recorded response for {}", path);
+    (
+        StatusCode::NOT_FOUND,
+        format!("No recorded response for {}", path),
+    )
+        .into_response()
+}
+
+fn serve(fixtures: &Fixtures, name: &str) -> Response {
+    match std::fs::read(fixtures.join(name)) {
+        Ok(body) => ([(header::CONTENT_TYPE, content_type(name))], body).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to read fixture {}: {}", name, e),
+        )
+            .into_response(),
+    }
+}
+
+fn content_type(name: &str) -> &'static str {
+    match name.rsplit('.').next() {
+        Some("json") => "application/json; charset=utf-8",
+        Some("jsonp") => "application/javascript; charset=utf-8",
+        Some("html") => "text/html; charset=utf-8",
+        Some("xlsx") => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        _ => "application/octet-stream",
+    }
+}
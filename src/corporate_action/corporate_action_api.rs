@@ -0,0 +1,254 @@
+use crate::corporate_action::corporate_action_model::{ActionType, Model};
+use crate::exchange::exchange_model::Exchange;
+use crate::stock::stock_model;
+use crate::stock::stock_price_api::{
+    YahooDividend, YahooSplit, get_akshare_base_url, get_yahoo_chart, to_yahoo_symbol,
+};
+use async_trait::async_trait;
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde_json::Value;
+use std::error::Error;
+use std::str::FromStr;
+use tracing::info;
+use util::request::Request;
+
+#[async_trait]
+pub trait CorporateActionApi {
+    async fn get_corporate_actions(
+        &self,
+        stock: &stock_model::Model,
+    ) -> Result<Vec<Model>, Box<dyn Error>>;
+}
+
+#[async_trait]
+impl CorporateActionApi for Exchange {
+    async fn get_corporate_actions(
+        &self,
+        stock: &stock_model::Model,
+    ) -> Result<Vec<Model>, Box<dyn Error>> {
+        match self {
+            Exchange::SSE | Exchange::SZSE | Exchange::BSE => {
+                let mut actions = get_dividend_actions_from_akshare(stock).await?;
+                actions.extend(get_rights_actions_from_akshare(stock).await?);
+                Ok(actions)
+            }
+            Exchange::HKEX | Exchange::NASDAQ | Exchange::NYSE | Exchange::AMEX => {
+                get_actions_from_yahoo(self, stock).await
+            }
+        }
+    }
+}
+
+/// 解析 akshare 返回的日期，支持 `yyyy-MM-ddTHH:mm:ss.SSS` 和 `yyyy-MM-dd` 两种格式
+fn parse_akshare_date(value: &Value) -> Option<NaiveDate> {
+    let date = value.as_str()?;
+    NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.f")
+        .map(|datetime| datetime.date())
+        .or_else(|_| NaiveDate::parse_from_str(date, "%Y-%m-%d"))
+        .ok()
+}
+
+/// 将 akshare 返回的每 10 股数值换算为每股数值，为空或为 0 时返回 `None`
+fn per_share(value: &Value) -> Option<BigDecimal> {
+    let value = BigDecimal::from_str(&value.as_f64()?.to_string()).ok()?;
+    if value.is_zero() {
+        return None;
+    }
+    Some(value / 10)
+}
+
+async fn get_akshare_dividend_detail(
+    stock: &stock_model::Model,
+    indicator: &str,
+) -> Result<Vec<Value>, Box<dyn Error>> {
+    let base_url = get_akshare_base_url().await?;
+    let url = format!(
+        "{}/api/public/stock_history_dividend_detail?symbol={}&indicator={}",
+        base_url, stock.stock_code, indicator
+    );
+    info!("Get corporate actions from akshare: {}", url);
+    let response = Request::get_response(&url).await?;
+    let data: Value = response.json().await?;
+    Ok(data.as_array().cloned().unwrap_or_default())
+}
+
+/// 从 akshare 获取 A 股分红送转，只保留已实施的方案
+async fn get_dividend_actions_from_akshare(
+    stock: &stock_model::Model,
+) -> Result<Vec<Model>, Box<dyn Error>> {
+    let rows = get_akshare_dividend_detail(stock, "分红").await?;
+    let mut actions = Vec::new();
+    for row in rows {
+        if row["进度"].as_str() != Some("实施") {
+            continue;
+        }
+        let ex_date = match parse_akshare_date(&row["除权除息日"]) {
+            Some(ex_date) => ex_date,
+            None => continue,
+        };
+        let announce_date = parse_akshare_date(&row["公告日期"]);
+
+        if let Some(cash) = per_share(&row["派息"]) {
+            actions.push(Model {
+                code: stock.code.clone(),
+                ex_date,
+                action_type: ActionType::Dividend.to_string(),
+                cash: Some(cash),
+                ratio: None,
+                price: None,
+                announce_date,
+                source: "akshare".to_string(),
+            });
+        }
+        let bonus = match (per_share(&row["送股"]), per_share(&row["转增"])) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+        if let Some(ratio) = bonus {
+            actions.push(Model {
+                code: stock.code.clone(),
+                ex_date,
+                action_type: ActionType::Bonus.to_string(),
+                cash: None,
+                ratio: Some(ratio),
+                price: None,
+                announce_date,
+                source: "akshare".to_string(),
+            });
+        }
+    }
+    Ok(actions)
+}
+
+/// 从 akshare 获取 A 股配股
+async fn get_rights_actions_from_akshare(
+    stock: &stock_model::Model,
+) -> Result<Vec<Model>, Box<dyn Error>> {
+    let rows = get_akshare_dividend_detail(stock, "配股").await?;
+    let mut actions = Vec::new();
+    for row in rows {
+        let ex_date = match parse_akshare_date(&row["除权日"]) {
+            Some(ex_date) => ex_date,
+            None => continue,
+        };
+        let (ratio, price) = match (per_share(&row["配股方案"]), row["配股价格"].as_f64()) {
+            (Some(ratio), Some(price)) => (ratio, BigDecimal::from_str(&price.to_string())?),
+            _ => continue,
+        };
+        actions.push(Model {
+            code: stock.code.clone(),
+            ex_date,
+            action_type: ActionType::Rights.to_string(),
+            cash: None,
+            ratio: Some(ratio),
+            price: Some(price),
+            announce_date: parse_akshare_date(&row["公告日期"]),
+            source: "akshare".to_string(),
+        });
+    }
+    Ok(actions)
+}
+
+/// 从 Yahoo Finance 获取港股、美股的分红和拆股
+async fn get_actions_from_yahoo(
+    exchange: &Exchange,
+    stock: &stock_model::Model,
+) -> Result<Vec<Model>, Box<dyn Error>> {
+    let symbol = to_yahoo_symbol(stock);
+    info!(
+        "Get corporate actions from Yahoo Finance, symbol: {}",
+        symbol
+    );
+    let chart = get_yahoo_chart(&symbol, "1d", "max").await?;
+    let to_date = |timestamp: i64| {
+        DateTime::from_timestamp(timestamp, 0)
+            .unwrap_or_default()
+            .with_timezone(&exchange.time_zone())
+            .date_naive()
+    };
+
+    let mut actions = Vec::new();
+    for dividend in unadjust_yahoo_dividends(chart.dividends, &chart.splits) {
+        actions.push(Model {
+            code: stock.code.clone(),
+            ex_date: to_date(dividend.date),
+            action_type: ActionType::Dividend.to_string(),
+            cash: Some(BigDecimal::from_str(&format!("{:.6}", dividend.amount))?),
+            ratio: None,
+            price: None,
+            announce_date: None,
+            source: "yahoo".to_string(),
+        });
+    }
+    for split in chart.splits {
+        if split.denominator <= 0.0 {
+            continue;
+        }
+        // 拆股比例换算为每股新增股数，如 2 拆 1 为 1，10 合 1 为 -0.9
+        let ratio = split.numerator / split.denominator - 1.0;
+        actions.push(Model {
+            code: stock.code.clone(),
+            ex_date: to_date(split.date),
+            action_type: ActionType::Split.to_string(),
+            cash: None,
+            ratio: Some(BigDecimal::from_str(&format!("{:.6}", ratio))?),
+            price: None,
+            announce_date: None,
+            source: "yahoo".to_string(),
+        });
+    }
+    Ok(actions)
+}
+
+/// Yahoo Finance 的分红已按之后的拆股复权，乘以除息日之后全部拆股的比例还原为当时的每股分红，
+/// 与不复权入库的日线一致
+fn unadjust_yahoo_dividends(
+    dividends: Vec<YahooDividend>,
+    splits: &[YahooSplit],
+) -> Vec<YahooDividend> {
+    dividends
+        .into_iter()
+        .map(|dividend| {
+            let factor = splits
+                .iter()
+                .filter(|split| split.date > dividend.date)
+                .filter(|split| split.numerator > 0.0 && split.denominator > 0.0)
+                .map(|split| split.numerator / split.denominator)
+                .product::<f64>();
+            YahooDividend {
+                amount: dividend.amount * factor,
+                ..dividend
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unadjust_yahoo_dividends() {
+        let dividends = vec![
+            YahooDividend {
+                date: 1596807000,
+                amount: 0.205,
+            },
+            YahooDividend {
+                date: 1604673000,
+                amount: 0.205,
+            },
+        ];
+        // 2020-08-31 起 4 拆 1，之前的分红乘以 4，之后的分红不变
+        let splits = vec![YahooSplit {
+            date: 1598880600,
+            numerator: 4.0,
+            denominator: 1.0,
+        }];
+        let dividends = unadjust_yahoo_dividends(dividends, &splits);
+        assert_eq!(format!("{:.3}", dividends[0].amount), "0.820");
+        assert_eq!(dividends[0].date, 1596807000);
+        assert_eq!(dividends[1].amount, 0.205);
+    }
+}
@@ -0,0 +1,39 @@
+use crate::corporate_action::corporate_action_job::SyncCorporateActionJob;
+use crate::corporate_action::corporate_action_svc;
+use crate::error;
+use application_core::lang::runnable::Runnable;
+use application_web::response::RespBody;
+use application_web_macros::get;
+use axum::extract::Query;
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+use tokio::spawn;
+use tracing::info;
+
+#[derive(Serialize, Deserialize)]
+struct CorporateActionParams {
+    code: String,
+}
+
+/// 获取股票的公司行动，包括分红、送转、拆股和配股
+///
+/// ```text
+/// GET /stock/actions?code=600000.SH
+/// ```
+#[get("/stock/actions")]
+async fn corporate_actions(Query(params): Query<CorporateActionParams>) -> impl IntoResponse {
+    info!("Get corporate actions, code = {}", params.code);
+    let r = corporate_action_svc::get_corporate_actions(&params.code).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+/// 在后台同步全部个股的公司行动
+#[get("/stock/actions/sync")]
+async fn sync() -> impl IntoResponse {
+    spawn(async {
+        let job = SyncCorporateActionJob;
+        job.run().await;
+    });
+
+    RespBody::<()>::success_info("Sync corporate actions in background")
+}
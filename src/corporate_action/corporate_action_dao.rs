@@ -0,0 +1,42 @@
+use crate::corporate_action::corporate_action_model;
+use crate::corporate_action::corporate_action_model::Model;
+use application_beans::factory::bean_factory::BeanFactory;
+use application_context::context::application_context::APPLICATION_CONTEXT;
+use database_mysql_seaorm::Dao;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ColumnTrait, DbErr, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder};
+
+/// 按除权除息日升序查询股票的公司行动
+pub async fn find_actions(code: &str) -> Result<Vec<Model>, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    corporate_action_model::Entity::find()
+        .filter(corporate_action_model::Column::Code.eq(code))
+        .order_by_asc(corporate_action_model::Column::ExDate)
+        .all(&dao.connection)
+        .await
+}
+
+/// 批量保存公司行动，已存在的记录保持不变
+pub async fn save_actions(actions: &[Model]) -> Result<(), DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    let models = actions
+        .iter()
+        .map(|action| action.clone().into_active_model())
+        .collect::<Vec<_>>();
+    corporate_action_model::Entity::insert_many(models)
+        .on_conflict(
+            OnConflict::columns([
+                corporate_action_model::Column::Code,
+                corporate_action_model::Column::ExDate,
+                corporate_action_model::Column::ActionType,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .on_empty_do_nothing()
+        .exec(&dao.connection)
+        .await?;
+    Ok(())
+}
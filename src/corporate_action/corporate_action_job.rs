@@ -0,0 +1,22 @@
+use crate::corporate_action::corporate_action_svc::sync_corporate_actions;
+use application_core::lang::runnable::Runnable;
+use async_trait::async_trait;
+use tracing::{error, info};
+
+pub struct SyncCorporateActionJob;
+
+#[async_trait]
+impl Runnable for SyncCorporateActionJob {
+    async fn run(&self) {
+        info!("SyncCorporateActionJob run ...");
+        let r = sync_corporate_actions().await;
+        match r {
+            Ok(_) => {
+                info!("SyncCorporateActionJob end success")
+            }
+            Err(e) => {
+                error!("Sync corporate actions error {}", e)
+            }
+        }
+    }
+}
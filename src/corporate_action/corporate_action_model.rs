@@ -0,0 +1,78 @@
+use bigdecimal::BigDecimal;
+use sea_orm::entity::prelude::*;
+use sea_orm::{ActiveModelBehavior, DeriveEntityModel, DeriveRelation, EnumIter};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// 公司行动：分红、送转、拆股和配股
+///
+/// 同一只股票同一除权除息日的同类行动只保存一条，比例和金额均换算为每股数值。
+#[derive(Debug, Serialize, Deserialize, Clone, DeriveEntityModel)]
+#[sea_orm(table_name = "corporate_action")]
+pub struct Model {
+    /// 股票代码
+    #[sea_orm(primary_key)]
+    pub code: String,
+    /// 除权除息日
+    #[sea_orm(primary_key)]
+    pub ex_date: Date,
+    /// 行动类型：Dividend/Bonus/Split/Rights
+    #[sea_orm(primary_key)]
+    pub action_type: String,
+    /// 每股现金分红（税前）
+    pub cash: Option<BigDecimal>,
+    /// 每股送转、拆分或配股的股数，拆股时为拆分后每股对应的新增股数
+    pub ratio: Option<BigDecimal>,
+    /// 配股价格
+    pub price: Option<BigDecimal>,
+    /// 公告日期
+    pub announce_date: Option<Date>,
+    /// 数据来源
+    pub source: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+/// 公司行动类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionType {
+    /// 现金分红
+    Dividend,
+    /// 送股、转增
+    Bonus,
+    /// 拆股、合股
+    Split,
+    /// 配股
+    Rights,
+}
+
+impl Display for ActionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            ActionType::Dividend => "Dividend",
+            ActionType::Bonus => "Bonus",
+            ActionType::Split => "Split",
+            ActionType::Rights => "Rights",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl FromStr for ActionType {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Dividend" => Ok(ActionType::Dividend),
+            "Bonus" => Ok(ActionType::Bonus),
+            "Split" => Ok(ActionType::Split),
+            "Rights" => Ok(ActionType::Rights),
+            _ => Err(format!("Invalid corporate action type: {}", s).into()),
+        }
+    }
+}
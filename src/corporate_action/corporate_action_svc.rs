@@ -0,0 +1,34 @@
+use crate::corporate_action::corporate_action_api::CorporateActionApi;
+use crate::corporate_action::corporate_action_dao;
+use crate::corporate_action::corporate_action_model::Model;
+use crate::exchange::exchange_model::Exchange;
+use crate::stock::stock_model::{Model as Stock, StockKind};
+use crate::stock::stock_svc;
+use std::error::Error;
+use std::str::FromStr;
+use tracing::{error, info};
+
+/// 获取股票已同步的公司行动，按除权除息日升序
+pub async fn get_corporate_actions(code: &str) -> Result<Vec<Model>, Box<dyn Error>> {
+    Ok(corporate_action_dao::find_actions(code).await?)
+}
+
+/// 同步单只股票的公司行动，已入库的记录保持不变
+pub async fn sync_stock_corporate_actions(stock: &Stock) -> Result<(), Box<dyn Error>> {
+    let exchange = Exchange::from_str(stock.exchange.as_str())?;
+    let actions = exchange.get_corporate_actions(stock).await?;
+    info!("Save {} corporate actions of {}", actions.len(), stock.code);
+    corporate_action_dao::save_actions(&actions).await?;
+    Ok(())
+}
+
+/// 同步全部个股的公司行动，单只股票同步失败不影响其他股票
+pub async fn sync_corporate_actions() -> Result<(), Box<dyn Error>> {
+    let stocks = stock_svc::get_stocks_by_kind(&StockKind::Stock).await?;
+    for stock in stocks {
+        if let Err(e) = sync_stock_corporate_actions(&stock).await {
+            error!("Sync corporate actions of {} error {}", stock.code, e);
+        }
+    }
+    Ok(())
+}
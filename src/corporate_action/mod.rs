@@ -0,0 +1,6 @@
+pub mod corporate_action_api;
+mod corporate_action_ctrl;
+pub mod corporate_action_dao;
+pub mod corporate_action_job;
+pub mod corporate_action_model;
+pub mod corporate_action_svc;
@@ -6,7 +6,10 @@ use std::str::FromStr;
 use util::request::Request;
 
 use crate::currency::currency_model::CurrencyRate;
+use crate::error;
+use crate::error::AppError;
 use crate::exchange::exchange_model::Exchange;
+use crate::upstream;
 
 #[async_trait]
 pub trait CurrencyApi {
@@ -17,30 +20,41 @@ pub trait CurrencyApi {
 impl CurrencyApi for Exchange {
     async fn get_rate(self) -> Result<Vec<CurrencyRate>, Box<dyn Error>> {
         match self {
-            Exchange::SSE | Exchange::SZSE => get_rate().await,
+            Exchange::SSE | Exchange::SZSE | Exchange::BSE => get_rate().await,
             _ => Ok(vec![]),
         }
     }
 }
 
 pub async fn get_rate() -> Result<Vec<CurrencyRate>, Box<dyn Error>> {
-    let response = Request::get_response("https://fx.cmbchina.com/api/v1/fx/rate").await?;
+    let url = format!(
+        "{}/api/v1/fx/rate",
+        upstream::get_url("stock.api.cmbchina.baseurl").await?
+    );
+    let response = Request::get_response(&url).await?;
     let data: Value = response.json().await?;
-    let return_code = data.get("returnCode").unwrap().as_str().unwrap_or_default();
+    let return_code = error::str_field(&data, "returnCode", "cmbchina")?;
     if return_code != "SUC0000" {
-        return Err("获取汇率信息失败".into());
+        return Err(AppError::Upstream {
+            upstream: "cmbchina".to_string(),
+            message: format!("获取汇率信息失败，返回码 {}", return_code),
+        }
+        .into());
     }
-    let body = data.get("body").unwrap().as_array();
+    let body = error::field(&data, "body", "cmbchina")?.as_array();
     let mut currency_rates = Vec::new();
     if let Some(rates) = body {
         for rate in rates {
+            let decimal = |name: &str| {
+                error::str_field(rate, name, "cmbchina").and_then(|value| {
+                    BigDecimal::from_str(value).map_err(|_| AppError::parse("cmbchina", name))
+                })
+            };
             let price = CurrencyRate {
-                from: rate.get("ccyNbrEng").unwrap().as_str().unwrap().to_string(),
+                from: error::str_field(rate, "ccyNbrEng", "cmbchina")?.to_string(),
                 to: "RMB".to_string(),
-                buy_price: BigDecimal::from_str(rate.get("rthOfr").unwrap().as_str().unwrap())?
-                    / 100, // 购汇
-                sell_price: BigDecimal::from_str(rate.get("rthBid").unwrap().as_str().unwrap())?
-                    / 100, // 结汇
+                buy_price: decimal("rthOfr")? / 100,  // 购汇
+                sell_price: decimal("rthBid")? / 100, // 结汇
             };
             currency_rates.push(price);
         }
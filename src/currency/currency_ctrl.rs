@@ -3,10 +3,11 @@ use application_web_macros::get;
 use axum::response::IntoResponse;
 
 use crate::currency::currency_svc;
+use crate::error;
 
 #[get("/currency/rate")]
 pub async fn get_rate() -> impl IntoResponse {
     let r = currency_svc::get_rate().await;
 
-    RespBody::result(&r).response()
+    error::with_status(&r, RespBody::result(&r).response())
 }
@@ -1,3 +1,4 @@
+use crate::error;
 use application_web::response::RespBody;
 use application_web_macros::get;
 use axum::extract::Query;
@@ -24,5 +25,5 @@ struct DebtParams {
 #[get("/debt/price")]
 async fn get_debt_price(Query(params): Query<DebtParams>) -> impl IntoResponse {
     let r = debt_svc::get_debt_price(&params.code).await;
-    RespBody::result(&r).response()
+    error::with_status(&r, RespBody::result(&r).response())
 }
@@ -0,0 +1,174 @@
+use crate::earnings::earnings_model::Model;
+use crate::error;
+use crate::error::AppError;
+use crate::stock::stock_api::EarningsSurpriseRow;
+use crate::upstream;
+use chrono::NaiveDate;
+use serde_json::Value;
+use std::error::Error;
+use tracing::info;
+
+/// 纳斯达克财报日历中的一条记录
+#[derive(Debug, Clone)]
+pub struct CalendarEarnings {
+    pub symbol: String,
+    pub name: String,
+    pub fiscal_quarter: String,
+    pub report_time: Option<String>,
+    pub eps_estimate: Option<f64>,
+}
+
+impl CalendarEarnings {
+    /// 转换为指定股票的财报发布记录
+    pub fn to_model(&self, code: &str, name: &str, report_date: &NaiveDate) -> Model {
+        Model {
+            code: code.to_string(),
+            report_date: *report_date,
+            name: name.to_string(),
+            fiscal_quarter: self.fiscal_quarter.clone(),
+            report_time: self.report_time.clone(),
+            eps_estimate: self.eps_estimate,
+            eps_actual: None,
+            surprise_percent: None,
+        }
+    }
+}
+
+/// 查询纳斯达克指定日期发布财报的全部公司
+pub async fn get_earnings_calendar(
+    date: &NaiveDate,
+) -> Result<Vec<CalendarEarnings>, Box<dyn Error>> {
+    let url = format!(
+        "{}/api/calendar/earnings?date={}",
+        upstream::get_url("stock.api.nasdaq.baseurl").await?,
+        date.format("%Y-%m-%d")
+    );
+    info!("Query earnings calendar from url = {}", url);
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36".parse()?);
+    headers.insert("Accept", "*/*".parse()?);
+    let client = reqwest::Client::builder().build()?;
+    let response = client.get(&url).headers(headers).send().await?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to fetch earnings calendar: {}", response.status()).into());
+    }
+    let data: Value = response.json().await?;
+    Ok(parse_earnings_calendar(&data)?)
+}
+
+/// 解析纳斯达克财报日历，当天没有公司发布财报时 `rows` 为 `null`
+fn parse_earnings_calendar(data: &Value) -> Result<Vec<CalendarEarnings>, AppError> {
+    let data = error::field(data, "data", "nasdaq")?;
+    let rows = match data.get("rows") {
+        Some(Value::Null) | None => return Ok(Vec::new()),
+        Some(_) => error::array_field(data, "rows", "nasdaq")?,
+    };
+    let mut earnings = Vec::new();
+    for row in rows {
+        let report_time = match error::str_field(row, "time", "nasdaq")? {
+            "time-pre-market" => Some("PreMarket".to_string()),
+            "time-after-hours" => Some("AfterHours".to_string()),
+            _ => None,
+        };
+        earnings.push(CalendarEarnings {
+            symbol: error::str_field(row, "symbol", "nasdaq")?.to_string(),
+            name: error::str_field(row, "name", "nasdaq")?.to_string(),
+            fiscal_quarter: parse_fiscal_quarter(error::str_field(
+                row,
+                "fiscalQuarterEnding",
+                "nasdaq",
+            )?),
+            report_time,
+            eps_estimate: parse_amount(error::str_field(row, "epsForecast", "nasdaq")?),
+        });
+    }
+    Ok(earnings)
+}
+
+/// 将纳斯达克历史 EPS 超预期数据转换为财报发布记录，发布日期无法解析的记录忽略
+pub fn parse_earnings_surprise(code: &str, name: &str, rows: &[EarningsSurpriseRow]) -> Vec<Model> {
+    rows.iter()
+        .filter_map(|row| {
+            let report_date = NaiveDate::parse_from_str(&row.date_reported, "%m/%d/%Y").ok()?;
+            Some(Model {
+                code: code.to_string(),
+                report_date,
+                name: name.to_string(),
+                fiscal_quarter: parse_fiscal_quarter(&row.fiscal_qtr_end),
+                report_time: None,
+                eps_estimate: parse_amount(&row.consensus_forecast),
+                eps_actual: Some(row.eps),
+                surprise_percent: parse_amount(&row.percentage_surprise),
+            })
+        })
+        .collect()
+}
+
+/// 财报日历的财季格式为 `Dec/2023`，统一为历史数据的 `Dec 2023` 格式
+fn parse_fiscal_quarter(value: &str) -> String {
+    value.replace('/', " ")
+}
+
+/// 解析 `$2.10`、`($0.05)` 和 `3.81` 格式的数值，括号表示负数，`N/A` 和空字符串返回 `None`
+fn parse_amount(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let (negative, value) = match value.strip_prefix('(').and_then(|v| v.strip_suffix(')')) {
+        Some(value) => (true, value),
+        None => (false, value),
+    };
+    let amount = value.replace(['$', ','], "").parse::<f64>().ok()?;
+    Some(if negative { -amount } else { amount })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_earnings_calendar() {
+        let json = serde_json::from_str(include_str!(
+            "../../tests/fixtures/nasdaq/earnings_calendar.json"
+        ))
+        .unwrap();
+        let earnings = parse_earnings_calendar(&json).unwrap();
+        assert_eq!(earnings.len(), 3);
+        assert_eq!(earnings[0].symbol, "AAPL");
+        assert_eq!(earnings[0].fiscal_quarter, "Dec 2023");
+        assert_eq!(earnings[0].report_time.as_deref(), Some("AfterHours"));
+        assert_eq!(earnings[0].eps_estimate, Some(2.10));
+        assert_eq!(earnings[1].report_time.as_deref(), Some("PreMarket"));
+        assert_eq!(earnings[1].eps_estimate, Some(-0.05));
+        assert_eq!(earnings[2].report_time, None);
+        assert_eq!(earnings[2].eps_estimate, None);
+    }
+
+    #[test]
+    fn test_parse_empty_earnings_calendar() {
+        let json = serde_json::from_str(include_str!(
+            "../../tests/fixtures/nasdaq/earnings_calendar_empty.json"
+        ))
+        .unwrap();
+        assert!(parse_earnings_calendar(&json).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_earnings_surprise() {
+        let json: Value = serde_json::from_str(include_str!(
+            "../../tests/fixtures/nasdaq/earnings_surprise.json"
+        ))
+        .unwrap();
+        let rows: Vec<EarningsSurpriseRow> =
+            serde_json::from_value(json["data"]["earningsSurpriseTable"]["rows"].clone()).unwrap();
+        let events = parse_earnings_surprise("AAPL.NS", "Apple Inc.", &rows);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].code, "AAPL.NS");
+        assert_eq!(
+            events[0].report_date,
+            NaiveDate::from_ymd_opt(2024, 2, 1).unwrap()
+        );
+        assert_eq!(events[0].fiscal_quarter, "Dec 2023");
+        assert_eq!(events[0].eps_actual, Some(2.18));
+        assert_eq!(events[0].eps_estimate, Some(2.10));
+        assert_eq!(events[0].surprise_percent, Some(3.81));
+    }
+}
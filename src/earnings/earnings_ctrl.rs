@@ -0,0 +1,62 @@
+use crate::earnings::earnings_job::SyncEarningsJob;
+use crate::earnings::earnings_svc;
+use crate::error;
+use application_core::lang::runnable::Runnable;
+use application_web::response::RespBody;
+use application_web_macros::get;
+use axum::extract::Query;
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+use tokio::spawn;
+use tracing::info;
+
+#[derive(Serialize, Deserialize)]
+struct EarningsCalendarParams {
+    /// 开始日期，格式 yyyy-MM-dd 或 yyyyMMdd，默认当天
+    from: Option<String>,
+    /// 结束日期，默认开始日期后 30 天
+    to: Option<String>,
+}
+
+/// 获取日期范围内纳斯达克100和标普500成分股的财报日历
+///
+/// ```text
+/// GET /earnings/calendar?from=2024-04-01&to=2024-04-30
+/// ```
+#[get("/earnings/calendar")]
+async fn calendar(Query(params): Query<EarningsCalendarParams>) -> impl IntoResponse {
+    info!(
+        "Get earnings calendar, from = {:?}, to = {:?}",
+        params.from, params.to
+    );
+    let r = earnings_svc::get_earnings_calendar(params.from.as_deref(), params.to.as_deref()).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+#[derive(Serialize, Deserialize)]
+struct StockEarningsParams {
+    code: String,
+}
+
+/// 获取股票已发布和即将发布的财报
+///
+/// ```text
+/// GET /stock/earnings?code=AAPL.NS
+/// ```
+#[get("/stock/earnings")]
+async fn stock_earnings(Query(params): Query<StockEarningsParams>) -> impl IntoResponse {
+    info!("Get stock earnings, code = {}", params.code);
+    let r = earnings_svc::get_stock_earnings(&params.code).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+/// 在后台同步纳斯达克100和标普500成分股的财报
+#[get("/earnings/sync")]
+async fn sync() -> impl IntoResponse {
+    spawn(async {
+        let job = SyncEarningsJob;
+        job.run().await;
+    });
+
+    RespBody::<()>::success_info("Sync earnings in background")
+}
@@ -0,0 +1,88 @@
+use crate::earnings::earnings_model;
+use crate::earnings::earnings_model::Model;
+use application_beans::factory::bean_factory::BeanFactory;
+use application_context::context::application_context::APPLICATION_CONTEXT;
+use database_mysql_seaorm::Dao;
+use sea_orm::prelude::Date;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ColumnTrait, DbErr, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, TransactionTrait,
+};
+
+/// 查询日期范围内（包含两端）发布的财报，按发布日期和股票代码升序排列
+pub async fn find_events_between(start: &Date, end: &Date) -> Result<Vec<Model>, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    earnings_model::Entity::find()
+        .filter(earnings_model::Column::ReportDate.between(*start, *end))
+        .order_by_asc(earnings_model::Column::ReportDate)
+        .order_by_asc(earnings_model::Column::Code)
+        .all(&dao.connection)
+        .await
+}
+
+/// 按发布日期降序查询股票的财报
+pub async fn find_events(code: &str) -> Result<Vec<Model>, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    earnings_model::Entity::find()
+        .filter(earnings_model::Column::Code.eq(code))
+        .order_by_desc(earnings_model::Column::ReportDate)
+        .all(&dao.connection)
+        .await
+}
+
+/// 用财报日历替换指定日期已保存的财报，发布日期调整后不会留下旧的记录
+///
+/// 删除和插入在同一个事务中完成，失败时保留原有的记录。
+///
+/// # Arguments
+///
+/// * `dates` - 已查询到财报日历的日期
+/// * `events` - 这些日期发布的财报
+pub async fn replace_events_on(dates: &[Date], events: &[Model]) -> Result<(), DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    let txn = dao.connection.begin().await?;
+    earnings_model::Entity::delete_many()
+        .filter(earnings_model::Column::ReportDate.is_in(dates.iter().cloned()))
+        .exec(&txn)
+        .await?;
+    let models = events
+        .iter()
+        .map(|event| event.clone().into_active_model())
+        .collect::<Vec<_>>();
+    earnings_model::Entity::insert_many(models)
+        .on_empty_do_nothing()
+        .exec(&txn)
+        .await?;
+    txn.commit().await
+}
+
+/// 批量保存已发布的财报，已存在的记录更新预期和实际 EPS，保留财报日历中的发布时段
+pub async fn save_reported_events(events: &[Model]) -> Result<(), DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    let models = events
+        .iter()
+        .map(|event| event.clone().into_active_model())
+        .collect::<Vec<_>>();
+    earnings_model::Entity::insert_many(models)
+        .on_conflict(
+            OnConflict::columns([
+                earnings_model::Column::Code,
+                earnings_model::Column::ReportDate,
+            ])
+            .update_columns([
+                earnings_model::Column::FiscalQuarter,
+                earnings_model::Column::EpsEstimate,
+                earnings_model::Column::EpsActual,
+                earnings_model::Column::SurprisePercent,
+            ])
+            .to_owned(),
+        )
+        .on_empty_do_nothing()
+        .exec(&dao.connection)
+        .await?;
+    Ok(())
+}
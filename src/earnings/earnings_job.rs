@@ -0,0 +1,22 @@
+use crate::earnings::earnings_svc::sync_earnings;
+use application_core::lang::runnable::Runnable;
+use async_trait::async_trait;
+use tracing::{error, info};
+
+pub struct SyncEarningsJob;
+
+#[async_trait]
+impl Runnable for SyncEarningsJob {
+    async fn run(&self) {
+        info!("SyncEarningsJob run ...");
+        let r = sync_earnings().await;
+        match r {
+            Ok(_) => {
+                info!("SyncEarningsJob end success")
+            }
+            Err(e) => {
+                error!("Sync earnings error {}", e)
+            }
+        }
+    }
+}
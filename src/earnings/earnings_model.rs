@@ -0,0 +1,34 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::{ActiveModelBehavior, DeriveEntityModel, DeriveRelation, EnumIter};
+use serde::{Deserialize, Serialize};
+
+/// 美股财报发布记录，包括已发布和即将发布的财报
+///
+/// 同一只股票同一发布日期只保存一条，EPS 单位为美元。
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "earnings_event")]
+pub struct Model {
+    /// 股票代码
+    #[sea_orm(primary_key)]
+    pub code: String,
+    /// 财报发布日期
+    #[sea_orm(primary_key)]
+    pub report_date: Date,
+    /// 股票名称
+    pub name: String,
+    /// 财季截止月份，如 `Dec 2023`
+    pub fiscal_quarter: String,
+    /// 发布时段：PreMarket/AfterHours，未公布时为空
+    pub report_time: Option<String>,
+    /// 市场一致预期 EPS
+    pub eps_estimate: Option<f64>,
+    /// 实际 EPS，未发布时为空
+    pub eps_actual: Option<f64>,
+    /// 实际 EPS 超出预期的百分比
+    pub surprise_percent: Option<f64>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,150 @@
+use crate::earnings::earnings_api;
+use crate::earnings::earnings_dao;
+use crate::earnings::earnings_model::Model;
+use crate::error::AppError;
+use crate::exchange::exchange_model::Exchange;
+use crate::index::index_svc;
+use crate::stock::stock_api;
+use crate::stock::{stock_model, stock_svc};
+use chrono::{Datelike, Local, NaiveDate, TimeDelta};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::str::FromStr;
+use tracing::{error, info};
+
+/// 同步财报的指数：纳斯达克100和标普500
+const EARNINGS_INDEXES: [&str; 2] = ["NDX.NS", "SPX.NS"];
+
+/// 同步未来 90 天的财报日历
+const UPCOMING_DAYS: i64 = 90;
+
+/// 财报日历默认查询未来 30 天
+const DEFAULT_CALENDAR_DAYS: i64 = 30;
+
+/// 财报日历单次最多查询 366 天
+const MAX_CALENDAR_DAYS: i64 = 366;
+
+/// 查询日期范围内发布的财报，未指定开始日期时为当天，未指定结束日期时为开始日期后 30 天
+pub async fn get_earnings_calendar(
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<Vec<Model>, Box<dyn Error>> {
+    let from = match from {
+        Some(from) => stock_model::parse_query_date(from)?,
+        None => today(),
+    };
+    let to = match to {
+        Some(to) => stock_model::parse_query_date(to)?,
+        None => from + TimeDelta::days(DEFAULT_CALENDAR_DAYS),
+    };
+    if from > to {
+        return Err(
+            AppError::InvalidParam(format!("From date {} is after to date {}", from, to)).into(),
+        );
+    }
+    if (to - from).num_days() > MAX_CALENDAR_DAYS {
+        return Err(AppError::InvalidParam(format!(
+            "Date range too large, at most {} days",
+            MAX_CALENDAR_DAYS
+        ))
+        .into());
+    }
+    Ok(earnings_dao::find_events_between(&from, &to).await?)
+}
+
+/// 获取股票已同步的财报，按发布日期降序
+pub async fn get_stock_earnings(code: &str) -> Result<Vec<Model>, Box<dyn Error>> {
+    let stock = stock_svc::get_stock(code).await?;
+    if !Exchange::from_str(&stock.exchange)?.is_us() {
+        return Err(AppError::UnsupportedExchange(format!(
+            "Earnings data is only available for US stocks, got {}",
+            stock.exchange
+        ))
+        .into());
+    }
+    Ok(earnings_dao::find_events(&stock.code).await?)
+}
+
+/// 同步纳斯达克100和标普500成分股的财报
+///
+/// 先按天查询明天起未来 90 天的财报日历，替换已保存的即将发布的财报；再逐只查询成分股最近几个季度的
+/// 实际 EPS。当天及以前的财报由实际 EPS 更新，不会被财报日历替换；某天的财报日历或单只股票
+/// 查询失败时记录日志并跳过，保留已保存的记录，不影响其他日期和股票。
+pub async fn sync_earnings() -> Result<(), Box<dyn Error>> {
+    let constituents = get_constituents().await?;
+    let start = today() + TimeDelta::days(1);
+    let end = start + TimeDelta::days(UPCOMING_DAYS);
+
+    let mut upcoming = Vec::new();
+    let mut dates = Vec::new();
+    let mut date = start;
+    while date <= end {
+        // 周末不查询财报日历，直接清理已保存的记录
+        let synced = date.weekday().number_from_monday() > 5
+            || match earnings_api::get_earnings_calendar(&date).await {
+                Ok(calendar) => {
+                    for earnings in calendar {
+                        if let Some((code, name)) = constituents.get(&earnings.symbol) {
+                            upcoming.push(earnings.to_model(code, name, &date));
+                        }
+                    }
+                    true
+                }
+                Err(e) => {
+                    error!("Get earnings calendar of {} error {}", date, e);
+                    false
+                }
+            };
+        if synced {
+            dates.push(date);
+        }
+        date += TimeDelta::days(1);
+    }
+    earnings_dao::replace_events_on(&dates, &upcoming).await?;
+    info!(
+        "Save {} upcoming earnings of {} days between {} and {}",
+        upcoming.len(),
+        dates.len(),
+        start,
+        end
+    );
+
+    for (symbol, (code, name)) in &constituents {
+        let rows = match stock_api::get_earnings_surprise(symbol).await {
+            Ok(rows) => rows,
+            Err(e) => {
+                error!("Sync earnings of {} error {}", code, e);
+                continue;
+            }
+        };
+        let events = earnings_api::parse_earnings_surprise(code, name, &rows);
+        earnings_dao::save_reported_events(&events).await?;
+    }
+    info!("Save reported earnings of {} stocks", constituents.len());
+    Ok(())
+}
+
+fn today() -> NaiveDate {
+    Local::now()
+        .with_timezone(&Exchange::NASDAQ.time_zone())
+        .date_naive()
+}
+
+/// 获取同步财报的成分股，返回去掉交易所后缀的美股代码到股票代码和名称的映射
+async fn get_constituents() -> Result<BTreeMap<String, (String, String)>, Box<dyn Error>> {
+    let mut constituents = BTreeMap::new();
+    for index in EARNINGS_INDEXES {
+        for constituent in index_svc::get_constituent_stocks(index).await? {
+            // 成分股分属纳斯达克、纽交所和美交所，后缀为最后一个 `.` 之后的部分
+            let symbol = constituent
+                .stock_code
+                .rsplit_once('.')
+                .map_or(constituent.stock_code.as_str(), |(symbol, _)| symbol)
+                .to_string();
+            constituents
+                .entry(symbol)
+                .or_insert((constituent.stock_code, constituent.stock_name));
+        }
+    }
+    Ok(constituents)
+}
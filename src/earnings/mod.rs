@@ -0,0 +1,6 @@
+pub mod earnings_api;
+mod earnings_ctrl;
+pub mod earnings_dao;
+pub mod earnings_job;
+pub mod earnings_model;
+pub mod earnings_svc;
@@ -0,0 +1,205 @@
+use crate::exchange::exchange_model::ExchangeError;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use sea_orm::DbErr;
+use serde_json::Value;
+use std::error::Error;
+use std::fmt;
+
+/// 错误响应中携带错误码的响应头
+pub const ERROR_CODE_HEADER: &str = "x-error-code";
+
+/// 应用错误
+///
+/// 服务仍返回 `Box<dyn Error>`，控制器通过 [`with_status`] 将其中的 `AppError`
+/// 转换为对应的 HTTP 状态码和错误码，无法识别的错误视为内部错误。
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppError {
+    /// 请求参数无效
+    InvalidParam(String),
+    /// 请求的数据不存在
+    NotFound(String),
+    /// 不支持的交易所
+    UnsupportedExchange(String),
+    /// 上游接口请求失败
+    Upstream { upstream: String, message: String },
+    /// 上游接口返回的数据缺少字段或格式不正确
+    Parse { upstream: String, field: String },
+    /// 数据库错误
+    Database(String),
+    /// 其他内部错误
+    Internal(String),
+}
+
+impl AppError {
+    /// 上游接口返回的数据缺少字段或字段格式不正确
+    pub fn parse(upstream: &str, field: &str) -> Self {
+        AppError::Parse {
+            upstream: upstream.to_string(),
+            field: field.to_string(),
+        }
+    }
+
+    /// 缺少配置项
+    pub fn missing_property(key: &str) -> Self {
+        AppError::Internal(format!("Missing property: {}", key))
+    }
+
+    /// 错误码
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::InvalidParam(_) => "INVALID_PARAM",
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::UnsupportedExchange(_) => "UNSUPPORTED_EXCHANGE",
+            AppError::Upstream { .. } => "UPSTREAM_UNAVAILABLE",
+            AppError::Parse { .. } => "UPSTREAM_PARSE_ERROR",
+            AppError::Database(_) => "DATABASE_ERROR",
+            AppError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+
+    /// 对应的 HTTP 状态码
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::InvalidParam(_) | AppError::UnsupportedExchange(_) => StatusCode::BAD_REQUEST,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Upstream { .. } | AppError::Parse { .. } => StatusCode::BAD_GATEWAY,
+            AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// 将服务返回的错误归类为 `AppError`
+    pub fn from_error(e: &(dyn Error + 'static)) -> Self {
+        if let Some(e) = e.downcast_ref::<AppError>() {
+            return e.clone();
+        }
+        if let Some(e) = e.downcast_ref::<ExchangeError>() {
+            return AppError::UnsupportedExchange(e.to_string());
+        }
+        if let Some(e) = e.downcast_ref::<reqwest::Error>() {
+            return AppError::from_reqwest(e);
+        }
+        if let Some(e) = e.downcast_ref::<DbErr>() {
+            return AppError::Database(e.to_string());
+        }
+        AppError::Internal(e.to_string())
+    }
+
+    fn from_reqwest(e: &reqwest::Error) -> Self {
+        let upstream = e
+            .url()
+            .and_then(|url| url.host_str())
+            .unwrap_or("unknown")
+            .to_string();
+        if e.is_decode() {
+            return AppError::Parse {
+                upstream,
+                field: "body".to_string(),
+            };
+        }
+        AppError::Upstream {
+            upstream,
+            message: e.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::InvalidParam(msg) => write!(f, "{}", msg),
+            AppError::NotFound(msg) => write!(f, "{}", msg),
+            AppError::UnsupportedExchange(msg) => write!(f, "{}", msg),
+            AppError::Upstream { upstream, message } => {
+                write!(f, "Upstream {} request failed: {}", upstream, message)
+            }
+            AppError::Parse { upstream, field } => {
+                write!(
+                    f,
+                    "Upstream {} response missing or invalid field: {}",
+                    upstream, field
+                )
+            }
+            AppError::Database(msg) => write!(f, "Database error: {}", msg),
+            AppError::Internal(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl Error for AppError {}
+
+impl From<ExchangeError> for AppError {
+    fn from(e: ExchangeError) -> Self {
+        AppError::UnsupportedExchange(e.to_string())
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(e: reqwest::Error) -> Self {
+        AppError::from_reqwest(&e)
+    }
+}
+
+impl From<DbErr> for AppError {
+    fn from(e: DbErr) -> Self {
+        AppError::Database(e.to_string())
+    }
+}
+
+/// 读取上游 JSON 中的字段，字段不存在时返回解析错误
+pub fn field<'a>(value: &'a Value, name: &str, upstream: &str) -> Result<&'a Value, AppError> {
+    value
+        .get(name)
+        .ok_or_else(|| AppError::parse(upstream, name))
+}
+
+/// 读取上游 JSON 中的字符串字段
+pub fn str_field<'a>(value: &'a Value, name: &str, upstream: &str) -> Result<&'a str, AppError> {
+    field(value, name, upstream)?
+        .as_str()
+        .ok_or_else(|| AppError::parse(upstream, name))
+}
+
+/// 读取上游 JSON 中的数字字段，返回数字的文本
+pub fn number_field(value: &Value, name: &str, upstream: &str) -> Result<String, AppError> {
+    field(value, name, upstream)?
+        .as_number()
+        .map(|number| number.to_string())
+        .ok_or_else(|| AppError::parse(upstream, name))
+}
+
+/// 读取上游 JSON 中的非负整数字段
+pub fn u64_field(value: &Value, name: &str, upstream: &str) -> Result<u64, AppError> {
+    field(value, name, upstream)?
+        .as_u64()
+        .ok_or_else(|| AppError::parse(upstream, name))
+}
+
+/// 读取上游 JSON 中的数组字段
+pub fn array_field<'a>(
+    value: &'a Value,
+    name: &str,
+    upstream: &str,
+) -> Result<&'a Vec<Value>, AppError> {
+    field(value, name, upstream)?
+        .as_array()
+        .ok_or_else(|| AppError::parse(upstream, name))
+}
+
+/// 按服务结果设置响应的 HTTP 状态码和错误码
+///
+/// 成功时原样返回响应，失败时按错误类型设置状态码，并在 `x-error-code` 响应头中返回错误码：
+///
+/// ```text
+/// let r = stock_svc::get_stock(&params.code).await;
+/// error::with_status(&r, RespBody::result(&r).response())
+/// ```
+pub fn with_status<T>(r: &Result<T, Box<dyn Error>>, response: impl IntoResponse) -> Response {
+    match r {
+        Ok(_) => response.into_response(),
+        Err(e) => {
+            let e = AppError::from_error(e.as_ref());
+            (e.status(), [(ERROR_CODE_HEADER, e.code())], response).into_response()
+        }
+    }
+}
@@ -1,9 +1,10 @@
+use crate::error;
 use crate::exchange::exchange_job::SyncStocksJob;
-use crate::exchange::exchange_model::Exchange;
-use crate::exchange::exchange_svc;
+use crate::exchange::{exchange_registry, exchange_svc};
+use crate::stock::stock_svc;
 use application_core::lang::runnable::Runnable;
 use application_web::response::RespBody;
-use application_web_macros::get;
+use application_web_macros::{get, post};
 use axum::extract::{Path, Query};
 use axum::response::IntoResponse;
 use serde::{Deserialize, Serialize};
@@ -19,9 +20,16 @@ struct MarketStatusParams {
     pub stock_code: String,
 }
 
+/// 股票列表同步请求参数
+#[derive(Serialize, Deserialize)]
+struct StockSyncParams {
+    /// 为 `true` 时同步执行并只返回股票和基金列表的差异，不提交任何变更
+    pub dry_run: Option<bool>,
+}
+
 /// 获取交易所列表
 ///
-/// 处理对 `/exchange/list` 路径的 GET 请求，返回系统中所有可用交易所的列表。
+/// 处理对 `/exchange/list` 路径的 GET 请求，返回 `exchange` 表中全部交易所的元数据。
 /// 该接口主要用于前端或客户端获取支持的交易所信息，便于后续操作选择。
 ///
 /// # 示例
@@ -36,14 +44,56 @@ struct MarketStatusParams {
 ///
 /// # 返回数据
 ///
-/// 返回包含交易所标识符的数组，例如：`["sh", "sz"]`
+/// 返回交易所元数据的数组，包括代码、名称、股票代码后缀、时区、货币、国家或地区、
+/// 交易日历来源和是否参与同步，例如：
+///
+/// ```json
+/// [{"code": "SSE", "name": "上海证券交易所", "suffix": ".SH", "int_code": 10,
+///   "time_zone": "Asia/Chongqing", "currency": "CNY", "country": "CN",
+///   "calendar_source": "szse", "enabled": true}]
+/// ```
 #[get("/exchange/list")]
 async fn exchange_list() -> impl IntoResponse {
-    let exchanges = Exchange::VALUES
-        .iter()
-        .map(|e| e.as_ref().to_string())
-        .collect::<Vec<_>>();
-    RespBody::success(&exchanges)
+    RespBody::success(&exchange_registry::all())
+}
+
+/// 启用交易所
+///
+/// 更新 `exchange` 表并重新加载交易所注册表，之后的股票列表、指数成分股和休市日期同步
+/// 包含该交易所，无需重启服务。
+///
+/// ```text
+/// POST /exchange/BSE/enable
+/// ```
+#[post("/exchange/{exchange}/enable")]
+async fn enable(Path(exchange): Path<String>) -> impl IntoResponse {
+    info!("Enable exchange {}", exchange);
+    let r = exchange_registry::set_enabled(&exchange, true).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+/// 停用交易所
+///
+/// 更新 `exchange` 表并重新加载交易所注册表，之后的同步跳过该交易所，
+/// 手动同步该交易所的股票列表返回参数错误。
+///
+/// ```text
+/// POST /exchange/BSE/disable
+/// ```
+#[post("/exchange/{exchange}/disable")]
+async fn disable(Path(exchange): Path<String>) -> impl IntoResponse {
+    info!("Disable exchange {}", exchange);
+    let r = exchange_registry::set_enabled(&exchange, false).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+/// 重新加载交易所注册表
+///
+/// 直接修改 `exchange` 表后调用，返回加载的交易所数量。
+#[get("/exchange/reload")]
+async fn reload() -> impl IntoResponse {
+    let r = exchange_registry::reload().await;
+    error::with_status(&r, RespBody::result(&r).response())
 }
 
 /// 获取指定交易所的当前时间
@@ -71,7 +121,7 @@ async fn exchange_list() -> impl IntoResponse {
 #[get("/exchange/{exchange}/time")]
 async fn exchange_current_time(Path(exchange): Path<String>) -> impl IntoResponse {
     let r = exchange_svc::get_exchange_current_time(&exchange).await;
-    RespBody::result(&r)
+    error::with_status(&r, RespBody::result(&r))
 }
 
 /// 获取指定交易所的市场状态
@@ -105,11 +155,11 @@ async fn exchange_current_time(Path(exchange): Path<String>) -> impl IntoRespons
 ///
 /// 使用 `Path` 参数来捕获 URL 中的 `exchange` 部分，以便于获取特定交易所的信息。
 /// 通过调用 `exchange_svc::get_exchange_market_status` 函数来获取市场状态信息。
-/// 最后使用 `RespBody::result` 来根据查询结果构建 HTTP 响应。
+/// 最后使用 `RespBody::result` 来根据查询结果构建 HTTP 响应，并由 `error::with_status` 设置错误状态码。
 #[get("/exchange/{exchange}/market/status")]
 async fn get_market_status(Path(exchange): Path<String>) -> impl IntoResponse {
     let r = exchange_svc::get_exchange_market_status(&exchange).await;
-    RespBody::result(&r)
+    error::with_status(&r, RespBody::result(&r))
 }
 
 /// 根据股票代码获取市场状态
@@ -144,7 +194,7 @@ async fn get_market_status_by_stock_code(
 ) -> impl IntoResponse {
     info!("Get market status by stock_code {}", params.stock_code);
     let r = exchange_svc::get_stock_market_status(&params.stock_code).await;
-    RespBody::result(&r)
+    error::with_status(&r, RespBody::result(&r))
 }
 
 /// 同步指定交易所的股票数据
@@ -156,10 +206,13 @@ async fn get_market_status_by_stock_code(
 ///
 /// * `exchange` - 交易所代码，通过 URL 路径参数传递，例如 `SSE`（上海证券交易所）或 `SZSE`（深圳证券交易所）
 ///
+/// * `dry_run` - 可选查询参数，为 `true` 时只对比差异而不提交
+///
 /// # 示例
 ///
 /// ```
 /// GET /exchange/stock/sync/SSE
+/// GET /exchange/stock/sync/SSE?dry_run=true
 /// ```
 ///
 /// # Returns
@@ -169,6 +222,7 @@ async fn get_market_status_by_stock_code(
 /// # 返回数据
 ///
 /// 立即返回确认信息，表示同步任务已启动。实际的数据同步在后台异步执行。
+/// `dry_run=true` 时等待对比完成，返回新上市、退市和更名的股票及基金，数据库保持不变。
 ///
 /// # 异步处理
 ///
@@ -183,11 +237,19 @@ async fn get_market_status_by_stock_code(
 /// - 建议避免频繁调用，以免对数据源造成压力
 /// - 可通过日志或监控工具查看同步进度和结果
 #[get("/exchange/stock/sync/{exchange}")]
-async fn sync(Path(exchange): Path<String>) -> impl IntoResponse {
+async fn sync(
+    Path(exchange): Path<String>,
+    Query(params): Query<StockSyncParams>,
+) -> impl IntoResponse {
+    if params.dry_run.unwrap_or(false) {
+        let r = stock_svc::sync(&exchange, true).await;
+        return error::with_status(&r, RespBody::result(&r).response());
+    }
+
     spawn(async {
         let job = SyncStocksJob { exchange };
         job.run().await;
     });
 
-    RespBody::<()>::success_info("Sync Stocks in background")
+    RespBody::<()>::success_info("Sync Stocks in background").response()
 }
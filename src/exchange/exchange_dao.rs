@@ -0,0 +1,44 @@
+use crate::exchange::exchange_info_model;
+use crate::exchange::exchange_info_model::Model;
+use application_beans::factory::bean_factory::BeanFactory;
+use application_context::context::application_context::APPLICATION_CONTEXT;
+use database_mysql_seaorm::Dao;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, DbErr, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder};
+
+/// 按内部代码升序查询全部交易所
+pub async fn find_all() -> Result<Vec<Model>, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    exchange_info_model::Entity::find()
+        .order_by_asc(exchange_info_model::Column::IntCode)
+        .all(&dao.connection)
+        .await
+}
+
+/// 批量新增交易所
+pub async fn insert_exchanges(exchanges: &[Model]) -> Result<(), DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    let models = exchanges
+        .iter()
+        .map(|exchange| exchange.clone().into_active_model())
+        .collect::<Vec<_>>();
+    exchange_info_model::Entity::insert_many(models)
+        .on_empty_do_nothing()
+        .exec(&dao.connection)
+        .await?;
+    Ok(())
+}
+
+/// 更新交易所是否参与同步，返回更新的行数
+pub async fn update_enabled(code: &str, enabled: bool) -> Result<u64, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    let result = exchange_info_model::Entity::update_many()
+        .col_expr(exchange_info_model::Column::Enabled, Expr::value(enabled))
+        .filter(exchange_info_model::Column::Code.eq(code))
+        .exec(&dao.connection)
+        .await?;
+    Ok(result.rows_affected)
+}
@@ -0,0 +1,33 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::{ActiveModelBehavior, DeriveEntityModel, DeriveRelation, EnumIter};
+use serde::{Deserialize, Serialize};
+
+/// 交易所元数据
+#[derive(Serialize, Deserialize, DeriveEntityModel, Debug, Clone, PartialEq)]
+#[sea_orm(table_name = "exchange")]
+pub struct Model {
+    /// 交易所代码，如 `SSE`、`NASDAQ`
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub code: String,
+    /// 交易所名称
+    pub name: String,
+    /// 股票代码后缀，如 `.SH`
+    pub suffix: String,
+    /// 内部代码，作为休市日期 id 的后两位
+    pub int_code: u32,
+    /// 时区，如 `Asia/Shanghai`
+    pub time_zone: String,
+    /// 计价货币
+    pub currency: String,
+    /// 国家或地区代码，如 `CN`、`US`
+    pub country: String,
+    /// 交易日历来源：szse/gov_hk/nasdaq
+    pub calendar_source: String,
+    /// 是否参与同步
+    pub enabled: bool,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
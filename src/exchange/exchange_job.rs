@@ -11,7 +11,7 @@ pub struct SyncStocksJob {
 impl Runnable for SyncStocksJob {
     async fn run(&self) {
         info!("SyncStocksJob run ...");
-        let result = sync(&self.exchange).await;
+        let result = sync(&self.exchange, false).await;
         match result {
             Ok(_) => {
                 info!("SyncStocksJob end success")
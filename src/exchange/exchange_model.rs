@@ -1,3 +1,4 @@
+use crate::exchange::exchange_registry;
 use chrono_tz::Tz;
 use std::fmt;
 use std::str::FromStr;
@@ -13,39 +14,69 @@ pub enum Exchange {
     HKEX,
     /// 纳斯达克交易所
     NASDAQ,
+    /// 北交所
+    BSE,
+    /// 纽约证券交易所
+    NYSE,
+    /// 美国证券交易所
+    AMEX,
 }
 
 impl Exchange {
-    /// 定义所有支持的交易所常量数组
-    pub const VALUES: [Self; 4] = [Self::SSE, Self::SZSE, Self::HKEX, Self::NASDAQ];
+    /// 定义所有有上游接口的交易所常量数组，元数据见 `exchange` 表
+    ///
+    /// 新增市场需要在这里增加交易所并实现上游接口，`exchange` 表只负责元数据和是否参与同步。
+    pub const VALUES: [Self; 7] = [
+        Self::SSE,
+        Self::SZSE,
+        Self::HKEX,
+        Self::NASDAQ,
+        Self::BSE,
+        Self::NYSE,
+        Self::AMEX,
+    ];
+
+    /// 美股交易所，共用美股的交易日历和交易时间
+    pub const US_VALUES: [Self; 3] = [Self::NASDAQ, Self::NYSE, Self::AMEX];
+
+    /// 交易所在 `VALUES` 中的下标，枚举按 `VALUES` 的顺序声明
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// 是否为美股交易所
+    pub fn is_us(&self) -> bool {
+        exchange_registry::with_meta(*self, |meta| meta.country == "US")
+    }
+
+    /// 是否参与股票列表、指数成分股和休市日期的同步
+    pub fn is_enabled(&self) -> bool {
+        exchange_registry::with_meta(*self, |meta| meta.enabled)
+    }
 
     /// 返回交易所对应的时间区
     pub fn time_zone(&self) -> Tz {
-        match self {
-            Exchange::SSE | Exchange::SZSE => chrono_tz::Asia::Chongqing,
-            Exchange::HKEX => chrono_tz::Asia::Hong_Kong,
-            Exchange::NASDAQ => chrono_tz::America::New_York,
-        }
+        exchange_registry::with_meta(*self, |meta| meta.time_zone)
     }
 
     /// 返回交易所的内部代码
     pub fn int_code(&self) -> usize {
-        match self {
-            Exchange::SSE => 10,
-            Exchange::SZSE => 20,
-            Exchange::HKEX => 30,
-            Exchange::NASDAQ => 40,
-        }
+        exchange_registry::with_meta(*self, |meta| meta.int_code as usize)
     }
 
     /// 返回交易所的股票代码后缀
-    pub fn stock_code_suffix(&self) -> &'static str {
-        match self {
-            Exchange::SSE => ".SH",
-            Exchange::SZSE => ".SZ",
-            Exchange::HKEX => ".HK",
-            Exchange::NASDAQ => ".NS",
-        }
+    pub fn stock_code_suffix(&self) -> String {
+        exchange_registry::with_meta(*self, |meta| meta.suffix.clone())
+    }
+
+    /// 返回交易所的计价货币
+    pub fn currency(&self) -> String {
+        exchange_registry::with_meta(*self, |meta| meta.currency.clone())
+    }
+
+    /// 返回交易所的交易日历来源
+    pub fn calendar_source(&self) -> String {
+        exchange_registry::with_meta(*self, |meta| meta.calendar_source.clone())
     }
 }
 
@@ -56,6 +87,9 @@ impl AsRef<str> for Exchange {
             Exchange::SZSE => "SZSE",
             Exchange::HKEX => "HKEX",
             Exchange::NASDAQ => "NASDAQ",
+            Exchange::BSE => "BSE",
+            Exchange::NYSE => "NYSE",
+            Exchange::AMEX => "AMEX",
         }
     }
 }
@@ -76,6 +110,9 @@ impl FromStr for Exchange {
             "SZSE" => Ok(Exchange::SZSE),
             "HKEX" => Ok(Exchange::HKEX),
             "NASDAQ" => Ok(Exchange::NASDAQ),
+            "BSE" => Ok(Exchange::BSE),
+            "NYSE" => Ok(Exchange::NYSE),
+            "AMEX" => Ok(Exchange::AMEX),
             _ => Err(ExchangeError::InvalidCode(s.to_string())),
         }
     }
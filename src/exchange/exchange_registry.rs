@@ -0,0 +1,307 @@
+use crate::error::AppError;
+use crate::exchange::exchange_dao;
+use crate::exchange::exchange_info_model::Model;
+use crate::exchange::exchange_model::Exchange;
+use crate::stock::stock_dao;
+use chrono_tz::Tz;
+use lazy_static::lazy_static;
+use std::error::Error;
+use std::str::FromStr;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard};
+use tracing::{info, warn};
+
+/// 内置交易所的元数据，顺序与 `Exchange::VALUES` 一致
+///
+/// 服务启动时写入 `exchange` 表中缺少的内置交易所，之后以表中的数据为准。
+/// 依次为交易所、名称、后缀、内部代码、时区、货币、国家或地区、交易日历来源。
+const BUILT_IN_EXCHANGES: [(Exchange, &str, &str, u32, Tz, &str, &str, &str); 7] = [
+    (
+        Exchange::SSE,
+        "上海证券交易所",
+        ".SH",
+        10,
+        Tz::Asia__Chongqing,
+        "CNY",
+        "CN",
+        "szse",
+    ),
+    (
+        Exchange::SZSE,
+        "深圳证券交易所",
+        ".SZ",
+        20,
+        Tz::Asia__Chongqing,
+        "CNY",
+        "CN",
+        "szse",
+    ),
+    (
+        Exchange::HKEX,
+        "香港交易所",
+        ".HK",
+        30,
+        Tz::Asia__Hong_Kong,
+        "HKD",
+        "HK",
+        "gov_hk",
+    ),
+    (
+        Exchange::NASDAQ,
+        "纳斯达克交易所",
+        ".NS",
+        40,
+        Tz::America__New_York,
+        "USD",
+        "US",
+        "nasdaq",
+    ),
+    (
+        Exchange::BSE,
+        "北京证券交易所",
+        ".BJ",
+        50,
+        Tz::Asia__Chongqing,
+        "CNY",
+        "CN",
+        "szse",
+    ),
+    (
+        Exchange::NYSE,
+        "纽约证券交易所",
+        ".N",
+        60,
+        Tz::America__New_York,
+        "USD",
+        "US",
+        "nasdaq",
+    ),
+    (
+        Exchange::AMEX,
+        "美国证券交易所",
+        ".A",
+        70,
+        Tz::America__New_York,
+        "USD",
+        "US",
+        "nasdaq",
+    ),
+];
+
+/// 有上游接口的交易所的元数据，时区在加载时解析
+#[derive(Debug, Clone)]
+pub struct ExchangeMeta {
+    /// 股票代码后缀
+    pub suffix: String,
+    /// 内部代码
+    pub int_code: u32,
+    /// 时区
+    pub time_zone: Tz,
+    /// 计价货币
+    pub currency: String,
+    /// 国家或地区代码
+    pub country: String,
+    /// 交易日历来源
+    pub calendar_source: String,
+    /// 是否参与同步
+    pub enabled: bool,
+}
+
+impl ExchangeMeta {
+    /// 由表中的记录创建元数据，时区无效时返回错误
+    fn from_model(model: &Model) -> Result<Self, AppError> {
+        let time_zone = Tz::from_str(&model.time_zone).map_err(|_| {
+            AppError::InvalidParam(format!(
+                "Invalid time zone {} of exchange {}",
+                model.time_zone, model.code
+            ))
+        })?;
+        Ok(ExchangeMeta {
+            suffix: model.suffix.clone(),
+            int_code: model.int_code,
+            time_zone,
+            currency: model.currency.clone(),
+            country: model.country.clone(),
+            calendar_source: model.calendar_source.clone(),
+            enabled: model.enabled,
+        })
+    }
+}
+
+/// 交易所注册表
+struct Registry {
+    /// 全部交易所的元数据，按内部代码升序
+    exchanges: Vec<Model>,
+    /// 有上游接口的交易所的元数据，下标为 `Exchange::index`
+    built_in: [ExchangeMeta; Exchange::VALUES.len()],
+}
+
+lazy_static! {
+    /// 交易所注册表，启动前和测试中使用内置的元数据，启动时由 `exchange` 表覆盖
+    static ref REGISTRY: RwLock<Registry> = RwLock::new(Registry {
+        exchanges: built_in_models(),
+        built_in: std::array::from_fn(built_in_meta),
+    });
+}
+
+fn built_in_models() -> Vec<Model> {
+    BUILT_IN_EXCHANGES
+        .iter()
+        .map(
+            |(exchange, name, suffix, int_code, time_zone, currency, country, calendar_source)| {
+                Model {
+                    code: exchange.to_string(),
+                    name: name.to_string(),
+                    suffix: suffix.to_string(),
+                    int_code: *int_code,
+                    time_zone: time_zone.name().to_string(),
+                    currency: currency.to_string(),
+                    country: country.to_string(),
+                    calendar_source: calendar_source.to_string(),
+                    enabled: true,
+                }
+            },
+        )
+        .collect()
+}
+
+fn built_in_meta(index: usize) -> ExchangeMeta {
+    let (_, _, suffix, int_code, time_zone, currency, country, calendar_source) =
+        BUILT_IN_EXCHANGES[index];
+    ExchangeMeta {
+        suffix: suffix.to_string(),
+        int_code,
+        time_zone,
+        currency: currency.to_string(),
+        country: country.to_string(),
+        calendar_source: calendar_source.to_string(),
+        enabled: true,
+    }
+}
+
+/// 由 `exchange` 表重新加载交易所元数据，返回加载的交易所数量
+///
+/// 表中缺少的内置交易所先写入表中；时区无效，或已有股票的交易所修改了后缀时返回错误，
+/// 保留原有的元数据。已入库的股票代码带有原来的后缀，修改后缀需要先迁移股票代码。
+/// 表中没有上游接口的交易所只出现在交易所列表中，不参与同步。
+pub async fn reload() -> Result<usize, Box<dyn Error>> {
+    let mut exchanges = exchange_dao::find_all().await?;
+    let missing = built_in_models()
+        .into_iter()
+        .filter(|model| !exchanges.iter().any(|e| e.code == model.code))
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        exchange_dao::insert_exchanges(&missing).await?;
+        for exchange in &missing {
+            info!("Add built-in exchange {} to table", exchange.code);
+        }
+        exchanges.extend(missing);
+        exchanges.sort_by_key(|exchange| exchange.int_code);
+    }
+
+    let mut built_in: [ExchangeMeta; Exchange::VALUES.len()] = std::array::from_fn(built_in_meta);
+    for exchange in &exchanges {
+        let meta = ExchangeMeta::from_model(exchange)?;
+        let suffix_changed =
+            get(&exchange.code).is_some_and(|previous| previous.suffix != exchange.suffix);
+        if suffix_changed
+            && stock_dao::exists_stock_without_suffix(&exchange.code, &exchange.suffix).await?
+        {
+            return Err(AppError::InvalidParam(format!(
+                "Suffix {} of exchange {} does not match its stored stock codes",
+                exchange.suffix, exchange.code
+            ))
+            .into());
+        }
+        match Exchange::from_str(&exchange.code) {
+            Ok(code) => built_in[code.index()] = meta,
+            Err(_) => warn!("Exchange {} has no upstream api, skip sync", exchange.code),
+        }
+    }
+
+    let count = exchanges.len();
+    *REGISTRY.write().unwrap_or_else(PoisonError::into_inner) = Registry {
+        exchanges,
+        built_in,
+    };
+    info!("Reload exchange registry, {} exchanges", count);
+    Ok(count)
+}
+
+/// 读取注册表，持有写锁的线程崩溃时注册表仍保持完整，忽略锁中毒
+fn registry() -> RwLockReadGuard<'static, Registry> {
+    REGISTRY.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// 全部交易所的元数据
+pub fn all() -> Vec<Model> {
+    registry().exchanges.clone()
+}
+
+/// 按代码查询交易所的元数据，忽略大小写
+pub fn get(code: &str) -> Option<Model> {
+    registry()
+        .exchanges
+        .iter()
+        .find(|exchange| exchange.code.eq_ignore_ascii_case(code))
+        .cloned()
+}
+
+/// 读取有上游接口的交易所的元数据，只复制需要的字段
+pub fn with_meta<T>(exchange: Exchange, f: impl FnOnce(&ExchangeMeta) -> T) -> T {
+    f(&registry().built_in[exchange.index()])
+}
+
+/// 启用同步且有上游接口的交易所
+pub fn enabled() -> Vec<Exchange> {
+    let registry = registry();
+    Exchange::VALUES
+        .into_iter()
+        .filter(|exchange| registry.built_in[exchange.index()].enabled)
+        .collect()
+}
+
+/// 更新交易所是否参与同步并重新加载，无需重启服务
+pub async fn set_enabled(code: &str, enabled: bool) -> Result<Model, Box<dyn Error>> {
+    let exchange =
+        get(code).ok_or_else(|| AppError::NotFound(format!("Exchange {} not found", code)))?;
+    exchange_dao::update_enabled(&exchange.code, enabled).await?;
+    reload().await?;
+
+    let exchange = get(&exchange.code)
+        .ok_or_else(|| AppError::NotFound(format!("Exchange {} not found", code)))?;
+    info!("Exchange {} enabled = {}", exchange.code, enabled);
+    Ok(exchange)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_built_in_exchanges_follow_values() {
+        for (index, exchange) in Exchange::VALUES.into_iter().enumerate() {
+            assert_eq!(exchange.index(), index);
+            assert_eq!(BUILT_IN_EXCHANGES[index].0, exchange);
+        }
+    }
+
+    #[test]
+    fn test_built_in_meta() {
+        assert_eq!(Exchange::NYSE.stock_code_suffix(), ".N");
+        assert_eq!(Exchange::HKEX.time_zone(), Tz::Asia__Hong_Kong);
+        assert!(Exchange::AMEX.is_us());
+        assert!(!Exchange::BSE.is_us());
+    }
+
+    #[test]
+    fn test_poisoned_registry() {
+        let _ = std::thread::spawn(|| {
+            let _registry = REGISTRY.write().unwrap_or_else(PoisonError::into_inner);
+            panic!("poison registry");
+        })
+        .join();
+        assert!(REGISTRY.is_poisoned());
+        assert_eq!(Exchange::SSE.stock_code_suffix(), ".SH");
+        assert!(get("sse").is_some());
+    }
+}
@@ -1,5 +1,8 @@
 mod exchange_ctrl;
+mod exchange_dao;
+pub mod exchange_info_model;
 mod exchange_job;
 pub mod exchange_model;
+pub mod exchange_registry;
 pub mod exchange_svc;
 mod market_time;
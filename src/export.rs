@@ -0,0 +1,665 @@
+use crate::error;
+use crate::error::AppError;
+use crate::stock::stock_price_api::{StockDailyPrice, StockMinutePrice};
+use application_web::response::RespBody;
+use arrow_array::{
+    ArrayRef, Date32Array, Float64Array, RecordBatch, StringArray, TimestampSecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use axum::body::{Body, Bytes};
+use axum::http::header::{ACCEPT, CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::http::{HeaderMap, HeaderValue};
+use axum::response::{IntoResponse, Response};
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::{NaiveDate, NaiveDateTime};
+use futures::{Stream, StreamExt, stream};
+use parquet::arrow::ArrowWriter;
+use parquet::file::metadata::KeyValue;
+use rust_xlsxwriter::{Format, Workbook};
+use std::error::Error;
+use std::sync::Arc;
+
+/// 导出文件的列
+const COLUMNS: [&str; 8] = [
+    "code", "time", "open", "high", "low", "close", "volume", "amount",
+];
+
+/// XLSX 单个工作表最多的数据行数，不含表头
+const XLSX_MAX_ROWS: usize = 1_048_575;
+
+/// 逐批导出时每批查询的行数
+pub const EXPORT_BATCH_ROWS: u64 = 1000;
+
+/// XLSX 导出时返回查询失败的股票代码的响应头
+pub const FAILED_CODES_HEADER: &str = "x-export-failed-codes";
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Xlsx,
+    Parquet,
+}
+
+impl ExportFormat {
+    /// 按 `format` 参数或 `Accept` 请求头选择导出格式，`format` 参数优先
+    ///
+    /// `Accept` 请求头按 q 值选择，q 值相同时取靠前的类型，q 为 0 的类型不可接受；
+    /// 都没有指定导出格式，或 `application/json` 的 q 值更高时返回 `None`，接口按 JSON 返回。
+    pub fn from_request(
+        format: Option<&str>,
+        headers: &HeaderMap,
+    ) -> Result<Option<Self>, AppError> {
+        if let Some(format) = format.filter(|format| !format.is_empty()) {
+            return match format.to_lowercase().as_str() {
+                "json" => Ok(None),
+                "csv" => Ok(Some(ExportFormat::Csv)),
+                "xlsx" => Ok(Some(ExportFormat::Xlsx)),
+                "parquet" => Ok(Some(ExportFormat::Parquet)),
+                _ => Err(AppError::InvalidParam(format!(
+                    "Invalid export format: {}",
+                    format
+                ))),
+            };
+        }
+        let accept = headers
+            .get(ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .unwrap_or_default();
+        let mut best: Option<(f32, Option<ExportFormat>)> = None;
+        for media_range in accept.split(',') {
+            let mut params = media_range.split(';');
+            let media_type = params.next().unwrap_or_default().trim();
+            let format = if media_type.eq_ignore_ascii_case("application/json") {
+                None
+            } else {
+                match [ExportFormat::Csv, ExportFormat::Xlsx, ExportFormat::Parquet]
+                    .into_iter()
+                    .find(|format| format.content_type().eq_ignore_ascii_case(media_type))
+                {
+                    Some(format) => Some(format),
+                    None => continue,
+                }
+            };
+            let quality = params
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .and_then(|quality| quality.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality > 0.0 && best.is_none_or(|(best, _)| quality > best) {
+                best = Some((quality, format));
+            }
+        }
+        Ok(best.and_then(|(_, format)| format))
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+/// 价格数据的周期，决定时间列导出为日期还是日期时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceKind {
+    Daily,
+    Minute,
+}
+
+impl PriceKind {
+    fn time_format(&self) -> &'static str {
+        match self {
+            PriceKind::Daily => "%Y-%m-%d",
+            PriceKind::Minute => "%Y-%m-%d %H:%M:%S",
+        }
+    }
+
+    fn excel_format(&self) -> &'static str {
+        match self {
+            PriceKind::Daily => "yyyy-mm-dd",
+            PriceKind::Minute => "yyyy-mm-dd hh:mm:ss",
+        }
+    }
+}
+
+/// 导出的一行价格数据
+#[derive(Debug, Clone)]
+pub struct PriceRow {
+    pub code: String,
+    pub time: NaiveDateTime,
+    pub open: BigDecimal,
+    pub high: BigDecimal,
+    pub low: BigDecimal,
+    pub close: BigDecimal,
+    pub volume: Option<BigDecimal>,
+    pub amount: Option<BigDecimal>,
+}
+
+impl PriceRow {
+    pub fn from_daily(code: &str, price: &StockDailyPrice) -> Result<Self, Box<dyn Error>> {
+        Ok(PriceRow {
+            code: code.to_string(),
+            time: parse_time(price.time)?,
+            open: price.open.clone(),
+            high: price.high.clone(),
+            low: price.low.clone(),
+            close: price.close.clone(),
+            volume: price.volume.clone(),
+            amount: price.amount.clone(),
+        })
+    }
+
+    pub fn from_minute(code: &str, price: &StockMinutePrice) -> Result<Self, Box<dyn Error>> {
+        Ok(PriceRow {
+            code: code.to_string(),
+            time: parse_time(price.time)?,
+            open: price.open.clone(),
+            high: price.high.clone(),
+            low: price.low.clone(),
+            close: price.close.clone(),
+            volume: price.volume.clone(),
+            amount: price.amount.clone(),
+        })
+    }
+}
+
+/// 解析 `yyyyMMddHHmmss` 格式的时间
+fn parse_time(time: u64) -> Result<NaiveDateTime, Box<dyn Error>> {
+    Ok(NaiveDateTime::parse_from_str(
+        &time.to_string(),
+        "%Y%m%d%H%M%S",
+    )?)
+}
+
+/// 逐批导出的一批价格数据
+#[derive(Debug, Clone)]
+pub enum PriceBatch {
+    /// 一批价格数据
+    Rows(Vec<PriceRow>),
+    /// 查询失败而跳过的股票代码
+    Failed(String),
+}
+
+impl PriceBatch {
+    /// 由一批日线创建
+    pub fn daily(code: &str, prices: &[StockDailyPrice]) -> Result<Self, AppError> {
+        prices
+            .iter()
+            .map(|price| PriceRow::from_daily(code, price))
+            .collect::<Result<Vec<_>, _>>()
+            .map(PriceBatch::Rows)
+            .map_err(|e| AppError::Internal(e.to_string()))
+    }
+}
+
+/// 导出已查询出的价格数据
+pub fn export_rows(
+    format: ExportFormat,
+    kind: PriceKind,
+    name: &str,
+    rows: Vec<PriceRow>,
+) -> Response {
+    let body = match format {
+        ExportFormat::Csv => {
+            let mut csv = csv_header();
+            csv.push_str(&csv_rows(kind, &rows));
+            Ok(csv.into_bytes())
+        }
+        ExportFormat::Xlsx => write_xlsx(kind, &rows),
+        ExportFormat::Parquet => write_parquet(kind, &rows),
+    };
+    match body {
+        Ok(body) => attachment(format, name, &[], Body::from(body)),
+        Err(e) => error_response(e),
+    }
+}
+
+/// 导出逐批查询的价格数据
+///
+/// CSV 每查询出一批就写出一批；Parquet 每批写为一个行组，行组写完即发送，文件尾部的元数据
+/// 最后发送。两者都不需要等全部数据查询完成，响应头发送后出错时直接中断响应。
+/// XLSX 无法逐批写出，在内存中生成完整的文件后再发送，最多 `XLSX_MAX_ROWS` 行。
+///
+/// 查询失败而跳过的股票代码以逗号分隔：CSV 写在最后一行 `# failed: ...`，Parquet 写在文件的
+/// `failed_codes` 元数据中，XLSX 通过 `x-export-failed-codes` 响应头返回。
+pub async fn export_batches<S>(
+    format: ExportFormat,
+    kind: PriceKind,
+    name: &str,
+    batches: S,
+) -> Response
+where
+    S: Stream<Item = Result<PriceBatch, AppError>> + Send + 'static,
+{
+    match format {
+        ExportFormat::Csv => attachment(
+            format,
+            name,
+            &[],
+            Body::from_stream(csv_stream(kind, batches)),
+        ),
+        ExportFormat::Parquet => match parquet_stream(kind, batches) {
+            Ok(stream) => attachment(format, name, &[], Body::from_stream(stream)),
+            Err(e) => error_response(e),
+        },
+        ExportFormat::Xlsx => {
+            let mut batches = Box::pin(batches);
+            let mut rows = Vec::new();
+            let mut failed = Vec::new();
+            while let Some(batch) = batches.next().await {
+                match batch {
+                    Ok(PriceBatch::Rows(batch)) => {
+                        rows.extend(batch);
+                        if rows.len() > XLSX_MAX_ROWS {
+                            return error_response(too_many_xlsx_rows().into());
+                        }
+                    }
+                    Ok(PriceBatch::Failed(code)) => failed.push(code),
+                    Err(e) => return error_response(e.into()),
+                }
+            }
+            match write_xlsx(kind, &rows) {
+                Ok(body) => attachment(format, name, &failed, Body::from(body)),
+                Err(e) => error_response(e),
+            }
+        }
+    }
+}
+
+/// 逐批写出 CSV，最后一行为查询失败的股票代码
+fn csv_stream<S>(
+    kind: PriceKind,
+    batches: S,
+) -> impl Stream<Item = Result<Bytes, AppError>> + Send + 'static
+where
+    S: Stream<Item = Result<PriceBatch, AppError>> + Send + 'static,
+{
+    let header = stream::once(async { Ok(Bytes::from(csv_header())) });
+    let rows = stream::unfold(
+        Some((Box::pin(batches), Vec::new())),
+        move |state| async move {
+            let (mut batches, mut failed) = state?;
+            loop {
+                match batches.next().await {
+                    Some(Ok(PriceBatch::Rows(rows))) => {
+                        let csv = Bytes::from(csv_rows(kind, &rows));
+                        return Some((Ok(csv), Some((batches, failed))));
+                    }
+                    Some(Ok(PriceBatch::Failed(code))) => failed.push(code),
+                    Some(Err(e)) => return Some((Err(e), None)),
+                    None if failed.is_empty() => return None,
+                    None => {
+                        let trailer = format!("# failed: {}\n", failed.join(","));
+                        return Some((Ok(Bytes::from(trailer)), None));
+                    }
+                }
+            }
+        },
+    );
+    header.chain(rows)
+}
+
+/// 逐批写出 Parquet，每批一个行组，查询失败的股票代码写在 `failed_codes` 元数据中
+fn parquet_stream<S>(
+    kind: PriceKind,
+    batches: S,
+) -> Result<impl Stream<Item = Result<Bytes, AppError>> + Send + 'static, Box<dyn Error>>
+where
+    S: Stream<Item = Result<PriceBatch, AppError>> + Send + 'static,
+{
+    let schema = parquet_schema(kind);
+    let writer = ArrowWriter::try_new(Vec::new(), schema.clone(), None)?;
+    let parquet_error = |e: parquet::errors::ParquetError| AppError::Internal(e.to_string());
+    Ok(stream::unfold(
+        Some((Box::pin(batches), writer, Vec::new())),
+        move |state| {
+            let schema = schema.clone();
+            async move {
+                let (mut batches, mut writer, mut failed) = state?;
+                loop {
+                    match batches.next().await {
+                        Some(Ok(PriceBatch::Rows(rows))) => {
+                            let written = record_batch(kind, &schema, &rows)
+                                .map_err(|e| AppError::Internal(e.to_string()))
+                                .and_then(|batch| writer.write(&batch).map_err(parquet_error))
+                                .and_then(|_| writer.flush().map_err(parquet_error));
+                            if let Err(e) = written {
+                                return Some((Err(e), None));
+                            }
+                            // 已写完的行组立即发送，未写完的部分留在写入器中
+                            let bytes = std::mem::take(writer.inner_mut());
+                            if !bytes.is_empty() {
+                                return Some((
+                                    Ok(Bytes::from(bytes)),
+                                    Some((batches, writer, failed)),
+                                ));
+                            }
+                        }
+                        Some(Ok(PriceBatch::Failed(code))) => failed.push(code),
+                        Some(Err(e)) => return Some((Err(e), None)),
+                        None => {
+                            if !failed.is_empty() {
+                                writer.append_key_value_metadata(KeyValue::new(
+                                    "failed_codes".to_string(),
+                                    failed.join(","),
+                                ));
+                            }
+                            let footer = writer.into_inner().map_err(parquet_error);
+                            return Some((footer.map(Bytes::from), None));
+                        }
+                    }
+                }
+            }
+        },
+    ))
+}
+
+fn attachment(format: ExportFormat, name: &str, failed: &[String], body: Body) -> Response {
+    let disposition = format!("attachment; filename=\"{}.{}\"", name, format.extension());
+    let mut response = (
+        [
+            (CONTENT_TYPE, format.content_type().to_string()),
+            (CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response();
+    let failed = HeaderValue::from_str(&failed.join(","))
+        .ok()
+        .filter(|_| !failed.is_empty());
+    if let Some(failed) = failed {
+        response.headers_mut().insert(FAILED_CODES_HEADER, failed);
+    }
+    response
+}
+
+fn error_response(e: Box<dyn Error>) -> Response {
+    let r: Result<(), Box<dyn Error>> = Err(e);
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+fn csv_header() -> String {
+    format!("{}\n", COLUMNS.join(","))
+}
+
+fn csv_rows(kind: PriceKind, rows: &[PriceRow]) -> String {
+    let optional = |value: &Option<BigDecimal>| {
+        value
+            .as_ref()
+            .map(|value| value.to_string())
+            .unwrap_or_default()
+    };
+    let mut csv = String::new();
+    for row in rows {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            row.code,
+            row.time.format(kind.time_format()),
+            row.open,
+            row.high,
+            row.low,
+            row.close,
+            optional(&row.volume),
+            optional(&row.amount),
+        ));
+    }
+    csv
+}
+
+fn too_many_xlsx_rows() -> AppError {
+    AppError::InvalidParam(format!(
+        "Too many rows for xlsx, at most {} rows, use csv or parquet instead",
+        XLSX_MAX_ROWS
+    ))
+}
+
+fn write_xlsx(kind: PriceKind, rows: &[PriceRow]) -> Result<Vec<u8>, Box<dyn Error>> {
+    if rows.len() > XLSX_MAX_ROWS {
+        return Err(too_many_xlsx_rows().into());
+    }
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    let time_format = Format::new().set_num_format(kind.excel_format());
+    for (col, name) in COLUMNS.iter().enumerate() {
+        worksheet.write_string(0, col as u16, *name)?;
+    }
+    for (i, row) in rows.iter().enumerate() {
+        let r = i as u32 + 1;
+        worksheet.write_string(r, 0, &row.code)?;
+        worksheet.write_datetime_with_format(r, 1, &row.time, &time_format)?;
+        let values = [
+            Some(&row.open),
+            Some(&row.high),
+            Some(&row.low),
+            Some(&row.close),
+            row.volume.as_ref(),
+            row.amount.as_ref(),
+        ];
+        for (col, value) in values.iter().enumerate() {
+            if let Some(value) = value.and_then(|value| value.to_f64()) {
+                worksheet.write_number(r, col as u16 + 2, value)?;
+            }
+        }
+    }
+    Ok(workbook.save_to_buffer()?)
+}
+
+fn write_parquet(kind: PriceKind, rows: &[PriceRow]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let schema = parquet_schema(kind);
+    let batch = record_batch(kind, &schema, rows)?;
+    let mut buffer = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut buffer, schema, None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(buffer)
+}
+
+fn parquet_schema(kind: PriceKind) -> Arc<Schema> {
+    let time = match kind {
+        PriceKind::Daily => DataType::Date32,
+        // 分钟线时间为交易所当地时间，不带时区
+        PriceKind::Minute => DataType::Timestamp(TimeUnit::Second, None),
+    };
+    Arc::new(Schema::new(vec![
+        Field::new(COLUMNS[0], DataType::Utf8, false),
+        Field::new(COLUMNS[1], time, false),
+        Field::new(COLUMNS[2], DataType::Float64, true),
+        Field::new(COLUMNS[3], DataType::Float64, true),
+        Field::new(COLUMNS[4], DataType::Float64, true),
+        Field::new(COLUMNS[5], DataType::Float64, true),
+        Field::new(COLUMNS[6], DataType::Float64, true),
+        Field::new(COLUMNS[7], DataType::Float64, true),
+    ]))
+}
+
+fn record_batch(
+    kind: PriceKind,
+    schema: &Arc<Schema>,
+    rows: &[PriceRow],
+) -> Result<RecordBatch, Box<dyn Error>> {
+    let time: ArrayRef = match kind {
+        PriceKind::Daily => {
+            let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).ok_or("Invalid epoch")?;
+            Arc::new(Date32Array::from(
+                rows.iter()
+                    .map(|row| (row.time.date() - epoch).num_days() as i32)
+                    .collect::<Vec<_>>(),
+            ))
+        }
+        PriceKind::Minute => Arc::new(TimestampSecondArray::from(
+            rows.iter()
+                .map(|row| row.time.and_utc().timestamp())
+                .collect::<Vec<_>>(),
+        )),
+    };
+    let decimals = |value: fn(&PriceRow) -> Option<&BigDecimal>| -> ArrayRef {
+        Arc::new(Float64Array::from(
+            rows.iter()
+                .map(|row| value(row).and_then(|value| value.to_f64()))
+                .collect::<Vec<_>>(),
+        ))
+    };
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(
+            rows.iter().map(|row| row.code.as_str()),
+        )),
+        time,
+        decimals(|row| Some(&row.open)),
+        decimals(|row| Some(&row.high)),
+        decimals(|row| Some(&row.low)),
+        decimals(|row| Some(&row.close)),
+        decimals(|row| row.volume.as_ref()),
+        decimals(|row| row.amount.as_ref()),
+    ];
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use std::str::FromStr;
+
+    fn headers(accept: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, accept.parse().unwrap());
+        headers
+    }
+
+    fn row(time: &str, volume: Option<&str>) -> PriceRow {
+        let decimal = |value: &str| BigDecimal::from_str(value).unwrap();
+        PriceRow {
+            code: "600000.SH".to_string(),
+            time: NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S").unwrap(),
+            open: decimal("10.1"),
+            high: decimal("10.5"),
+            low: decimal("10"),
+            close: decimal("10.20"),
+            volume: volume.map(decimal),
+            amount: None,
+        }
+    }
+
+    #[test]
+    fn test_format_param_overrides_accept() {
+        let accept = headers("text/csv");
+        assert_eq!(
+            ExportFormat::from_request(Some("XLSX"), &accept).unwrap(),
+            Some(ExportFormat::Xlsx)
+        );
+        assert_eq!(
+            ExportFormat::from_request(Some("json"), &accept).unwrap(),
+            None
+        );
+        assert_eq!(
+            ExportFormat::from_request(Some(""), &accept).unwrap(),
+            Some(ExportFormat::Csv)
+        );
+        assert_eq!(
+            ExportFormat::from_request(None, &HeaderMap::new()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_accept_quality() {
+        let format = |accept: &str| ExportFormat::from_request(None, &headers(accept)).unwrap();
+        assert_eq!(
+            format("text/csv;q=0.5, application/vnd.apache.parquet;q=0.8"),
+            Some(ExportFormat::Parquet)
+        );
+        assert_eq!(
+            format("text/csv; charset=utf-8, application/vnd.apache.parquet"),
+            Some(ExportFormat::Csv)
+        );
+        assert_eq!(format("text/csv;q=0, text/html"), None);
+        assert_eq!(format("application/json, text/csv;q=0.9"), None);
+        assert_eq!(format("text/html, */*;q=0.8"), None);
+    }
+
+    #[test]
+    fn test_unknown_format() {
+        let e = ExportFormat::from_request(Some("xml"), &HeaderMap::new()).unwrap_err();
+        assert!(matches!(e, AppError::InvalidParam(_)));
+    }
+
+    #[test]
+    fn test_csv_rows() {
+        let rows = vec![
+            row("2024-01-02 09:30:00", Some("1000")),
+            row("2024-01-03 09:30:00", None),
+        ];
+        assert_eq!(
+            csv_header(),
+            "code,time,open,high,low,close,volume,amount\n"
+        );
+        assert_eq!(
+            csv_rows(PriceKind::Daily, &rows),
+            "600000.SH,2024-01-02,10.1,10.5,10,10.20,1000,\n\
+             600000.SH,2024-01-03,10.1,10.5,10,10.20,,\n"
+        );
+        assert_eq!(
+            csv_rows(PriceKind::Minute, &rows[..1]),
+            "600000.SH,2024-01-02 09:30:00,10.1,10.5,10,10.20,1000,\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_stream_failed_codes() {
+        let batches = stream::iter(vec![
+            Ok(PriceBatch::Rows(vec![row("2024-01-02 09:30:00", None)])),
+            Ok(PriceBatch::Failed("600001.SH".to_string())),
+            Ok(PriceBatch::Failed("600002.SH".to_string())),
+        ]);
+        let csv = block_on(csv_stream(PriceKind::Daily, batches).collect::<Vec<_>>())
+            .into_iter()
+            .map(|bytes| String::from_utf8(bytes.unwrap().to_vec()).unwrap())
+            .collect::<String>();
+        assert_eq!(
+            csv,
+            "code,time,open,high,low,close,volume,amount\n\
+             600000.SH,2024-01-02,10.1,10.5,10,10.20,,\n\
+             # failed: 600001.SH,600002.SH\n"
+        );
+    }
+
+    #[test]
+    fn test_parquet_stream_row_groups() {
+        let batches = stream::iter(vec![
+            Ok(PriceBatch::Rows(vec![row("2024-01-02 09:30:00", None); 2])),
+            Ok(PriceBatch::Failed("600001.SH".to_string())),
+            Ok(PriceBatch::Rows(vec![row(
+                "2024-01-03 09:30:00",
+                Some("1000"),
+            )])),
+        ]);
+        let stream = parquet_stream(PriceKind::Daily, batches).unwrap();
+        let file = block_on(stream.collect::<Vec<_>>())
+            .into_iter()
+            .flat_map(|bytes| bytes.unwrap().to_vec())
+            .collect::<Vec<_>>();
+        let reader = SerializedFileReader::new(Bytes::from(file)).unwrap();
+        let metadata = reader.metadata();
+        assert_eq!(metadata.num_row_groups(), 2);
+        assert_eq!(metadata.file_metadata().num_rows(), 3);
+        let failed = metadata
+            .file_metadata()
+            .key_value_metadata()
+            .and_then(|kvs| kvs.iter().find(|kv| kv.key == "failed_codes"))
+            .and_then(|kv| kv.value.clone());
+        assert_eq!(failed.as_deref(), Some("600001.SH"));
+    }
+}
@@ -1,10 +1,11 @@
+use crate::error;
+use crate::error::AppError;
 use crate::exchange::exchange_model::Exchange;
 use crate::stock::stock_model::{Model as Stock, Model, StockKind};
 use crate::token::token_svc;
-use application_context::context::application_context::APPLICATION_CONTEXT;
-use application_core::env::property_resolver::PropertyResolver;
+use crate::upstream;
 use async_trait::async_trait;
-use calamine::{Reader, Xlsx, open_workbook};
+use calamine::{Data, Range, Reader, Xlsx, open_workbook};
 use chrono::Local;
 use rand::{RngExt, rng};
 use serde_json::Value;
@@ -26,13 +27,18 @@ impl FundApi for Exchange {
             Exchange::SZSE => get_funds_from_szse(self).await,
             Exchange::HKEX => get_funds_from_hkex(self).await,
             Exchange::NASDAQ => get_funds_from_nasdaq(self).await,
+            // 北交所没有上市基金
+            Exchange::BSE => Ok(Vec::new()),
+            // ETF 筛选器不区分上市交易所，美股 ETF 统一归入纳斯达克
+            Exchange::NYSE | Exchange::AMEX => Ok(Vec::new()),
         }
     }
 }
 
 async fn get_funds_from_szse(exchange: &Exchange) -> Result<Vec<Model>, Box<dyn Error>> {
     let url = format!(
-        "https://www.szse.cn/api/report/ShowReport?SHOWTYPE=xlsx&CATALOGID=1105&TABKEY=tab1&random={}",
+        "{}/api/report/ShowReport?SHOWTYPE=xlsx&CATALOGID=1105&TABKEY=tab1&random={}",
+        upstream::get_url("stock.api.sz.baseurl").await?,
         rng().random::<f64>()
     );
     let dir = tempdir()?;
@@ -56,11 +62,7 @@ async fn get_funds_from_hkex_sub_category(
     exchange: &Exchange,
     sub_category: &str,
 ) -> Result<Vec<Stock>, Box<dyn Error>> {
-    let application_context = APPLICATION_CONTEXT.read().await;
-    let environment = application_context.get_environment().await;
-    let base_url = environment
-        .get_property::<String>("stock.api.hk.baseurl")
-        .unwrap();
+    let base_url = upstream::get_url("stock.api.hk.baseurl").await?;
     let token = token_svc::get_hkex_token().await;
     let timestamp = Local::now().timestamp_millis();
     let url = format!(
@@ -70,26 +72,34 @@ async fn get_funds_from_hkex_sub_category(
     let client = Request::client().await;
     let response = client.get(url).send().await?;
     let text = response.text().await?;
-    let json = crate::stock::stock_price_api::remove_jquery_wrapping_fn_call(&text);
-    let data = json.get("data").unwrap();
-    let data = data.get("stocklist").unwrap().as_array();
-    let data = data.unwrap();
+    let json = crate::stock::stock_price_api::remove_jquery_wrapping_fn_call(&text, "hkex")?;
+    Ok(parse_hkex_etp_list(&json, exchange)?)
+}
+
+/// 解析港交所交易所买卖产品列表
+fn parse_hkex_etp_list(json: &Value, exchange: &Exchange) -> Result<Vec<Stock>, AppError> {
+    let data = error::field(json, "data", "hkex")?;
+    let data = error::array_field(data, "stocklist", "hkex")?;
     let mut funds = Vec::new();
     for stock in data {
-        let code = stock.get("sym").unwrap().as_str().unwrap();
+        let code = error::str_field(stock, "sym", "hkex")?;
         funds.push(Stock {
             code: format!("{}{}", code, exchange.stock_code_suffix()),
-            name: stock.get("nm").unwrap().as_str().unwrap().to_string(),
+            name: error::str_field(stock, "nm", "hkex")?.to_string(),
             exchange: exchange.as_ref().to_string(),
             stock_type: StockKind::Fund.to_string(),
             stock_code: code.to_string(),
+            delisted_at: None,
         })
     }
     Ok(funds)
 }
 
 async fn get_funds_from_nasdaq(exchange: &Exchange) -> Result<Vec<Stock>, Box<dyn Error>> {
-    let url = "https://api.nasdaq.com/api/screener/etf?download=true&assetclass=equity".to_string();
+    let url = format!(
+        "{}/api/screener/etf?download=true&assetclass=equity",
+        upstream::get_url("stock.api.nasdaq.baseurl").await?
+    );
     let mut headers = reqwest::header::HeaderMap::new();
     headers.insert("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36".parse()?);
     headers.insert("Accept", "*/*".parse()?);
@@ -101,36 +111,38 @@ async fn get_funds_from_nasdaq(exchange: &Exchange) -> Result<Vec<Stock>, Box<dy
     match response {
         Ok(response) => {
             let json: Value = response.json().await?;
-            let data = json
-                .get("data")
-                .unwrap()
-                .get("data")
-                .unwrap()
-                .get("rows")
-                .unwrap()
-                .as_array();
-            let mut funds = Vec::new();
-            if let Some(data) = data {
-                for fund in data {
-                    let symbol = fund.get("symbol").unwrap().as_str().unwrap();
-                    funds.push(Stock {
-                        code: format!("{}{}", symbol, exchange.stock_code_suffix()),
-                        name: symbol.to_string(),
-                        exchange: exchange.as_ref().to_string(),
-                        stock_type: StockKind::Fund.to_string(),
-                        stock_code: symbol.to_string(),
-                    });
-                }
-            }
-            Ok(funds)
+            Ok(parse_nasdaq_etf_screener(&json, exchange)?)
         }
         Err(e) => Err(e.into()),
     }
 }
 
+/// 解析纳斯达克 ETF 筛选器返回的基金列表
+fn parse_nasdaq_etf_screener(json: &Value, exchange: &Exchange) -> Result<Vec<Stock>, AppError> {
+    let data = error::field(json, "data", "nasdaq")?;
+    let data = error::field(data, "data", "nasdaq")?;
+    let data = error::field(data, "rows", "nasdaq")?.as_array();
+    let mut funds = Vec::new();
+    if let Some(data) = data {
+        for fund in data {
+            let symbol = error::str_field(fund, "symbol", "nasdaq")?;
+            funds.push(Stock {
+                code: format!("{}{}", symbol, exchange.stock_code_suffix()),
+                name: symbol.to_string(),
+                exchange: exchange.as_ref().to_string(),
+                stock_type: StockKind::Fund.to_string(),
+                stock_code: symbol.to_string(),
+                delisted_at: None,
+            });
+        }
+    }
+    Ok(funds)
+}
+
 async fn get_funds_from_sse(exchange: &Exchange) -> Result<Vec<Stock>, Box<dyn Error>> {
     let url = format!(
-        "https://query.sse.com.cn/commonSoaQuery.do?sqlId=FUND_LIST&fundType=00&_={}",
+        "{}/commonSoaQuery.do?sqlId=FUND_LIST&fundType=00&_={}",
+        upstream::get_url("stock.api.sh.query").await?,
         rng().random::<f64>()
     );
     let mut headers = reqwest::header::HeaderMap::new();
@@ -143,40 +155,33 @@ async fn get_funds_from_sse(exchange: &Exchange) -> Result<Vec<Stock>, Box<dyn E
     match response {
         Ok(response) => {
             let json: Value = response.json().await?;
-            let data = json
-                .get("pageHelp")
-                .unwrap()
-                .get("data")
-                .unwrap()
-                .as_array();
-            let mut funds = Vec::new();
-            if let Some(data) = data {
-                for fund in data {
-                    let stock = Stock {
-                        code: format!(
-                            "{}{}",
-                            fund.get("fundCode").unwrap().as_str().unwrap().to_string(),
-                            exchange.stock_code_suffix()
-                        ),
-                        name: fund
-                            .get("secNameFull")
-                            .unwrap()
-                            .as_str()
-                            .unwrap()
-                            .to_string(),
-                        exchange: exchange.as_ref().to_string(),
-                        stock_type: "Fund".to_string(),
-                        stock_code: fund.get("fundCode").unwrap().as_str().unwrap().to_string(),
-                    };
-                    funds.push(stock);
-                }
-            }
-            Ok(funds)
+            Ok(parse_sse_fund_list(&json, exchange)?)
         }
         Err(e) => Err(e.into()),
     }
 }
 
+/// 解析上交所基金列表
+fn parse_sse_fund_list(json: &Value, exchange: &Exchange) -> Result<Vec<Stock>, AppError> {
+    let data = error::field(json, "pageHelp", "sse")?;
+    let data = error::field(data, "data", "sse")?.as_array();
+    let mut funds = Vec::new();
+    if let Some(data) = data {
+        for fund in data {
+            let fund_code = error::str_field(fund, "fundCode", "sse")?;
+            funds.push(Stock {
+                code: format!("{}{}", fund_code, exchange.stock_code_suffix()),
+                name: error::str_field(fund, "secNameFull", "sse")?.to_string(),
+                exchange: exchange.as_ref().to_string(),
+                stock_type: "Fund".to_string(),
+                stock_code: fund_code.to_string(),
+                delisted_at: None,
+            });
+        }
+    }
+    Ok(funds)
+}
+
 fn read_funds_from_sz_excel(
     path: &Path,
     exchange: &Exchange,
@@ -185,26 +190,98 @@ fn read_funds_from_sz_excel(
 
     let mut stocks = Vec::new();
     if let Ok(r) = excel_xlsx.worksheet_range("基金列表") {
-        for row in r.rows() {
-            if row[0] == "基金代码" {
-                // 跳过标题行
-                continue;
-            }
-            if row[2] != "ETF" {
-                continue;
-            }
-            if row[3] == "货币市场基金" || row[3] == "债券基金" {
-                continue;
-            }
-            stocks.push(Stock {
-                code: format!("{}{}", row[0], exchange.stock_code_suffix()),
-                name: row[1].to_string(),
-                exchange: exchange.as_ref().to_string(),
-                stock_type: "Fund".to_string(),
-                stock_code: row[0].to_string(),
-            });
-        }
+        stocks = read_funds_from_range(&r, exchange);
     }
 
     Ok(stocks)
 }
+
+/// 读取深交所基金列表表格，只保留非货币、非债券的 ETF
+fn read_funds_from_range(range: &Range<Data>, exchange: &Exchange) -> Vec<Stock> {
+    let mut stocks = Vec::new();
+    for row in range.rows() {
+        if row[0] == "基金代码" {
+            // 跳过标题行
+            continue;
+        }
+        if row[2] != "ETF" {
+            continue;
+        }
+        if row[3] == "货币市场基金" || row[3] == "债券基金" {
+            continue;
+        }
+        stocks.push(Stock {
+            code: format!("{}{}", row[0], exchange.stock_code_suffix()),
+            name: row[1].to_string(),
+            exchange: exchange.as_ref().to_string(),
+            stock_type: "Fund".to_string(),
+            stock_code: row[0].to_string(),
+            delisted_at: None,
+        });
+    }
+    stocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stock::stock_price_api::remove_jquery_wrapping_fn_call;
+
+    fn codes(funds: &[Stock]) -> Vec<(&str, &str)> {
+        funds
+            .iter()
+            .map(|f| (f.code.as_str(), f.name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_sse_fund_list() {
+        let json =
+            serde_json::from_str(include_str!("../../tests/fixtures/sse/fund_list.json")).unwrap();
+        let funds = parse_sse_fund_list(&json, &Exchange::SSE).unwrap();
+        assert_eq!(
+            codes(&funds),
+            vec![
+                ("510050.SH", "上证50ETF华夏"),
+                ("510300.SH", "沪深300ETF华泰柏瑞")
+            ]
+        );
+        assert_eq!(funds[0].stock_type, "Fund");
+    }
+
+    #[test]
+    fn test_read_funds_from_sz_excel() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/szse/fund_list.xlsx"
+        );
+        let funds = read_funds_from_sz_excel(Path::new(path), &Exchange::SZSE).unwrap();
+        // 只保留非货币、非债券的 ETF
+        assert_eq!(
+            codes(&funds),
+            vec![("159915.SZ", "创业板ETF"), ("159920.SZ", "恒生ETF")]
+        );
+    }
+
+    #[test]
+    fn test_parse_hkex_etp_list() {
+        let text = include_str!("../../tests/fixtures/hkex/etp_filter.jsonp");
+        let json = remove_jquery_wrapping_fn_call(text, "hkex").unwrap();
+        let funds = parse_hkex_etp_list(&json, &Exchange::HKEX).unwrap();
+        assert_eq!(
+            codes(&funds),
+            vec![("2800.HK", "盈富基金"), ("3033.HK", "南方恒生科技")]
+        );
+        assert_eq!(funds[0].stock_code, "2800");
+    }
+
+    #[test]
+    fn test_parse_nasdaq_etf_screener() {
+        let json = serde_json::from_str(include_str!(
+            "../../tests/fixtures/nasdaq/etf_screener.json"
+        ))
+        .unwrap();
+        let funds = parse_nasdaq_etf_screener(&json, &Exchange::NASDAQ).unwrap();
+        assert_eq!(codes(&funds), vec![("QQQ.NS", "QQQ"), ("SPY.NS", "SPY")]);
+    }
+}
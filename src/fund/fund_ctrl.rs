@@ -1,3 +1,4 @@
+use crate::error;
 use crate::fund::fund_svc;
 use application_web::response::RespBody;
 use application_web_macros::get;
@@ -17,10 +18,10 @@ use axum::response::IntoResponse;
 ///
 /// # Note
 /// - 使用`fund_svc::find_by_exchange`异步函数查询资金信息，该函数根据交易所名称查询并返回资金记录
-/// - 最后，使用`RespBody::result(&r)`来构建响应体，它根据查询结果生成适当的HTTP响应
+/// - 最后，使用`RespBody::result(&r)`来构建响应体，并由`error::with_status`按错误类型设置HTTP状态码
 #[get("/exchange/{exchange}/funds")]
 pub async fn get_exchange_funds(Path(exchange): Path<String>) -> impl IntoResponse {
     let r = fund_svc::find_by_exchange(&exchange).await;
 
-    RespBody::result(&r)
+    error::with_status(&r, RespBody::result(&r))
 }
@@ -1,4 +1,3 @@
-use crate::exchange::exchange_model::Exchange;
 use crate::fund::fund_model;
 use crate::fund::fund_model::Model;
 use application_beans::factory::bean_factory::BeanFactory;
@@ -6,8 +5,8 @@ use application_context::context::application_context::APPLICATION_CONTEXT;
 use database_mysql_seaorm::Dao;
 use sea_orm::ColumnTrait;
 use sea_orm::QueryFilter;
-use sea_orm::{DbErr, EntityTrait};
-use std::error::Error;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ConnectionTrait, DbErr, EntityTrait, IntoActiveModel};
 
 pub async fn find_all() -> Result<Vec<Model>, DbErr> {
     let application_context = APPLICATION_CONTEXT.read().await;
@@ -24,12 +23,34 @@ pub async fn find_by_exchange(exchange: &str) -> Result<Vec<Model>, DbErr> {
         .await
 }
 
-pub async fn delete_funds_by_exchange(exchange: &Exchange) -> Result<(), Box<dyn Error>> {
-    let application_context = APPLICATION_CONTEXT.read().await;
-    let dao = application_context.get_bean_factory().get::<Dao>();
+/// 批量新增或更新基金
+pub async fn upsert_funds<C: ConnectionTrait>(db: &C, funds: &[Model]) -> Result<(), DbErr> {
+    let models = funds
+        .iter()
+        .map(|fund| fund.clone().into_active_model())
+        .collect::<Vec<_>>();
+    fund_model::Entity::insert_many(models)
+        .on_conflict(
+            OnConflict::column(fund_model::Column::Code)
+                .update_columns([fund_model::Column::Name, fund_model::Column::Exchange])
+                .to_owned(),
+        )
+        .on_empty_do_nothing()
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// 删除交易所中不在最新列表里的基金
+pub async fn delete_funds_not_in<C: ConnectionTrait>(
+    db: &C,
+    exchange: &str,
+    codes: &[String],
+) -> Result<(), DbErr> {
     fund_model::Entity::delete_many()
-        .filter(fund_model::Column::Exchange.eq(exchange.as_ref()))
-        .exec(&dao.connection)
+        .filter(fund_model::Column::Exchange.eq(exchange))
+        .filter(fund_model::Column::Code.is_not_in(codes.iter().cloned()))
+        .exec(db)
         .await?;
     Ok(())
 }
@@ -0,0 +1,189 @@
+use crate::error;
+use crate::error::AppError;
+use crate::exchange::exchange_model::Exchange;
+use crate::stock::stock_model;
+use crate::stock::stock_price_api::get_akshare_base_url;
+use crate::upstream;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::error::Error;
+use tracing::info;
+use util::request::Request;
+
+/// 上游接口返回的基本面数据，上游没有的指标为空
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Fundamental {
+    pub total_shares: Option<f64>,
+    pub float_shares: Option<f64>,
+    pub market_cap: Option<f64>,
+    pub float_market_cap: Option<f64>,
+    pub pe_ttm: Option<f64>,
+    pub pb: Option<f64>,
+    pub dividend_yield: Option<f64>,
+    pub source: &'static str,
+}
+
+#[async_trait]
+pub trait FundamentalApi {
+    async fn get_fundamental(
+        &self,
+        stock: &stock_model::Model,
+    ) -> Result<Fundamental, Box<dyn Error>>;
+}
+
+#[async_trait]
+impl FundamentalApi for Exchange {
+    async fn get_fundamental(
+        &self,
+        stock: &stock_model::Model,
+    ) -> Result<Fundamental, Box<dyn Error>> {
+        match self {
+            Exchange::SSE | Exchange::SZSE | Exchange::BSE => {
+                let data = get_akshare_data("stock_value_em", &stock.stock_code).await?;
+                Ok(parse_akshare_stock_value(&data)?)
+            }
+            Exchange::HKEX => {
+                let symbol = format!("{:0>5}", stock.stock_code);
+                let data = get_akshare_data("stock_hk_financial_indicator_em", &symbol).await?;
+                Ok(parse_akshare_hk_indicator(&data)?)
+            }
+            Exchange::NASDAQ | Exchange::NYSE | Exchange::AMEX => {
+                get_fundamental_from_nasdaq(&stock.stock_code).await
+            }
+        }
+    }
+}
+
+async fn get_akshare_data(name: &str, symbol: &str) -> Result<Value, Box<dyn Error>> {
+    let url = format!(
+        "{}/api/public/{}?symbol={}",
+        get_akshare_base_url().await?,
+        name,
+        symbol
+    );
+    info!("Get fundamental from akshare: {}", url);
+    let response = Request::get_response(&url).await?;
+    Ok(response.json().await?)
+}
+
+async fn get_fundamental_from_nasdaq(symbol: &str) -> Result<Fundamental, Box<dyn Error>> {
+    let url = format!(
+        "{}/api/quote/{}/summary?assetclass=stocks",
+        upstream::get_url("stock.api.nasdaq.baseurl").await?,
+        symbol
+    );
+    info!("Get fundamental from nasdaq: {}", url);
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36".parse()?);
+    headers.insert("Accept", "*/*".parse()?);
+    let client = reqwest::Client::builder().build()?;
+    let response = client.get(&url).headers(headers).send().await?;
+    let data: Value = response.json().await?;
+    Ok(parse_nasdaq_summary(&data)?)
+}
+
+/// 读取数值，支持数字和 `$1,234.5`、`0.52%` 格式的文本，`N/A` 等无法解析的值返回 `None`
+fn to_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.replace(['$', ',', '%'], "").trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// 解析 A 股估值数据，取最近一个交易日
+fn parse_akshare_stock_value(data: &Value) -> Result<Fundamental, AppError> {
+    let row = data
+        .as_array()
+        .and_then(|rows| rows.last())
+        .ok_or_else(|| AppError::parse("akshare", "stock_value_em"))?;
+    Ok(Fundamental {
+        total_shares: to_f64(&row["总股本"]),
+        float_shares: to_f64(&row["流通股本"]),
+        market_cap: to_f64(&row["总市值"]),
+        float_market_cap: to_f64(&row["流通市值"]),
+        pe_ttm: to_f64(&row["PE(TTM)"]),
+        pb: to_f64(&row["市净率"]),
+        dividend_yield: None,
+        source: "akshare",
+    })
+}
+
+/// 解析港股财务指标，流通股本和流通市值取港股部分
+fn parse_akshare_hk_indicator(data: &Value) -> Result<Fundamental, AppError> {
+    let row = data
+        .as_array()
+        .and_then(|rows| rows.first())
+        .ok_or_else(|| AppError::parse("akshare", "stock_hk_financial_indicator_em"))?;
+    Ok(Fundamental {
+        total_shares: to_f64(&row["已发行股本(股)"]),
+        float_shares: to_f64(&row["已发行股本-H股(股)"]),
+        market_cap: to_f64(&row["总市值(港元)"]),
+        float_market_cap: to_f64(&row["港股市值(港元)"]),
+        pe_ttm: to_f64(&row["市盈率"]),
+        pb: to_f64(&row["市净率"]),
+        dividend_yield: to_f64(&row["股息率TTM(%)"]),
+        source: "akshare",
+    })
+}
+
+/// 解析纳斯达克股票摘要，摘要中没有股本和市净率
+fn parse_nasdaq_summary(data: &Value) -> Result<Fundamental, AppError> {
+    let data = error::field(data, "data", "nasdaq")?;
+    let summary = error::field(data, "summaryData", "nasdaq")?;
+    let value = |name: &str| to_f64(&summary[name]["value"]);
+    Ok(Fundamental {
+        market_cap: value("MarketCap"),
+        pe_ttm: value("PERatio"),
+        dividend_yield: value("Yield"),
+        source: "nasdaq",
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_akshare_stock_value() {
+        let json = serde_json::from_str(include_str!(
+            "../../tests/fixtures/akshare/stock_value_em.json"
+        ))
+        .unwrap();
+        let fundamental = parse_akshare_stock_value(&json).unwrap();
+        assert_eq!(fundamental.total_shares, Some(29352000000.0));
+        assert_eq!(fundamental.market_cap, Some(194313000000.0));
+        assert_eq!(fundamental.pe_ttm, Some(4.55));
+        assert_eq!(fundamental.pb, Some(0.34));
+        assert_eq!(fundamental.dividend_yield, None);
+    }
+
+    #[test]
+    fn test_parse_akshare_hk_indicator() {
+        let json = serde_json::from_str(include_str!(
+            "../../tests/fixtures/akshare/hk_financial_indicator.json"
+        ))
+        .unwrap();
+        let fundamental = parse_akshare_hk_indicator(&json).unwrap();
+        assert_eq!(fundamental.total_shares, Some(9500000000.0));
+        assert_eq!(fundamental.market_cap, Some(2780000000000.0));
+        assert_eq!(fundamental.pe_ttm, Some(23.7));
+        assert_eq!(fundamental.pb, Some(2.95));
+        assert_eq!(fundamental.dividend_yield, Some(0.82));
+    }
+
+    #[test]
+    fn test_parse_nasdaq_summary() {
+        let json = serde_json::from_str(include_str!(
+            "../../tests/fixtures/nasdaq/quote_summary.json"
+        ))
+        .unwrap();
+        let fundamental = parse_nasdaq_summary(&json).unwrap();
+        assert_eq!(fundamental.market_cap, Some(2865733000000.0));
+        assert_eq!(fundamental.pe_ttm, Some(30.05));
+        assert_eq!(fundamental.dividend_yield, Some(0.52));
+        assert_eq!(fundamental.total_shares, None);
+        assert_eq!(fundamental.pb, None);
+    }
+}
@@ -0,0 +1,50 @@
+use crate::error;
+use crate::fundamental::fundamental_job::SyncFundamentalJob;
+use crate::fundamental::fundamental_svc;
+use application_core::lang::runnable::Runnable;
+use application_web::response::RespBody;
+use application_web_macros::get;
+use axum::extract::{Path, Query};
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+use tokio::spawn;
+use tracing::info;
+
+#[derive(Serialize, Deserialize)]
+struct FundamentalParams {
+    code: String,
+}
+
+/// 获取股票的基本面快照：股本、市值、市盈率、市净率、股息率和 52 周最高最低价
+///
+/// ```text
+/// GET /stock/fundamentals?code=600000.SH
+/// ```
+#[get("/stock/fundamentals")]
+async fn fundamentals(Query(params): Query<FundamentalParams>) -> impl IntoResponse {
+    info!("Get fundamentals, code = {}", params.code);
+    let r = fundamental_svc::get_fundamental(&params.code).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+/// 获取指数全部成分股的基本面快照
+///
+/// ```text
+/// GET /index/000300.SH/fundamentals
+/// ```
+#[get("/index/{code}/fundamentals")]
+async fn index_fundamentals(Path(code): Path<String>) -> impl IntoResponse {
+    let r = fundamental_svc::get_index_fundamentals(&code).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+/// 在后台同步全部个股的基本面快照
+#[get("/stock/fundamentals/sync")]
+async fn sync() -> impl IntoResponse {
+    spawn(async {
+        let job = SyncFundamentalJob;
+        job.run().await;
+    });
+
+    RespBody::<()>::success_info("Sync fundamentals in background")
+}
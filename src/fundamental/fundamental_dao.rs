@@ -0,0 +1,54 @@
+use crate::fundamental::fundamental_model;
+use crate::fundamental::fundamental_model::Model;
+use application_beans::factory::bean_factory::BeanFactory;
+use application_context::context::application_context::APPLICATION_CONTEXT;
+use database_mysql_seaorm::Dao;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ColumnTrait, DbErr, EntityTrait, IntoActiveModel, QueryFilter};
+
+/// 查询股票的基本面快照
+pub async fn get_fundamental(code: &str) -> Result<Option<Model>, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    fundamental_model::Entity::find_by_id(code)
+        .one(&dao.connection)
+        .await
+}
+
+/// 批量查询股票的基本面快照
+pub async fn find_fundamentals(codes: &[String]) -> Result<Vec<Model>, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    fundamental_model::Entity::find()
+        .filter(fundamental_model::Column::Code.is_in(codes.iter().cloned()))
+        .all(&dao.connection)
+        .await
+}
+
+/// 保存股票的基本面快照，已存在时覆盖
+pub async fn save_fundamental(fundamental: &Model) -> Result<(), DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    fundamental_model::Entity::insert(fundamental.clone().into_active_model())
+        .on_conflict(
+            OnConflict::column(fundamental_model::Column::Code)
+                .update_columns([
+                    fundamental_model::Column::Date,
+                    fundamental_model::Column::Currency,
+                    fundamental_model::Column::TotalShares,
+                    fundamental_model::Column::FloatShares,
+                    fundamental_model::Column::MarketCap,
+                    fundamental_model::Column::FloatMarketCap,
+                    fundamental_model::Column::PeTtm,
+                    fundamental_model::Column::Pb,
+                    fundamental_model::Column::DividendYield,
+                    fundamental_model::Column::Week52High,
+                    fundamental_model::Column::Week52Low,
+                    fundamental_model::Column::Source,
+                ])
+                .to_owned(),
+        )
+        .exec(&dao.connection)
+        .await?;
+    Ok(())
+}
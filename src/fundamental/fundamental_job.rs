@@ -0,0 +1,22 @@
+use crate::fundamental::fundamental_svc::sync_fundamentals;
+use application_core::lang::runnable::Runnable;
+use async_trait::async_trait;
+use tracing::{error, info};
+
+pub struct SyncFundamentalJob;
+
+#[async_trait]
+impl Runnable for SyncFundamentalJob {
+    async fn run(&self) {
+        info!("SyncFundamentalJob run ...");
+        let r = sync_fundamentals().await;
+        match r {
+            Ok(_) => {
+                info!("SyncFundamentalJob end success")
+            }
+            Err(e) => {
+                error!("Sync fundamentals error {}", e)
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::{ActiveModelBehavior, DeriveEntityModel, DeriveRelation, EnumIter};
+use serde::{Deserialize, Serialize};
+
+/// 股票基本面快照，每只股票只保存最近一次同步的数据
+///
+/// 股本单位为股，市值单位为 `currency` 对应的货币，股息率为百分比。
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "stock_fundamental")]
+pub struct Model {
+    /// 股票代码，与指数成分股的 `stock_code` 对应
+    #[sea_orm(primary_key)]
+    pub code: String,
+    /// 快照日期
+    pub date: Date,
+    /// 市值的货币：CNY/HKD/USD
+    pub currency: String,
+    /// 总股本
+    pub total_shares: Option<f64>,
+    /// 流通股本
+    pub float_shares: Option<f64>,
+    /// 总市值
+    pub market_cap: Option<f64>,
+    /// 流通市值
+    pub float_market_cap: Option<f64>,
+    /// 滚动市盈率
+    pub pe_ttm: Option<f64>,
+    /// 市净率
+    pub pb: Option<f64>,
+    /// 近 12 个月股息率
+    pub dividend_yield: Option<f64>,
+    /// 52 周最高价（前复权）
+    pub week52_high: Option<f64>,
+    /// 52 周最低价（前复权）
+    pub week52_low: Option<f64>,
+    /// 数据来源
+    pub source: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+/// 指数成分股及其基本面快照，尚未同步基本面的成分股 `fundamental` 为空
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConstituentFundamental {
+    pub stock_code: String,
+    pub stock_name: String,
+    pub fundamental: Option<Model>,
+}
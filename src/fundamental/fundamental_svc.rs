@@ -0,0 +1,164 @@
+use crate::corporate_action::corporate_action_model::ActionType;
+use crate::corporate_action::corporate_action_svc;
+use crate::error::AppError;
+use crate::exchange::exchange_model::Exchange;
+use crate::fundamental::fundamental_api::FundamentalApi;
+use crate::fundamental::fundamental_dao;
+use crate::fundamental::fundamental_model::{ConstituentFundamental, Model};
+use crate::index::index_svc;
+use crate::stock::stock_model::{Model as Stock, PriceAdjust, StockKind};
+use crate::stock::stock_price_api::StockDailyPrice;
+use crate::stock::stock_svc;
+use bigdecimal::ToPrimitive;
+use chrono::{Local, NaiveDate, TimeDelta};
+use std::collections::HashMap;
+use std::error::Error;
+use std::str::FromStr;
+use tracing::{error, info};
+
+/// 获取股票的基本面快照
+pub async fn get_fundamental(code: &str) -> Result<Model, Box<dyn Error>> {
+    let stock = stock_svc::get_stock(code).await?;
+    let fundamental = fundamental_dao::get_fundamental(&stock.code)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Fundamental of {} not synced", code)))?;
+    Ok(fundamental)
+}
+
+/// 获取指数全部成分股的基本面快照
+pub async fn get_index_fundamentals(
+    index: &str,
+) -> Result<Vec<ConstituentFundamental>, Box<dyn Error>> {
+    let constituents = index_svc::get_constituent_stocks(index).await?;
+    let codes = constituents
+        .iter()
+        .map(|constituent| constituent.stock_code.clone())
+        .collect::<Vec<_>>();
+    let mut fundamentals = fundamental_dao::find_fundamentals(&codes)
+        .await?
+        .into_iter()
+        .map(|fundamental| (fundamental.code.clone(), fundamental))
+        .collect::<HashMap<_, _>>();
+    Ok(constituents
+        .into_iter()
+        .map(|constituent| ConstituentFundamental {
+            fundamental: fundamentals.remove(&constituent.stock_code),
+            stock_code: constituent.stock_code,
+            stock_name: constituent.stock_name,
+        })
+        .collect())
+}
+
+/// 同步单只股票的基本面快照
+///
+/// 股本、市值、市盈率和市净率来自上游接口；52 周最高最低价由前复权日线计算；
+/// 上游没有股息率时，由近 12 个月已同步的现金分红和最新收盘价计算；
+/// 上游没有总股本时，由总市值和最新收盘价推算。
+pub async fn sync_stock_fundamental(stock: &Stock) -> Result<Model, Box<dyn Error>> {
+    let exchange = Exchange::from_str(stock.exchange.as_str())?;
+    let fundamental = exchange.get_fundamental(stock).await?;
+    let prices = stock_svc::get_stock_adjusted_daily_price(&stock.code, PriceAdjust::Qfq)
+        .await?
+        .prices;
+
+    let date = Local::now()
+        .with_timezone(&exchange.time_zone())
+        .date_naive();
+    let year_ago = date - TimeDelta::days(365);
+    let close = prices.last().and_then(|price| price.close.to_f64());
+    let start_time = year_ago.format("%Y%m%d000000").to_string().parse::<u64>()?;
+    let (week52_high, week52_low) = get_week52_range(&prices, start_time);
+
+    let dividend_yield = match fundamental.dividend_yield {
+        Some(dividend_yield) => Some(dividend_yield),
+        None => get_dividend_yield(&stock.code, &year_ago, close).await?,
+    };
+    let total_shares = fundamental.total_shares.or_else(|| {
+        fundamental
+            .market_cap
+            .zip(close)
+            .filter(|(_, close)| *close > 0.0)
+            .map(|(market_cap, close)| (market_cap / close).round())
+    });
+
+    let model = Model {
+        code: stock.code.clone(),
+        date,
+        currency: exchange.currency(),
+        total_shares,
+        float_shares: fundamental.float_shares,
+        market_cap: fundamental.market_cap,
+        float_market_cap: fundamental.float_market_cap,
+        pe_ttm: fundamental.pe_ttm,
+        pb: fundamental.pb,
+        dividend_yield,
+        week52_high,
+        week52_low,
+        source: fundamental.source.to_string(),
+    };
+    fundamental_dao::save_fundamental(&model).await?;
+    Ok(model)
+}
+
+/// 同步全部个股的基本面快照，单只股票同步失败不影响其他股票
+pub async fn sync_fundamentals() -> Result<(), Box<dyn Error>> {
+    let stocks = stock_svc::get_stocks_by_kind(&StockKind::Stock).await?;
+    let mut count = 0;
+    for stock in &stocks {
+        if let Err(e) = sync_stock_fundamental(stock).await {
+            error!("Sync fundamental of {} error {}", stock.code, e);
+            continue;
+        }
+        count += 1;
+    }
+    info!("Save fundamentals of {}/{} stocks", count, stocks.len());
+    Ok(())
+}
+
+/// 计算指定时间之后（包含）日线的最高价和最低价
+fn get_week52_range(prices: &[StockDailyPrice], start_time: u64) -> (Option<f64>, Option<f64>) {
+    prices
+        .iter()
+        .filter(|price| price.time >= start_time)
+        .fold((None, None), |(high, low), price| {
+            (max(high, price.high.to_f64()), min(low, price.low.to_f64()))
+        })
+}
+
+fn max(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn min(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// 由指定日期之后的现金分红计算股息率，没有已同步的公司行动时返回 `None`
+async fn get_dividend_yield(
+    code: &str,
+    start: &NaiveDate,
+    close: Option<f64>,
+) -> Result<Option<f64>, Box<dyn Error>> {
+    let close = match close {
+        Some(close) if close > 0.0 => close,
+        _ => return Ok(None),
+    };
+    let actions = corporate_action_svc::get_corporate_actions(code).await?;
+    if actions.is_empty() {
+        return Ok(None);
+    }
+    let dividend = actions
+        .iter()
+        .filter(|action| {
+            action.action_type == ActionType::Dividend.to_string() && action.ex_date >= *start
+        })
+        .filter_map(|action| action.cash.as_ref().and_then(|cash| cash.to_f64()))
+        .sum::<f64>();
+    Ok(Some((dividend / close * 10000.0).round() / 100.0))
+}
@@ -0,0 +1,6 @@
+pub mod fundamental_api;
+mod fundamental_ctrl;
+pub mod fundamental_dao;
+pub mod fundamental_job;
+pub mod fundamental_model;
+pub mod fundamental_svc;
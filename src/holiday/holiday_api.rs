@@ -1,5 +1,8 @@
+use crate::error;
+use crate::error::AppError;
 use crate::exchange::exchange_model::Exchange;
 use crate::holiday::holiday_model::{Model, create_holiday_model};
+use crate::upstream;
 use application_context::context::application_context::APPLICATION_CONTEXT;
 use application_core::env::property_resolver::PropertyResolver;
 use async_trait::async_trait;
@@ -18,10 +21,15 @@ pub trait HolidayApi {
 #[async_trait]
 impl HolidayApi for Exchange {
     async fn get_holidays(&self) -> Result<Vec<Model>, Box<dyn Error>> {
-        match self {
-            Exchange::SSE | Exchange::SZSE => get_china_stock_holiday(self).await,
-            Exchange::HKEX => get_holiday_from_gov_hk(self).await,
-            Exchange::NASDAQ => get_holiday_from_nasdaq(self).await,
+        // 按 `exchange` 表配置的交易日历来源获取，北交所与沪深两市、美股各交易所分别共用交易日历
+        match self.calendar_source().as_str() {
+            "szse" => get_china_stock_holiday(self).await,
+            "gov_hk" => get_holiday_from_gov_hk(self).await,
+            "nasdaq" => get_holiday_from_nasdaq(self).await,
+            source => Err(Box::new(AppError::UnsupportedExchange(format!(
+                "Unsupported calendar source {} of exchange {}",
+                source, self
+            )))),
         }
     }
 }
@@ -29,12 +37,15 @@ impl HolidayApi for Exchange {
 async fn get_china_stock_holiday(exchange: &Exchange) -> Result<Vec<Model>, Box<dyn Error>> {
     let utc = Utc
         .with_ymd_and_hms(Utc::now().year(), 1, 1, 0, 0, 0)
-        .unwrap();
+        .single()
+        .ok_or_else(|| AppError::Internal("Invalid first day of year".to_string()))?;
     let month = utc.with_timezone(&exchange.time_zone());
     let mut vec = Vec::new();
     let mut i = 0;
     while i < 12 {
-        let month = month.checked_add_months(Months::new(i)).unwrap();
+        let month = month
+            .checked_add_months(Months::new(i))
+            .ok_or_else(|| AppError::Internal(format!("Invalid month {} of year", i + 1)))?;
         let r = get_holiday_from_sz(exchange, &month).await?;
         for x in r {
             vec.push(x)
@@ -52,7 +63,7 @@ async fn get_holiday_from_sz(
     let environment = application_context.get_environment().await;
     let base_url = environment
         .get_property::<String>("stock.api.sz.baseurl")
-        .unwrap();
+        .ok_or_else(|| AppError::missing_property("stock.api.sz.baseurl"))?;
     let month = month.format("%Y-%m").to_string();
     let url = format!(
         "{}/api/report/exchange/onepersistenthour/monthList?month={}&random={}",
@@ -63,32 +74,33 @@ async fn get_holiday_from_sz(
     let client = Request::client().await;
     let response = client.get(url).send().await?;
     let json: serde_json::Value = response.json().await?;
-    let data = json.get("data").unwrap().as_array().unwrap();
+    parse_szse_holidays(&json, exchange)
+}
+
+/// 解析深交所交易日历，非交易日且不是周末的日期为节假日
+fn parse_szse_holidays(
+    json: &serde_json::Value,
+    exchange: &Exchange,
+) -> Result<Vec<Model>, Box<dyn Error>> {
+    let data = error::array_field(json, "data", "szse")?;
     let mut vec = Vec::new();
     for h in data {
-        let bz = h.get("jybz").unwrap().as_str().unwrap();
+        let bz = error::str_field(h, "jybz", "szse")?;
         if bz == "1" {
             continue;
         }
-        let date = h.get("jyrq").unwrap().as_str().unwrap();
-        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d");
-        if date.is_err() {
-            continue;
-        }
-        let date = date.unwrap();
-        let xh = h
-            .get("zrxh")
-            .unwrap()
-            .as_number()
-            .unwrap()
-            .as_u64()
-            .unwrap();
+        let date = error::str_field(h, "jyrq", "szse")?;
+        let date = match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(date) => date,
+            Err(_) => continue,
+        };
+        let xh = error::u64_field(h, "zrxh", "szse")?;
         if xh == 7 || xh == 1 {
             continue;
         }
         let id = format!("{}{}", date.format("%Y%m%d"), exchange.int_code());
         vec.push(Model {
-            id: id.parse::<u64>().unwrap(),
+            id: id.parse::<u64>()?,
             year: date.year() as u16,
             month: date.month() as u8,
             day: date.day() as u8,
@@ -98,12 +110,26 @@ async fn get_holiday_from_sz(
 }
 
 async fn get_holiday_from_nasdaq(exchange: &Exchange) -> Result<Vec<Model>, Box<dyn Error>> {
-    let url = "https://www.nasdaq.com/market-activity/stock-market-holiday-schedule";
+    let url = format!(
+        "{}/market-activity/stock-market-holiday-schedule",
+        upstream::get_url("stock.api.nasdaq.website").await?
+    );
     let client = Request::client().await;
     let response = client.get(url).send().await?;
     let body = response.text().await?;
+    // Assume the current year
+    let current_year = Utc::now().year();
+    parse_nasdaq_holidays(&body, exchange, current_year)
+}
+
+/// 解析纳斯达克休市日程页面
+fn parse_nasdaq_holidays(
+    body: &str,
+    exchange: &Exchange,
+    current_year: i32,
+) -> Result<Vec<Model>, Box<dyn Error>> {
     // Parse the HTML document
-    let document = Html::parse_document(&body);
+    let document = Html::parse_document(body);
 
     // Define a selector for the table rows within the holiday schedule table
     let row_selector = Selector::parse("div.nsdq_table--responsive table tbody tr").unwrap();
@@ -116,13 +142,13 @@ async fn get_holiday_from_nasdaq(exchange: &Exchange) -> Result<Vec<Model>, Box<
         // Collect the text content of each cell
         let cells: Vec<String> = collect_cells(&row, &cell_selector);
 
-        let date = cells[1].clone();
+        let date = cells
+            .get(1)
+            .cloned()
+            .ok_or_else(|| AppError::parse("nasdaq", "td[1]"))?;
         // Define the format to match "May 26"
         let format = "%B %d";
 
-        // Assume the current year
-        let current_year = Utc::now().year();
-
         // Parse the month and day, then construct the full date
         let parsed_date = NaiveDate::parse_from_str(
             &format!("{} {}", date, current_year),
@@ -130,7 +156,7 @@ async fn get_holiday_from_nasdaq(exchange: &Exchange) -> Result<Vec<Model>, Box<
         )?;
         let id = format!("{}{}", parsed_date.format("%Y%m%d"), exchange.int_code());
         let holiday = create_holiday_model(
-            id.parse::<u64>().unwrap(),
+            id.parse::<u64>()?,
             parsed_date.year() as u16,
             parsed_date.month() as u8,
             parsed_date.day() as u8,
@@ -144,12 +170,25 @@ async fn get_holiday_from_nasdaq(exchange: &Exchange) -> Result<Vec<Model>, Box<
 async fn get_holiday_from_gov_hk(exchange: &Exchange) -> Result<Vec<Model>, Box<dyn Error>> {
     let utc = Utc::now();
     let year = utc.with_timezone(&exchange.time_zone()).year();
-    let url = format!("https://www.gov.hk/sc/about/abouthk/holiday/{}.htm", year);
+    let url = format!(
+        "{}/sc/about/abouthk/holiday/{}.htm",
+        upstream::get_url("stock.api.govhk.baseurl").await?,
+        year
+    );
     let client = Request::client().await;
     let response = client.get(url).send().await?;
     let body = response.text().await?;
+    parse_gov_hk_holidays(&body, exchange, year)
+}
+
+/// 解析香港政府公众假期页面，只读取第一个表格
+fn parse_gov_hk_holidays(
+    body: &str,
+    exchange: &Exchange,
+    year: i32,
+) -> Result<Vec<Model>, Box<dyn Error>> {
     // Parse the HTML document
-    let document = Html::parse_document(&body);
+    let document = Html::parse_document(body);
 
     let row_selector = Selector::parse("section.blockItem table:first-of-type tbody tr").unwrap();
 
@@ -158,7 +197,10 @@ async fn get_holiday_from_gov_hk(exchange: &Exchange) -> Result<Vec<Model>, Box<
         let cell_selector = Selector::parse("td").unwrap();
         let cells = collect_cells(&row, &cell_selector);
 
-        let date = cells[1].clone();
+        let date = cells
+            .get(1)
+            .cloned()
+            .ok_or_else(|| AppError::parse("gov.hk", "td[1]"))?;
         if date.is_empty() {
             continue;
         }
@@ -168,7 +210,7 @@ async fn get_holiday_from_gov_hk(exchange: &Exchange) -> Result<Vec<Model>, Box<
             NaiveDate::parse_from_str(&format!("{}年{}", year, date), &format!("%Y年{}", format))?;
         let id = format!("{}{}", parsed_date.format("%Y%m%d"), exchange.int_code());
         let holiday = create_holiday_model(
-            id.parse::<u64>().unwrap(),
+            id.parse::<u64>()?,
             parsed_date.year() as u16,
             parsed_date.month() as u8,
             parsed_date.day() as u8,
@@ -186,3 +228,45 @@ fn collect_cells(row: &ElementRef, cell_selector: &Selector) -> Vec<String> {
         .collect();
     cells
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dates(holidays: &[Model]) -> Vec<(u16, u8, u8)> {
+        holidays.iter().map(|h| (h.year, h.month, h.day)).collect()
+    }
+
+    #[test]
+    fn test_parse_szse_holidays() {
+        let json = serde_json::from_str(include_str!("../../tests/fixtures/szse/month_list.json"))
+            .unwrap();
+        let holidays = parse_szse_holidays(&json, &Exchange::SZSE).unwrap();
+        // 交易日和周末不计入节假日
+        assert_eq!(dates(&holidays), vec![(2024, 2, 9), (2024, 2, 12)]);
+        assert_eq!(holidays[0].id, 2024020920);
+    }
+
+    #[test]
+    fn test_parse_nasdaq_holidays() {
+        let body = include_str!("../../tests/fixtures/nasdaq/holiday_schedule.html");
+        let holidays = parse_nasdaq_holidays(body, &Exchange::NASDAQ, 2024).unwrap();
+        assert_eq!(
+            dates(&holidays),
+            vec![(2024, 1, 1), (2024, 1, 15), (2024, 3, 29), (2024, 12, 25)]
+        );
+        assert_eq!(holidays[0].id, 2024010140);
+    }
+
+    #[test]
+    fn test_parse_gov_hk_holidays() {
+        let body = include_str!("../../tests/fixtures/gov_hk/holiday.html");
+        let holidays = parse_gov_hk_holidays(body, &Exchange::HKEX, 2024).unwrap();
+        // 空行和第二个表格被忽略
+        assert_eq!(
+            dates(&holidays),
+            vec![(2024, 1, 1), (2024, 2, 10), (2024, 12, 25)]
+        );
+        assert_eq!(holidays[2].id, 2024122530);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::error;
 use crate::holiday::holiday_job::SyncHolidayJob;
 use crate::holiday::holiday_svc;
 use application_core::lang::runnable::Runnable;
@@ -21,7 +22,7 @@ struct HolidayParams {
 async fn today_is_holiday(Query(params): Query<HolidayParams>) -> impl IntoResponse {
     let r = holiday_svc::is_holiday(&params.exchange).await;
 
-    RespBody::result(&r).response()
+    error::with_status(&r, RespBody::result(&r).response())
 }
 
 /// 定义一个异步函数sync，返回类型为IntoResponse的实现
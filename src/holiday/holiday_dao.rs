@@ -3,7 +3,7 @@ use crate::holiday::holiday_model;
 use crate::holiday::holiday_model::{ActiveModel, Model};
 use application_beans::factory::bean_factory::BeanFactory;
 use application_context::context::application_context::APPLICATION_CONTEXT;
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, NaiveDate};
 use database_mysql_seaorm::Dao;
 use sea_orm::{DbErr, EntityTrait};
 use std::error::Error;
@@ -27,6 +27,18 @@ pub async fn get_holiday(
         .await
 }
 
+pub async fn get_holiday_by_date(
+    exchange: &Exchange,
+    date: &NaiveDate,
+) -> Result<Option<Model>, DbErr> {
+    let date = format!("{}{}", date.format("%Y%m%d"), exchange.int_code());
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    holiday_model::Entity::find_by_id(date.parse::<u64>().unwrap())
+        .one(&dao.connection)
+        .await
+}
+
 pub async fn save_holidays(holidays: Vec<ActiveModel>) -> Result<(), Box<dyn Error>> {
     let application_context = APPLICATION_CONTEXT.read().await;
     let dao = application_context.get_bean_factory().get::<Dao>();
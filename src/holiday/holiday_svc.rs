@@ -1,11 +1,13 @@
 use crate::exchange::exchange_model::Exchange;
+use crate::exchange::exchange_registry;
 use crate::holiday::holiday_api::HolidayApi;
 use crate::holiday::holiday_dao;
 use crate::holiday::holiday_model::ActiveModel;
 use application_cache::CacheManager;
-use chrono::{DateTime, Datelike, Local};
+use chrono::{DateTime, Datelike, Local, NaiveDate};
 use sea_orm::Set;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::error::Error;
 use std::str::FromStr;
 use std::time::Duration;
@@ -69,12 +71,39 @@ pub async fn is_holiday(exchange: &str) -> Result<bool, Box<dyn Error>> {
     Ok(holiday_status)
 }
 
+/// 判断指定日期是否为交易所的交易日（非周末且非休市日）
+pub async fn is_trade_date(exchange: &Exchange, date: &NaiveDate) -> Result<bool, Box<dyn Error>> {
+    if date.weekday().number_from_monday() >= 6 {
+        return Ok(false);
+    }
+
+    let market_holiday = holiday_dao::get_holiday_by_date(exchange, date).await?;
+    Ok(market_holiday.is_none())
+}
+
+/// 获取交易所的全部休市日期
+pub async fn get_holiday_dates(exchange: &Exchange) -> Result<HashSet<NaiveDate>, Box<dyn Error>> {
+    let holidays = holiday_dao::get_all_holiday().await?;
+    let dates = holidays
+        .iter()
+        .filter(|holiday| holiday.id % 100 == exchange.int_code() as u64)
+        .filter_map(|holiday| {
+            NaiveDate::from_ymd_opt(
+                holiday.year as i32,
+                holiday.month as u32,
+                holiday.day as u32,
+            )
+        })
+        .collect();
+    Ok(dates)
+}
+
 pub async fn sync_holidays() -> Result<(), Box<dyn Error>> {
     let dates = holiday_dao::get_all_holiday().await?;
     let dates = dates.into_iter().map(|date| date.id).collect::<Vec<_>>();
 
     let mut holidays = Vec::new();
-    for exchange in Exchange::VALUES {
+    for exchange in exchange_registry::enabled() {
         info!("Sync {:?} holidays", exchange.as_ref());
         let result = exchange.get_holidays().await;
         if let Ok(vec) = result {
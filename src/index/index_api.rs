@@ -1,15 +1,19 @@
+use crate::error;
+use crate::error::AppError;
 use crate::exchange::exchange_model::Exchange;
 use crate::stock::stock_model::{Model as Stock, Model};
+use crate::upstream;
 use async_trait::async_trait;
 use calamine::Reader;
-use calamine::Xls;
-use calamine::open_workbook;
+use calamine::open_workbook_auto_from_rs;
+use calamine::{Data, Range};
 use rand::RngExt;
 use scraper::{Html, Selector};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::error::Error;
 use std::fs::File;
-use std::io::copy;
+use std::io::{Cursor, copy};
 use std::path::Path;
 use tempfile::tempdir;
 use tracing::info;
@@ -35,7 +39,8 @@ impl IndexApi for Exchange {
         match self {
             Exchange::SSE | Exchange::SZSE => {
                 let url = format!(
-                    "https://oss-ch.csindex.com.cn/static/html/csindex/public/uploads/file/autofile/cons/{}cons.xls",
+                    "{}/static/html/csindex/public/uploads/file/autofile/cons/{}cons.xls",
+                    upstream::get_url("stock.api.csindex.baseurl").await?,
                     index_code,
                 );
                 info!("Query Index Stocks from url = {}", url);
@@ -46,13 +51,15 @@ impl IndexApi for Exchange {
                 Ok(stocks)
             }
             Exchange::HKEX => get_index_stock_from_hkex(index_code, self).await,
-            Exchange::NASDAQ => {
-                if index_code == "SPX" {
-                    get_spx_stocks_from_wikipedia(self).await
-                } else {
-                    get_stocks_from_nasdaq(index_code, self).await
-                }
+            Exchange::NASDAQ | Exchange::NYSE | Exchange::AMEX => {
+                let venues = get_us_listing_venues().await?;
+                get_us_index_stocks(index_code, &venues).await
             }
+            Exchange::BSE => Err(AppError::UnsupportedExchange(format!(
+                "Index constituents of {} are not supported",
+                self.as_ref()
+            ))
+            .into()),
         }
     }
 }
@@ -62,53 +69,125 @@ async fn get_index_stock_from_hkex(
     exchange: &Exchange,
 ) -> Result<Vec<Model>, Box<dyn Error>> {
     let url = format!(
-        "https://www.hsi.com.hk/data/schi/rt/index-series/{}/constituents.do?{}",
+        "{}/data/schi/rt/index-series/{}/constituents.do?{}",
+        upstream::get_url("stock.api.hsi.baseurl").await?,
         index_code,
         rand::rng().random_range(1000..9999)
     );
     info!("Query Index Stocks from url = {}", url);
     let response = Request::get_response(&url).await?;
     let data: Value = response.json().await?;
-    let index_series_list = data.get("indexSeriesList").unwrap().as_array().unwrap();
-    let index_series = index_series_list.first().unwrap().as_object().unwrap();
-    let index_list = index_series.get("indexList").unwrap().as_array().unwrap();
-    let index_stocks = index_list
+    Ok(parse_hsi_constituents(&data, exchange)?)
+}
+
+/// 解析恒生指数公司返回的指数成分股
+pub(crate) fn parse_hsi_constituents(
+    data: &Value,
+    exchange: &Exchange,
+) -> Result<Vec<Stock>, AppError> {
+    let index_series_list = error::array_field(data, "indexSeriesList", "hsi")?;
+    let index_series = index_series_list
         .first()
-        .unwrap()
-        .get("constituentContent")
-        .unwrap()
-        .as_array()
-        .unwrap();
+        .ok_or_else(|| AppError::parse("hsi", "indexSeriesList[0]"))?;
+    let index_list = error::array_field(index_series, "indexList", "hsi")?;
+    let index = index_list
+        .first()
+        .ok_or_else(|| AppError::parse("hsi", "indexList[0]"))?;
+    let index_stocks = error::array_field(index, "constituentContent", "hsi")?;
     let mut stocks = Vec::new();
     for index_stock in index_stocks {
-        let code = index_stock
-            .get("code")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
+        let code = error::str_field(index_stock, "code", "hsi")?.to_string();
         let stock = Stock {
             code: format!("{}{}", code, exchange.stock_code_suffix()),
-            name: index_stock
-                .get("constituentName")
-                .unwrap()
-                .as_str()
-                .unwrap()
-                .to_string(),
-            exchange: "HK".to_string(),
+            name: error::str_field(index_stock, "constituentName", "hsi")?.to_string(),
+            exchange: exchange.as_ref().to_string(),
             stock_type: "Stock".to_string(),
             stock_code: code,
+            delisted_at: None,
         };
         stocks.push(stock);
     }
     Ok(stocks)
 }
 
+/// 获取美股指数的成分股，成分股按主板上市的交易所区分
+pub(crate) async fn get_us_index_stocks(
+    index_code: &str,
+    venues: &HashMap<String, Exchange>,
+) -> Result<Vec<Stock>, Box<dyn Error>> {
+    let stocks = if index_code == "SPX" {
+        get_spx_stocks_from_wikipedia(&Exchange::NASDAQ).await?
+    } else {
+        get_stocks_from_nasdaq(index_code, &Exchange::NASDAQ).await?
+    };
+    Ok(assign_us_listing_venues(stocks, venues))
+}
+
+/// 从纳斯达克股票筛选器获取纳斯达克、纽交所和美交所上市的股票，返回股票代码到上市交易所的映射
+pub(crate) async fn get_us_listing_venues() -> Result<HashMap<String, Exchange>, Box<dyn Error>> {
+    let base_url = upstream::get_url("stock.api.nasdaq.baseurl").await?;
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36".parse()?);
+    headers.insert("Accept", "*/*".parse()?);
+    headers.insert("Accept-Language", "en-US,en;q=0.9".parse()?);
+    let client = reqwest::Client::builder().cookie_store(true).build()?;
+
+    let mut venues = HashMap::new();
+    for exchange in Exchange::US_VALUES {
+        let url = format!(
+            "{}/api/screener/stocks?tableonly=true&download=true&exchange={}",
+            base_url,
+            exchange.as_ref().to_lowercase()
+        );
+        info!(
+            "Query {} listed stocks from url = {}",
+            exchange.as_ref(),
+            url
+        );
+        let response = client.get(&url).headers(headers.clone()).send().await?;
+        let data: Value = response.json().await?;
+        for symbol in parse_nasdaq_screener_symbols(&data)? {
+            venues.insert(symbol, exchange);
+        }
+    }
+    Ok(venues)
+}
+
+/// 解析纳斯达克股票筛选器返回的股票代码，类别股代码中的 `/` 转换为与成分股列表一致的 `.`
+fn parse_nasdaq_screener_symbols(data: &Value) -> Result<Vec<String>, AppError> {
+    let data = error::field(data, "data", "nasdaq")?;
+    let rows = error::array_field(data, "rows", "nasdaq")?;
+    let mut symbols = Vec::new();
+    for row in rows {
+        let symbol = error::str_field(row, "symbol", "nasdaq")?;
+        symbols.push(symbol.trim().replace('/', "."));
+    }
+    Ok(symbols)
+}
+
+/// 按上市交易所修改美股的交易所和代码后缀，筛选器中找不到的股票保持不变
+fn assign_us_listing_venues(stocks: Vec<Stock>, venues: &HashMap<String, Exchange>) -> Vec<Stock> {
+    stocks
+        .into_iter()
+        .map(|mut stock| {
+            if let Some(exchange) = venues.get(&stock.stock_code) {
+                stock.code = format!("{}{}", stock.stock_code, exchange.stock_code_suffix());
+                stock.exchange = exchange.as_ref().to_string();
+            }
+            stock
+        })
+        .collect()
+}
+
 async fn get_stocks_from_nasdaq(
     _index: &str,
     exchange: &Exchange,
 ) -> Result<Vec<Stock>, Box<dyn Error>> {
-    let url = format!("https://api.nasdaq.com/api/quote/list-type/{}", "nasdaq100");
+    let url = format!(
+        "{}/api/quote/list-type/{}",
+        upstream::get_url("stock.api.nasdaq.baseurl").await?,
+        "nasdaq100"
+    );
     info!("Query Index Stocks from url = {}", url);
     let mut headers = reqwest::header::HeaderMap::new();
     headers.insert("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36".parse()?);
@@ -120,18 +199,18 @@ async fn get_stocks_from_nasdaq(
     let response = client.get(&url).headers(headers).send().await?;
     let text = response.text().await?;
     let data = serde_json::from_str::<Value>(&text)?;
-    let data = data.get("data").unwrap();
-    let data = data.get("data").unwrap();
-    let rows = data.get("rows").unwrap().as_array().unwrap();
+    Ok(parse_nasdaq_list_type(&data, exchange)?)
+}
+
+/// 解析纳斯达克指数成分股列表，去掉公司名称中的股票类别后缀
+fn parse_nasdaq_list_type(data: &Value, exchange: &Exchange) -> Result<Vec<Stock>, AppError> {
+    let data = error::field(data, "data", "nasdaq")?;
+    let data = error::field(data, "data", "nasdaq")?;
+    let rows = error::array_field(data, "rows", "nasdaq")?;
     let mut stocks = Vec::new();
     for row in rows {
-        let code = row.get("symbol").unwrap().as_str().unwrap().to_string();
-        let mut name = row
-            .get("companyName")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
+        let code = error::str_field(row, "symbol", "nasdaq")?.to_string();
+        let mut name = error::str_field(row, "companyName", "nasdaq")?.to_string();
         name = name.replace("Class A Common Stock", "").trim().to_string();
         name = name.replace("Common Stock", "").trim().to_string();
         let stock = Stock {
@@ -140,6 +219,7 @@ async fn get_stocks_from_nasdaq(
             exchange: exchange.as_ref().to_string(),
             stock_type: "Stock".to_string(),
             stock_code: code,
+            delisted_at: None,
         };
         stocks.push(stock);
     }
@@ -147,7 +227,10 @@ async fn get_stocks_from_nasdaq(
 }
 
 async fn get_spx_stocks_from_wikipedia(exchange: &Exchange) -> Result<Vec<Stock>, Box<dyn Error>> {
-    let url = "https://en.wikipedia.org/wiki/List_of_S&P_500_companies";
+    let url = format!(
+        "{}/wiki/List_of_S&P_500_companies",
+        upstream::get_url("stock.api.wikipedia.baseurl").await?
+    );
     info!("Query Index Stocks from url = {}", url);
     let mut headers = reqwest::header::HeaderMap::new();
     headers.insert("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/139.0.0.0 Safari/537.36".parse()?);
@@ -157,13 +240,18 @@ async fn get_spx_stocks_from_wikipedia(exchange: &Exchange) -> Result<Vec<Stock>
     headers.insert("Accept-Language", "en-US,en;q=0.9".parse()?);
     let client = reqwest::Client::builder().cookie_store(true).build()?;
     let resp = client
-        .get(url)
+        .get(&url)
         .headers(headers)
         .send()
         .await?
         .text()
         .await?;
-    let document = Html::parse_document(&resp);
+    parse_spx_stocks(&resp, exchange)
+}
+
+/// 解析维基百科标普 500 成分股页面
+fn parse_spx_stocks(html: &str, exchange: &Exchange) -> Result<Vec<Stock>, Box<dyn Error>> {
+    let document = Html::parse_document(html);
     // Wikipedia 表格选择器
     let table_selector = Selector::parse("table.wikitable")?;
     let row_selector = Selector::parse("tr")?;
@@ -188,6 +276,7 @@ async fn get_spx_stocks_from_wikipedia(exchange: &Exchange) -> Result<Vec<Stock>
                 exchange: exchange.as_ref().to_string(),
                 stock_type: "Stock".to_string(),
                 stock_code: code,
+                delisted_at: None,
             };
             stocks.push(stock);
         }
@@ -195,6 +284,7 @@ async fn get_spx_stocks_from_wikipedia(exchange: &Exchange) -> Result<Vec<Stock>
 
     Ok(stocks)
 }
+
 async fn download(url: &str, path: &Path) -> Result<(), Box<dyn Error>> {
     let client = reqwest::Client::builder().build()?;
     let response = client.get(url).send().await;
@@ -209,31 +299,146 @@ async fn download(url: &str, path: &Path) -> Result<(), Box<dyn Error>> {
     }
 }
 
+/// 读取中证指数成分股文件，按文件内容识别 xls 或 xlsx 格式
 pub async fn read_index_stocks_from_excel(path: &Path) -> Result<Vec<Stock>, Box<dyn Error>> {
-    let mut excel_xlsx: Xls<_> = open_workbook(path)?;
+    let mut workbook = open_workbook_auto_from_rs(Cursor::new(std::fs::read(path)?))?;
 
     let mut stocks = Vec::new();
-    if let Some(Ok(result)) = excel_xlsx.worksheet_range_at(0) {
-        for (i, row) in result.rows().enumerate() {
-            if i == 0 {
-                continue;
-            }
-            let exchange: Exchange = if row[7] == "深圳证券交易所" {
-                Exchange::SZSE
-            } else {
-                Exchange::SSE
-            };
-            let stock_code = row[4].to_string();
-            let stock_name = row[5].to_string();
-            stocks.push(Stock {
-                code: format!("{}{}", stock_code, exchange.stock_code_suffix()),
-                name: stock_name,
-                exchange: exchange.as_ref().to_string(),
-                stock_type: "Stock".to_string(),
-                stock_code,
-            });
-        }
+    if let Some(Ok(result)) = workbook.worksheet_range_at(0) {
+        stocks = read_index_stocks_from_range(&result);
     }
 
     Ok(stocks)
 }
+
+/// 读取中证指数成分股表格，第一行为表头，第 5、6 列为成分券代码和名称，第 8 列为交易所
+fn read_index_stocks_from_range(range: &Range<Data>) -> Vec<Stock> {
+    let mut stocks = Vec::new();
+    for (i, row) in range.rows().enumerate() {
+        if i == 0 {
+            continue;
+        }
+        let exchange: Exchange = if row[7] == "深圳证券交易所" {
+            Exchange::SZSE
+        } else if row[7] == "北京证券交易所" {
+            Exchange::BSE
+        } else {
+            Exchange::SSE
+        };
+        let stock_code = row[4].to_string();
+        let stock_name = row[5].to_string();
+        stocks.push(Stock {
+            code: format!("{}{}", stock_code, exchange.stock_code_suffix()),
+            name: stock_name,
+            exchange: exchange.as_ref().to_string(),
+            stock_type: "Stock".to_string(),
+            stock_code,
+            delisted_at: None,
+        });
+    }
+    stocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calamine::{Xlsx, open_workbook};
+
+    fn codes(stocks: &[Stock]) -> Vec<(&str, &str, &str)> {
+        stocks
+            .iter()
+            .map(|s| (s.code.as_str(), s.name.as_str(), s.exchange.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_hsi_constituents() {
+        let data = serde_json::from_str(include_str!("../../tests/fixtures/hsi/constituents.json"))
+            .unwrap();
+        let stocks = parse_hsi_constituents(&data, &Exchange::HKEX).unwrap();
+        assert_eq!(
+            codes(&stocks),
+            vec![("5.HK", "汇丰控股", "HKEX"), ("700.HK", "腾讯控股", "HKEX")]
+        );
+        assert_eq!(stocks[1].stock_code, "700");
+    }
+
+    #[test]
+    fn test_parse_hsi_constituents_missing_field() {
+        let data = serde_json::json!({"indexSeriesList": []});
+        let err = parse_hsi_constituents(&data, &Exchange::HKEX).unwrap_err();
+        assert_eq!(err, AppError::parse("hsi", "indexSeriesList[0]"));
+    }
+
+    #[test]
+    fn test_parse_nasdaq_list_type() {
+        let data = serde_json::from_str(include_str!(
+            "../../tests/fixtures/nasdaq/list_type_nasdaq100.json"
+        ))
+        .unwrap();
+        let stocks = parse_nasdaq_list_type(&data, &Exchange::NASDAQ).unwrap();
+        assert_eq!(
+            codes(&stocks),
+            vec![
+                ("AAPL.NS", "Apple Inc.", "NASDAQ"),
+                ("GOOGL.NS", "Alphabet Inc.", "NASDAQ")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_spx_stocks() {
+        let html = include_str!("../../tests/fixtures/wikipedia/spx.html");
+        let stocks = parse_spx_stocks(html, &Exchange::NASDAQ).unwrap();
+        // 只读取第一个表格
+        assert_eq!(
+            codes(&stocks),
+            vec![
+                ("MMM.NS", "3M", "NASDAQ"),
+                ("AAPL.NS", "Apple Inc.", "NASDAQ")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_nasdaq_screener_symbols() {
+        let data = serde_json::from_str(include_str!(
+            "../../tests/fixtures/nasdaq/screener_nyse.json"
+        ))
+        .unwrap();
+        let symbols = parse_nasdaq_screener_symbols(&data).unwrap();
+        assert_eq!(symbols, vec!["MMM", "JPM", "BRK.B"]);
+    }
+
+    #[test]
+    fn test_assign_us_listing_venues() {
+        let html = include_str!("../../tests/fixtures/wikipedia/spx.html");
+        let stocks = parse_spx_stocks(html, &Exchange::NASDAQ).unwrap();
+        let venues = HashMap::from([("MMM".to_string(), Exchange::NYSE)]);
+        let stocks = assign_us_listing_venues(stocks, &venues);
+        // 筛选器中找不到的股票保持纳斯达克
+        assert_eq!(
+            codes(&stocks),
+            vec![("MMM.N", "3M", "NYSE"), ("AAPL.NS", "Apple Inc.", "NASDAQ")]
+        );
+        assert_eq!(stocks[0].stock_code, "MMM");
+    }
+
+    #[test]
+    fn test_read_index_stocks_from_range() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/csindex/000300cons.xlsx"
+        );
+        let mut workbook: Xlsx<_> = open_workbook(path).unwrap();
+        let range = workbook.worksheet_range_at(0).unwrap().unwrap();
+        let stocks = read_index_stocks_from_range(&range);
+        assert_eq!(
+            codes(&stocks),
+            vec![
+                ("000001.SZ", "平安银行", "SZSE"),
+                ("600000.SH", "浦发银行", "SSE")
+            ]
+        );
+    }
+}
@@ -24,6 +24,8 @@ impl ActiveModelBehavior for ActiveModel {}
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct SyncIndexConstituents {
+    /// 是否只对比差异而不提交
+    pub dry_run: bool,
     pub added: Vec<Model>,
     pub removed: Vec<Model>,
     pub old: Vec<Model>,
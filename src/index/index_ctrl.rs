@@ -1,12 +1,18 @@
+use crate::error;
 use crate::exchange::exchange_model::Exchange;
+use crate::export;
+use crate::export::{ExportFormat, PriceKind};
 use crate::index::index_job::SyncIndexStocksJob;
 use crate::index::index_svc;
+use crate::stock::stock_model::DailyPriceQuery;
 use application_core::lang::runnable::Runnable;
 use application_web::response::RespBody;
 use application_web_macros::get;
 use axum::extract::{Path, Query};
+use axum::http::HeaderMap;
 use axum::response::IntoResponse;
 use serde::{Deserialize, Serialize};
+use std::error::Error;
 use std::str::FromStr;
 use tokio::spawn;
 
@@ -15,6 +21,12 @@ struct GetIndexParams {
     pub exchange: Option<String>,
 }
 
+#[derive(Serialize, Deserialize)]
+struct IndexSyncParams {
+    /// 为 `true` 时只返回成分股的差异，不提交变更
+    pub dry_run: Option<bool>,
+}
+
 /// 获取股票指数信息
 ///
 /// 如果没有提供交易所参数，将返回所有交易所的股票指数信息；否则，返回指定交易所的股票指数信息
@@ -28,14 +40,15 @@ struct GetIndexParams {
 pub async fn get_all_index(Query(params): Query<GetIndexParams>) -> impl IntoResponse {
     // 根据查询参数中的交易所信息，转换为Exchange枚举类型，如果未提供则为None
 
-    let r = if params.exchange.is_none() {
-        index_svc::find_all_stock_index().await
-    } else {
-        let exchange = Exchange::from_str(&params.exchange.unwrap()).unwrap();
-        index_svc::find_stock_index_by_exchange(&exchange).await
+    let r = match params.exchange {
+        None => index_svc::find_all_stock_index().await,
+        Some(exchange) => match Exchange::from_str(&exchange) {
+            Ok(exchange) => index_svc::find_stock_index_by_exchange(&exchange).await,
+            Err(e) => Err(e.into()),
+        },
     };
 
-    RespBody::result(&r)
+    error::with_status(&r, RespBody::result(&r))
 }
 
 /// 获取指数的成分股
@@ -55,15 +68,79 @@ pub async fn get_all_index(Query(params): Query<GetIndexParams>) -> impl IntoRes
 pub async fn get_stocks(Path(code): Path<String>) -> impl IntoResponse {
     let r = index_svc::get_constituent_stocks(&code).await;
 
-    RespBody::result(&r).response()
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexPricesParams {
+    /// 开始日期，格式 yyyy-MM-dd 或 yyyyMMdd
+    start: Option<String>,
+    /// 结束日期，格式 yyyy-MM-dd 或 yyyyMMdd
+    end: Option<String>,
+    /// 每只股票只返回时间范围内最近的 limit 条
+    limit: Option<usize>,
+    /// 复权方式：none/qfq/hfq，默认 none
+    adjust: Option<String>,
+    /// 导出格式：csv/xlsx/parquet，默认 csv
+    format: Option<String>,
+}
+
+/// 导出指数全部成分股的日线
+///
+/// 导出格式由 `format` 参数或 `Accept` 请求头指定，默认 CSV。CSV 和 Parquet 每从数据库读出
+/// 一页日线就写出，不需要等全部成分股查询完成。查询失败的成分股代码写在 CSV 的最后一行、
+/// Parquet 的 `failed_codes` 元数据或 XLSX 响应的 `x-export-failed-codes` 头中：
+///
+/// ```text
+/// GET /index/000300/prices?start=2024-01-01&adjust=qfq&format=parquet
+/// ```
+#[get("/index/{code}/prices")]
+pub async fn export_prices(
+    Path(code): Path<String>,
+    headers: HeaderMap,
+    Query(params): Query<IndexPricesParams>,
+) -> impl IntoResponse {
+    let query = match DailyPriceQuery::parse(
+        params.start.as_deref(),
+        params.end.as_deref(),
+        params.limit,
+        None,
+        params.adjust.as_deref(),
+    ) {
+        Ok(query) => query,
+        Err(e) => {
+            let r: Result<(), Box<dyn Error>> = Err(e);
+            return error::with_status(&r, RespBody::result(&r).response());
+        }
+    };
+    let format = match ExportFormat::from_request(params.format.as_deref(), &headers) {
+        Ok(format) => format,
+        Err(e) => {
+            let r: Result<(), Box<dyn Error>> = Err(e.into());
+            return error::with_status(&r, RespBody::result(&r).response());
+        }
+    };
+    let constituents = match index_svc::get_constituent_stocks(&code).await {
+        Ok(constituents) => constituents,
+        Err(e) => {
+            let r: Result<(), Box<dyn Error>> = Err(e);
+            return error::with_status(&r, RespBody::result(&r).response());
+        }
+    };
+    let format = format.unwrap_or(ExportFormat::Csv);
+    let batches = index_svc::get_constituent_daily_prices(constituents, query);
+    export::export_batches(format, PriceKind::Daily, &code, batches).await
 }
 
-/// 后台同步指定指数的股票信息
+/// 同步指定指数的股票信息，`dry_run=true` 时只返回成分股的差异
 #[get("/index/sync/{code}")]
-pub async fn sync(Path(code): Path<String>) -> impl IntoResponse {
-    let r = index_svc::sync_constituents(&code).await;
+pub async fn sync(
+    Path(code): Path<String>,
+    Query(params): Query<IndexSyncParams>,
+) -> impl IntoResponse {
+    let r = index_svc::sync_constituents(&code, params.dry_run.unwrap_or(false)).await;
 
-    RespBody::result(&r).response()
+    error::with_status(&r, RespBody::result(&r).response())
 }
 
 /// 同步所有指数的股票信息
@@ -2,11 +2,9 @@ use crate::index::index_constituent_model::SyncIndexConstituents;
 use crate::index::index_svc::sync_constituents;
 use crate::index::{index_constituent_model, index_dao};
 use crate::exchange::exchange_model::Exchange;
-use application_context::context::application_context::APPLICATION_CONTEXT;
-use application_core::env::property_resolver::PropertyResolver;
+use crate::notifier;
 use application_core::lang::runnable::Runnable;
 use async_trait::async_trait;
-use notification::{Notification, NotificationConfig};
 use tokio::spawn;
 use tracing::info;
 use std::str::FromStr;
@@ -27,8 +25,12 @@ impl Runnable for SyncIndexStocksJob {
         };
         match indexes {
             Ok(indexes) => {
+                // 跳过 `exchange` 表中停用的交易所的指数
+                let indexes = indexes.into_iter().filter(|index| {
+                    Exchange::from_str(&index.exchange).is_ok_and(|exchange| exchange.is_enabled())
+                });
                 for index in indexes {
-                    let constituents = sync_constituents(&index.code).await.unwrap();
+                    let constituents = sync_constituents(&index.code, false).await.unwrap();
                     if constituents.added.is_empty() && constituents.removed.is_empty() {
                         continue;
                     }
@@ -93,19 +95,5 @@ async fn do_notification_index_stocks_changed(
         content
             .push_str(format!("{} {:<5} {}\n", label, stock.stock_name, stock.stock_code).as_str());
     }
-    let application_context = APPLICATION_CONTEXT.read().await;
-    let environment = application_context.get_environment().await;
-    let result = environment.get_property::<NotificationConfig>("notification");
-    match result {
-        None => {}
-        Some(notification_config) => {
-            let url = format!(
-                "{}/send/user/{}",
-                notification_config.url, notification_config.receiver
-            );
-            Notification::create(&title, &content)
-                .send(url.as_str(), notification_config.receiver.as_str())
-                .await
-        }
-    }
+    notifier::send(&title, &content).await
 }
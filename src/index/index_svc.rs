@@ -1,15 +1,21 @@
+use crate::error::AppError;
 use crate::exchange::exchange_model::Exchange;
+use crate::export::PriceBatch;
 use crate::index::index_api::IndexApi;
 use crate::index::index_constituent_model::SyncIndexConstituents;
 use crate::index::{index_constituent_model, index_dao, index_model};
+use crate::stock::stock_model::DailyPriceQuery;
+use crate::stock::stock_svc;
 use application_beans::factory::bean_factory::BeanFactory;
 use application_context::context::application_context::APPLICATION_CONTEXT;
 use database_mysql_seaorm::Dao;
-use sea_orm::{ColumnTrait, ModelTrait, Set, TryIntoModel};
+use futures::{Stream, StreamExt, stream};
+use sea_orm::{ColumnTrait, ModelTrait, Set, TransactionTrait, TryIntoModel};
 use sea_orm::{EntityTrait, QueryFilter};
 use std::error::Error;
 use std::ops::Not;
 use std::str::FromStr;
+use tracing::error;
 
 /// 获取指数的成分股
 ///
@@ -37,16 +43,24 @@ pub async fn get_constituent_stocks(
     Ok(stocks)
 }
 
-pub async fn sync_constituents(index: &str) -> Result<SyncIndexConstituents, Box<dyn Error>> {
+/// 同步指数的成分股
+///
+/// 成分股的读取、新增和移除在同一个数据库事务中完成，任何一步失败都整体回滚，
+/// 并发读取不会看到只更新了一半的成分股。`dry_run` 为 `true` 时只返回差异，不提交变更。
+pub async fn sync_constituents(
+    index: &str,
+    dry_run: bool,
+) -> Result<SyncIndexConstituents, Box<dyn Error>> {
     let index = get_stock_index(index).await?;
     let exchange = Exchange::from_str(&index.exchange)?;
     let stocks = exchange.get_index_stocks(&index.index_code).await?;
 
     let application_context = APPLICATION_CONTEXT.read().await;
     let dao = application_context.get_bean_factory().get::<Dao>();
+    let txn = dao.connection.begin().await?;
     let old_constituents = index_constituent_model::Entity::find()
         .filter(index_constituent_model::Column::IndexCode.eq(&index.code))
-        .all(&dao.connection)
+        .all(&txn)
         .await?;
     let old_constituent_codes = old_constituents
         .iter()
@@ -76,17 +90,23 @@ pub async fn sync_constituents(index: &str) -> Result<SyncIndexConstituents, Box
         }
     }
 
-    if constituents_to_add.is_empty().not() {
-        index_constituent_model::Entity::insert_many(constituents_to_add)
-            .exec(&dao.connection)
-            .await?;
-    }
-    if constituents_to_remove.is_empty().not() {
-        for index_constituent in constituents_to_remove.clone() {
-            index_constituent.delete(&dao.connection).await?;
+    if dry_run {
+        txn.rollback().await?;
+    } else {
+        if constituents_to_add.is_empty().not() {
+            index_constituent_model::Entity::insert_many(constituents_to_add)
+                .exec(&txn)
+                .await?;
+        }
+        if constituents_to_remove.is_empty().not() {
+            for index_constituent in constituents_to_remove.clone() {
+                index_constituent.delete(&txn).await?;
+            }
         }
+        txn.commit().await?;
     }
     Ok(SyncIndexConstituents {
+        dry_run,
         old: old_constituents,
         added: constituents_added,
         removed: constituents_to_remove,
@@ -94,9 +114,8 @@ pub async fn sync_constituents(index: &str) -> Result<SyncIndexConstituents, Box
 }
 
 pub async fn get_stock_index(index: &str) -> Result<index_model::Model, Box<dyn Error>> {
-    let index = index_dao::get_stock_index(index).await?;
-    match index {
-        None => Err("Stock index is not Supported".into()),
+    match index_dao::get_stock_index(index).await? {
+        None => Err(AppError::NotFound(format!("Stock index {} is not supported", index)).into()),
         Some(index) => Ok(index),
     }
 }
@@ -112,3 +131,34 @@ pub async fn find_stock_index_by_exchange(
     let indexes = index_dao::find_by_exchange(exchange).await?;
     Ok(indexes)
 }
+
+/// 逐只查询指数成分股的日线，每只股票的日线按页分为多批
+///
+/// 单只股票查询失败时记录日志，以 `PriceBatch::Failed` 报告失败的代码，不影响其他成分股。
+pub fn get_constituent_daily_prices(
+    constituents: Vec<index_constituent_model::Model>,
+    query: DailyPriceQuery,
+) -> impl Stream<Item = Result<PriceBatch, AppError>> + Send + 'static {
+    stream::iter(constituents).flat_map(move |constituent| {
+        let query = query.clone();
+        let code = constituent.stock_code;
+        stream::once(async move {
+            match stock_svc::stream_stock_prices(&code, &query).await {
+                Ok(prices) => prices
+                    .map(move |prices| {
+                        let batch = prices.and_then(|prices| PriceBatch::daily(&code, &prices));
+                        Ok(batch.unwrap_or_else(|e| {
+                            error!("Export daily prices of {} error {}", code, e);
+                            PriceBatch::Failed(code.clone())
+                        }))
+                    })
+                    .boxed(),
+                Err(e) => {
+                    error!("Export daily prices of {} error {}", code, e);
+                    stream::once(async move { Ok(PriceBatch::Failed(code)) }).boxed()
+                }
+            }
+        })
+        .flatten()
+    })
+}
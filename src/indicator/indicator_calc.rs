@@ -0,0 +1,255 @@
+/// 指标序列，与按时间升序排列的输入等长，数据不足以计算的位置为 `None`
+pub type Series = Vec<Option<f64>>;
+
+/// 简单移动平均
+pub fn sma(values: &[f64], period: usize) -> Series {
+    let mut result = vec![None; values.len()];
+    if period == 0 {
+        return result;
+    }
+    let mut sum = 0.0;
+    for (i, value) in values.iter().enumerate() {
+        sum += value;
+        if i >= period {
+            sum -= values[i - period];
+        }
+        if i + 1 >= period {
+            result[i] = Some(sum / period as f64);
+        }
+    }
+    result
+}
+
+/// 指数移动平均，以第一个值作为初始值
+pub fn ema(values: &[f64], period: usize) -> Series {
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let mut result = Vec::with_capacity(values.len());
+    let mut last: Option<f64> = None;
+    for value in values {
+        let current = match last {
+            Some(last) => alpha * value + (1.0 - alpha) * last,
+            None => *value,
+        };
+        last = Some(current);
+        result.push(Some(current));
+    }
+    result
+}
+
+/// MACD，返回 DIF、DEA 和 MACD 柱
+///
+/// MACD 柱按国内行情软件的习惯取 `2 × (DIF - DEA)`。
+pub fn macd(closes: &[f64], fast: usize, slow: usize, signal: usize) -> (Series, Series, Series) {
+    let fast = ema(closes, fast);
+    let slow = ema(closes, slow);
+    let dif = fast
+        .iter()
+        .zip(&slow)
+        .map(|(fast, slow)| fast.zip(*slow).map(|(fast, slow)| fast - slow))
+        .collect::<Vec<_>>();
+    let dea = ema(
+        &dif.iter()
+            .map(|v| v.unwrap_or_default())
+            .collect::<Vec<_>>(),
+        signal,
+    );
+    let histogram = dif
+        .iter()
+        .zip(&dea)
+        .map(|(dif, dea)| dif.zip(*dea).map(|(dif, dea)| 2.0 * (dif - dea)))
+        .collect::<Vec<_>>();
+    (dif, dea, histogram)
+}
+
+/// 相对强弱指标，采用 Wilder 平滑
+pub fn rsi(closes: &[f64], period: usize) -> Series {
+    let mut result = vec![None; closes.len()];
+    if period == 0 || closes.len() <= period {
+        return result;
+    }
+    let mut gain = 0.0;
+    let mut loss = 0.0;
+    for i in 1..closes.len() {
+        let change = closes[i] - closes[i - 1];
+        let (up, down) = (change.max(0.0), (-change).max(0.0));
+        if i <= period {
+            gain += up / period as f64;
+            loss += down / period as f64;
+            if i < period {
+                continue;
+            }
+        } else {
+            gain = (gain * (period - 1) as f64 + up) / period as f64;
+            loss = (loss * (period - 1) as f64 + down) / period as f64;
+        }
+        result[i] = Some(if gain + loss == 0.0 {
+            50.0
+        } else {
+            100.0 * gain / (gain + loss)
+        });
+    }
+    result
+}
+
+/// 布林带，返回中轨、上轨和下轨，标准差为总体标准差
+pub fn boll(closes: &[f64], period: usize, width: f64) -> (Series, Series, Series) {
+    let mid = sma(closes, period);
+    let mut upper = vec![None; closes.len()];
+    let mut lower = vec![None; closes.len()];
+    for (i, mid) in mid.iter().enumerate() {
+        if let Some(mid) = mid {
+            let window = &closes[i + 1 - period..=i];
+            let variance = window.iter().map(|v| (v - mid).powi(2)).sum::<f64>() / period as f64;
+            let std = variance.sqrt();
+            upper[i] = Some(mid + width * std);
+            lower[i] = Some(mid - width * std);
+        }
+    }
+    (mid, upper, lower)
+}
+
+/// 随机指标 KDJ，K、D 初始值为 50
+pub fn kdj(
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    period: usize,
+    k_period: usize,
+    d_period: usize,
+) -> (Series, Series, Series) {
+    let len = closes.len();
+    let (mut k_values, mut d_values, mut j_values) =
+        (vec![None; len], vec![None; len], vec![None; len]);
+    if period == 0 || k_period == 0 || d_period == 0 {
+        return (k_values, d_values, j_values);
+    }
+    let (mut k, mut d) = (50.0, 50.0);
+    for i in period.saturating_sub(1)..len {
+        let start = i + 1 - period;
+        let highest = highs[start..=i].iter().cloned().fold(f64::MIN, f64::max);
+        let lowest = lows[start..=i].iter().cloned().fold(f64::MAX, f64::min);
+        let rsv = if highest > lowest {
+            (closes[i] - lowest) / (highest - lowest) * 100.0
+        } else {
+            50.0
+        };
+        k = (k * (k_period - 1) as f64 + rsv) / k_period as f64;
+        d = (d * (d_period - 1) as f64 + k) / d_period as f64;
+        k_values[i] = Some(k);
+        d_values[i] = Some(d);
+        j_values[i] = Some(3.0 * k - 2.0 * d);
+    }
+    (k_values, d_values, j_values)
+}
+
+/// 平均真实波幅，采用 Wilder 平滑，第一个值为前 `period` 个真实波幅的平均值
+pub fn atr(highs: &[f64], lows: &[f64], closes: &[f64], period: usize) -> Series {
+    let len = closes.len();
+    let mut result = vec![None; len];
+    if period == 0 || len < period {
+        return result;
+    }
+    let true_ranges = (0..len)
+        .map(|i| {
+            let range = highs[i] - lows[i];
+            if i == 0 {
+                return range;
+            }
+            let pre_close = closes[i - 1];
+            range
+                .max((highs[i] - pre_close).abs())
+                .max((lows[i] - pre_close).abs())
+        })
+        .collect::<Vec<_>>();
+    let mut value = true_ranges[..period].iter().sum::<f64>() / period as f64;
+    result[period - 1] = Some(value);
+    for i in period..len {
+        value = (value * (period - 1) as f64 + true_ranges[i]) / period as f64;
+        result[i] = Some(value);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_series(actual: &Series, expected: &[Option<f64>]) {
+        assert_eq!(actual.len(), expected.len());
+        for (i, (actual, expected)) in actual.iter().zip(expected).enumerate() {
+            match (actual, expected) {
+                (Some(actual), Some(expected)) => assert!(
+                    (actual - expected).abs() < 1e-9,
+                    "index {}: {} != {}",
+                    i,
+                    actual,
+                    expected
+                ),
+                (None, None) => {}
+                _ => panic!("index {}: {:?} != {:?}", i, actual, expected),
+            }
+        }
+    }
+
+    #[test]
+    fn test_sma() {
+        let ma = sma(&[1.0, 2.0, 3.0, 4.0, 5.0], 3);
+        assert_series(&ma, &[None, None, Some(2.0), Some(3.0), Some(4.0)]);
+        assert_series(&sma(&[1.0, 2.0], 0), &[None, None]);
+    }
+
+    #[test]
+    fn test_ema() {
+        // alpha = 2 / (3 + 1) = 0.5
+        let ema = ema(&[1.0, 2.0, 3.0], 3);
+        assert_series(&ema, &[Some(1.0), Some(1.5), Some(2.25)]);
+    }
+
+    #[test]
+    fn test_macd() {
+        // 快线周期为 1 时等于收盘价，慢线为 [1, 1.5, 2.25]
+        let (dif, dea, histogram) = macd(&[1.0, 2.0, 3.0], 1, 3, 3);
+        assert_series(&dif, &[Some(0.0), Some(0.5), Some(0.75)]);
+        assert_series(&dea, &[Some(0.0), Some(0.25), Some(0.5)]);
+        assert_series(&histogram, &[Some(0.0), Some(0.5), Some(0.5)]);
+    }
+
+    #[test]
+    fn test_rsi() {
+        let values = rsi(&[1.0, 2.0, 1.0, 2.0, 3.0], 2);
+        assert_series(&values, &[None, None, Some(50.0), Some(75.0), Some(87.5)]);
+        assert_series(&rsi(&[1.0, 1.0, 1.0], 2), &[None, None, Some(50.0)]);
+        assert_series(&rsi(&[1.0, 2.0], 2), &[None, None]);
+    }
+
+    #[test]
+    fn test_boll() {
+        let (mid, upper, lower) = boll(&[1.0, 3.0, 5.0], 2, 2.0);
+        assert_series(&mid, &[None, Some(2.0), Some(4.0)]);
+        assert_series(&upper, &[None, Some(4.0), Some(6.0)]);
+        assert_series(&lower, &[None, Some(0.0), Some(2.0)]);
+    }
+
+    #[test]
+    fn test_kdj() {
+        let highs = [10.0, 12.0, 11.0];
+        let lows = [8.0, 9.0, 9.0];
+        let closes = [9.0, 11.0, 10.0];
+        let (k, d, j) = kdj(&highs, &lows, &closes, 2, 3, 3);
+        // RSV 依次为 75 和 100 / 3
+        assert_series(&k, &[None, Some(175.0 / 3.0), Some(50.0)]);
+        assert_series(&d, &[None, Some(475.0 / 9.0), Some(1400.0 / 27.0)]);
+        assert_series(&j, &[None, Some(625.0 / 9.0), Some(1250.0 / 27.0)]);
+    }
+
+    #[test]
+    fn test_atr() {
+        // 真实波幅依次为 2、3、2、3，最后一天跳空高开，真实波幅取最高价与前收盘价之差
+        let highs = [10.0, 12.0, 11.0, 13.0];
+        let lows = [8.0, 9.0, 9.0, 12.0];
+        let closes = [9.0, 11.0, 10.0, 12.5];
+        let atr = atr(&highs, &lows, &closes, 2);
+        assert_series(&atr, &[None, Some(2.5), Some(2.25), Some(2.625)]);
+        assert_series(&super::atr(&highs, &lows, &closes, 5), &[None; 4]);
+    }
+}
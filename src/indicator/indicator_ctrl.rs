@@ -0,0 +1,63 @@
+use crate::error;
+use crate::indicator::indicator_model::StockIndicators;
+use crate::indicator::indicator_svc;
+use crate::stock::stock_model::DailyPriceQuery;
+use application_web::response::RespBody;
+use application_web_macros::get;
+use axum::extract::Query;
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use tracing::info;
+
+#[derive(Serialize, Deserialize)]
+struct StockIndicatorParams {
+    code: String,
+    /// 指标名称，多个指标以逗号分隔，如 ma20,macd:12:26:9,rsi14
+    names: String,
+    /// 保留的小数位数，默认 4 位
+    precision: Option<usize>,
+    /// 开始日期，格式 yyyy-MM-dd 或 yyyyMMdd
+    start: Option<String>,
+    /// 结束日期，格式 yyyy-MM-dd 或 yyyyMMdd
+    end: Option<String>,
+    /// 只返回时间范围内最近的 limit 条
+    limit: Option<usize>,
+    /// 排序方向：asc/desc，默认 asc
+    order: Option<String>,
+    /// 复权方式：none/qfq/hfq，默认 none
+    adjust: Option<String>,
+}
+
+/// 获取股票日线的技术指标
+///
+/// 支持 MA、EMA、MACD、RSI、BOLL、KDJ 和 ATR，参数可跟在指标名后，例如获取最近 60 个
+/// 交易日的 20 日均线、MACD 和 14 日 RSI：
+///
+/// ```text
+/// GET /stock/indicators?code=600000.SH&names=ma20,macd,rsi14&limit=60&adjust=qfq
+/// ```
+#[get("/stock/indicators")]
+async fn stock_indicators(Query(params): Query<StockIndicatorParams>) -> impl IntoResponse {
+    info!(
+        "Get stock indicators, code = {}, names = {}",
+        params.code, params.names
+    );
+    let query = match DailyPriceQuery::parse(
+        params.start.as_deref(),
+        params.end.as_deref(),
+        params.limit,
+        params.order.as_deref(),
+        params.adjust.as_deref(),
+    ) {
+        Ok(query) => query,
+        Err(e) => {
+            let r: Result<StockIndicators, Box<dyn Error>> = Err(e);
+            return error::with_status(&r, RespBody::result(&r).response());
+        }
+    };
+    let r =
+        indicator_svc::get_stock_indicators(&params.code, &params.names, params.precision, &query)
+            .await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
@@ -0,0 +1,194 @@
+use crate::error::AppError;
+use crate::stock::stock_model::PriceAdjust;
+use bigdecimal::BigDecimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::str::FromStr;
+
+/// 技术指标及其参数
+///
+/// 名称格式为指标名加可选的参数，周期可直接跟在指标名后，其余参数以冒号分隔，
+/// 例如 `ma20`、`ema:12`、`macd:12:26:9`、`boll:20:2`、`kdj:9:3:3`。
+#[derive(Debug, Clone, PartialEq)]
+pub enum Indicator {
+    /// 简单移动平均
+    Ma(usize),
+    /// 指数移动平均
+    Ema(usize),
+    /// 平滑异同移动平均线
+    Macd {
+        fast: usize,
+        slow: usize,
+        signal: usize,
+    },
+    /// 相对强弱指标
+    Rsi(usize),
+    /// 布林带
+    Boll { period: usize, width: f64 },
+    /// 随机指标
+    Kdj {
+        period: usize,
+        k_period: usize,
+        d_period: usize,
+    },
+    /// 平均真实波幅
+    Atr(usize),
+}
+
+/// 单个指标参数的上限，避免过大的周期
+const MAX_PERIOD: usize = 1000;
+
+impl FromStr for Indicator {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        let mut parts = name.split(':');
+        let head = parts.next().unwrap_or_default();
+        let split = head
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(head.len());
+        let (kind, period) = head.split_at(split);
+        let mut params = Vec::new();
+        if !period.is_empty() {
+            params.push(period);
+        }
+        params.extend(parts);
+
+        let param = |index: usize, default: usize| -> Result<usize, Box<dyn Error>> {
+            let value = match params.get(index) {
+                Some(value) => value.parse::<usize>().map_err(|_| {
+                    AppError::InvalidParam(format!("Invalid indicator parameter: {}", s))
+                })?,
+                None => default,
+            };
+            if value == 0 || value > MAX_PERIOD {
+                return Err(AppError::InvalidParam(format!(
+                    "Indicator parameter out of range: {}",
+                    s
+                ))
+                .into());
+            }
+            Ok(value)
+        };
+
+        let indicator = match kind {
+            "ma" => Indicator::Ma(param(0, 5)?),
+            "ema" => Indicator::Ema(param(0, 12)?),
+            "macd" => Indicator::Macd {
+                fast: param(0, 12)?,
+                slow: param(1, 26)?,
+                signal: param(2, 9)?,
+            },
+            "rsi" => Indicator::Rsi(param(0, 14)?),
+            "boll" => Indicator::Boll {
+                period: param(0, 20)?,
+                width: match params.get(1) {
+                    Some(width) => {
+                        let width = width.parse::<f64>().map_err(|_| {
+                            AppError::InvalidParam(format!("Invalid indicator parameter: {}", s))
+                        })?;
+                        if !width.is_finite() || width <= 0.0 {
+                            return Err(AppError::InvalidParam(format!(
+                                "Indicator parameter out of range: {}",
+                                s
+                            ))
+                            .into());
+                        }
+                        width
+                    }
+                    None => 2.0,
+                },
+            },
+            "kdj" => Indicator::Kdj {
+                period: param(0, 9)?,
+                k_period: param(1, 3)?,
+                d_period: param(2, 3)?,
+            },
+            "atr" => Indicator::Atr(param(0, 14)?),
+            _ => {
+                return Err(AppError::InvalidParam(format!(
+                    "Invalid indicator: {}, expect ma, ema, macd, rsi, boll, kdj or atr",
+                    s
+                ))
+                .into());
+            }
+        };
+        Ok(indicator)
+    }
+}
+
+impl Indicator {
+    /// 指标的完整名称，包含全部参数
+    pub fn name(&self) -> String {
+        match self {
+            Indicator::Ma(period) => format!("ma{}", period),
+            Indicator::Ema(period) => format!("ema{}", period),
+            Indicator::Macd { fast, slow, signal } => format!("macd:{}:{}:{}", fast, slow, signal),
+            Indicator::Rsi(period) => format!("rsi{}", period),
+            Indicator::Boll { period, width } => format!("boll:{}:{}", period, width),
+            Indicator::Kdj {
+                period,
+                k_period,
+                d_period,
+            } => format!("kdj:{}:{}:{}", period, k_period, d_period),
+            Indicator::Atr(period) => format!("atr{}", period),
+        }
+    }
+}
+
+/// 指标在某个交易日的取值
+///
+/// # 属性
+/// - `time`：交易日期时间，与日线的 `time` 一致
+/// - `values`：指标各条线的取值，如 MACD 的 `dif`、`dea`、`macd`，数据不足时为空
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IndicatorPoint {
+    pub time: u64,
+    #[serde(flatten)]
+    pub values: BTreeMap<String, Option<BigDecimal>>,
+}
+
+/// 单个指标的计算结果
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IndicatorSeries {
+    /// 指标完整名称
+    pub name: String,
+    /// 按日线顺序排列的指标取值
+    pub points: Vec<IndicatorPoint>,
+}
+
+/// 股票技术指标
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StockIndicators {
+    /// 股票代码
+    pub code: String,
+    /// 计算指标所用日线的复权方式
+    pub adjust: PriceAdjust,
+    /// 各指标的计算结果
+    pub indicators: Vec<IndicatorSeries>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_boll_width() {
+        assert_eq!(
+            Indicator::from_str("boll:20:2.5").unwrap(),
+            Indicator::Boll {
+                period: 20,
+                width: 2.5
+            }
+        );
+        for s in ["boll:20:0", "boll:20:-1", "boll:20:nan", "boll:20:inf"] {
+            let e = Indicator::from_str(s).unwrap_err();
+            assert!(matches!(
+                e.downcast_ref::<AppError>(),
+                Some(AppError::InvalidParam(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use crate::error::AppError;
+use crate::indicator::indicator_calc;
+use crate::indicator::indicator_calc::Series;
+use crate::indicator::indicator_model::{
+    Indicator, IndicatorPoint, IndicatorSeries, StockIndicators,
+};
+use crate::stock::stock_model::DailyPriceQuery;
+use crate::stock::stock_price_api::StockDailyPrice;
+use crate::stock::stock_svc;
+use bigdecimal::{BigDecimal, ToPrimitive};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::str::FromStr;
+
+/// 默认保留的小数位数
+const DEFAULT_PRECISION: usize = 4;
+
+/// 最多保留的小数位数
+const MAX_PRECISION: usize = 10;
+
+/// 获取股票的技术指标
+///
+/// 指标基于全部历史日线计算，保证均线等指标在查询范围的起点也有足够的数据，
+/// 计算完成后再按查询条件筛选交易日。
+///
+/// # Arguments
+///
+/// * `code` - 股票代码
+/// * `names` - 指标名称，多个指标以逗号分隔，如 `ma20,macd,rsi14`
+/// * `precision` - 保留的小数位数，默认 4 位
+/// * `query` - 日线查询条件
+pub async fn get_stock_indicators(
+    code: &str,
+    names: &str,
+    precision: Option<usize>,
+    query: &DailyPriceQuery,
+) -> Result<StockIndicators, Box<dyn Error>> {
+    let indicators = names
+        .split(',')
+        .filter(|name| !name.trim().is_empty())
+        .map(Indicator::from_str)
+        .collect::<Result<Vec<_>, _>>()?;
+    if indicators.is_empty() {
+        return Err(AppError::InvalidParam("Missing indicator names".to_string()).into());
+    }
+    let precision = precision.unwrap_or(DEFAULT_PRECISION);
+    if precision > MAX_PRECISION {
+        return Err(
+            AppError::InvalidParam(format!("Precision must not exceed {}", MAX_PRECISION)).into(),
+        );
+    }
+
+    let adjusted = stock_svc::get_stock_adjusted_daily_price(code, query.adjust).await?;
+    let prices = adjusted.prices;
+    // 按查询条件筛选出需要返回的交易日，保持查询的排序方向
+    let indexes = prices
+        .iter()
+        .enumerate()
+        .map(|(i, price)| (price.time, i))
+        .collect::<HashMap<_, _>>();
+    let selected = stock_svc::filter_daily_prices(prices.clone(), query)
+        .iter()
+        .filter_map(|price| indexes.get(&price.time).copied())
+        .collect::<Vec<_>>();
+
+    let series = indicators
+        .iter()
+        .map(|indicator| {
+            let lines = compute_indicator(indicator, &prices);
+            let points = selected
+                .iter()
+                .map(|&i| IndicatorPoint {
+                    time: prices[i].time,
+                    values: lines
+                        .iter()
+                        .map(|(key, line)| (key.clone(), to_decimal(line[i], precision)))
+                        .collect::<BTreeMap<_, _>>(),
+                })
+                .collect();
+            IndicatorSeries {
+                name: indicator.name(),
+                points,
+            }
+        })
+        .collect();
+
+    Ok(StockIndicators {
+        code: code.to_string(),
+        adjust: adjusted.adjust,
+        indicators: series,
+    })
+}
+
+/// 计算指标的各条线，返回线名和对应的序列
+pub fn compute_indicator(
+    indicator: &Indicator,
+    prices: &[StockDailyPrice],
+) -> Vec<(String, Series)> {
+    let to_f64 = |value: &BigDecimal| value.to_f64().unwrap_or_default();
+    let closes = prices.iter().map(|p| to_f64(&p.close)).collect::<Vec<_>>();
+    let highs = prices.iter().map(|p| to_f64(&p.high)).collect::<Vec<_>>();
+    let lows = prices.iter().map(|p| to_f64(&p.low)).collect::<Vec<_>>();
+
+    match indicator {
+        Indicator::Ma(period) => vec![(indicator.name(), indicator_calc::sma(&closes, *period))],
+        Indicator::Ema(period) => vec![(indicator.name(), indicator_calc::ema(&closes, *period))],
+        Indicator::Macd { fast, slow, signal } => {
+            let (dif, dea, macd) = indicator_calc::macd(&closes, *fast, *slow, *signal);
+            vec![
+                ("dif".to_string(), dif),
+                ("dea".to_string(), dea),
+                ("macd".to_string(), macd),
+            ]
+        }
+        Indicator::Rsi(period) => vec![(indicator.name(), indicator_calc::rsi(&closes, *period))],
+        Indicator::Boll { period, width } => {
+            let (mid, upper, lower) = indicator_calc::boll(&closes, *period, *width);
+            vec![
+                ("mid".to_string(), mid),
+                ("upper".to_string(), upper),
+                ("lower".to_string(), lower),
+            ]
+        }
+        Indicator::Kdj {
+            period,
+            k_period,
+            d_period,
+        } => {
+            let (k, d, j) =
+                indicator_calc::kdj(&highs, &lows, &closes, *period, *k_period, *d_period);
+            vec![
+                ("k".to_string(), k),
+                ("d".to_string(), d),
+                ("j".to_string(), j),
+            ]
+        }
+        Indicator::Atr(period) => vec![(
+            indicator.name(),
+            indicator_calc::atr(&highs, &lows, &closes, *period),
+        )],
+    }
+}
+
+/// 按精度将指标值转换为十进制数，非有限值返回 `None`
+fn to_decimal(value: Option<f64>, precision: usize) -> Option<BigDecimal> {
+    let value = value.filter(|value| value.is_finite())?;
+    BigDecimal::from_str(&format!("{:.*}", precision, value)).ok()
+}
@@ -0,0 +1,4 @@
+pub mod indicator_calc;
+mod indicator_ctrl;
+pub mod indicator_model;
+pub mod indicator_svc;
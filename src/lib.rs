@@ -1,9 +1,19 @@
+pub mod alert;
+pub mod corporate_action;
 pub mod currency;
 pub mod debt;
+pub mod earnings;
+pub mod error;
 pub mod exchange;
+pub mod export;
 pub mod fund;
+pub mod fundamental;
 pub mod holiday;
 pub mod index;
+pub mod indicator;
+pub mod limit_up;
 pub mod listener;
+pub mod notifier;
 pub mod stock;
 pub mod token;
+pub mod upstream;
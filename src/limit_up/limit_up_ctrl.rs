@@ -0,0 +1,91 @@
+use crate::error;
+use crate::limit_up::limit_up_svc;
+use application_web::response::RespBody;
+use application_web_macros::get;
+use axum::extract::Query;
+use axum::response::IntoResponse;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+#[derive(Serialize, Deserialize)]
+struct SyncLimitUpParams {
+    exchange: String,
+    /// 交易日，格式 yyyy-MM-dd 或 yyyyMMdd，默认当天
+    date: Option<String>,
+}
+
+/// 保存交易所指定交易日的涨停池，可用于补录历史交易日，返回保存的股票数
+///
+/// ```text
+/// GET /limit-up/sync?exchange=SZSE&date=2024-06-03
+/// ```
+#[get("/limit-up/sync")]
+async fn sync(Query(params): Query<SyncLimitUpParams>) -> impl IntoResponse {
+    info!(
+        "Sync limit-up stocks, exchange = {}, date = {:?}",
+        params.exchange, params.date
+    );
+    let r = limit_up_svc::sync_limit_up_stocks(&params.exchange, params.date.as_deref()).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+#[derive(Serialize, Deserialize)]
+struct LadderParams {
+    /// 交易日，默认最近一个已保存涨停池的交易日
+    date: Option<String>,
+    /// 交易所：SSE/SZSE，默认沪深两市
+    exchange: Option<String>,
+}
+
+/// 获取连板梯队
+///
+/// ```text
+/// GET /limit-up/ladder?date=2024-06-03
+/// ```
+#[get("/limit-up/ladder")]
+async fn ladder(Query(params): Query<LadderParams>) -> impl IntoResponse {
+    let r = limit_up_svc::get_ladder(params.date.as_deref(), params.exchange.as_deref()).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+#[derive(Serialize, Deserialize)]
+struct LimitUpRangeParams {
+    /// 开始日期，默认结束日期前 30 天
+    start: Option<String>,
+    /// 结束日期，默认当天
+    end: Option<String>,
+    /// 交易所：SSE/SZSE，默认沪深两市
+    exchange: Option<String>,
+}
+
+/// 统计各行业每日的涨停数
+///
+/// ```text
+/// GET /limit-up/industry?start=2024-05-01&end=2024-05-31
+/// ```
+#[get("/limit-up/industry")]
+async fn industry(Query(params): Query<LimitUpRangeParams>) -> impl IntoResponse {
+    let r = limit_up_svc::get_industry_counts(
+        params.start.as_deref(),
+        params.end.as_deref(),
+        params.exchange.as_deref(),
+    )
+    .await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+/// 统计涨停股在下一交易日的晋级率，按连板数分组
+///
+/// ```text
+/// GET /limit-up/continuation?start=2024-05-01&end=2024-05-31
+/// ```
+#[get("/limit-up/continuation")]
+async fn continuation(Query(params): Query<LimitUpRangeParams>) -> impl IntoResponse {
+    let r = limit_up_svc::get_continuation_rates(
+        params.start.as_deref(),
+        params.end.as_deref(),
+        params.exchange.as_deref(),
+    )
+    .await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
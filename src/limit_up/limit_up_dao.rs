@@ -0,0 +1,73 @@
+use crate::exchange::exchange_model::Exchange;
+use crate::limit_up::limit_up_model;
+use crate::limit_up::limit_up_model::Model;
+use application_beans::factory::bean_factory::BeanFactory;
+use application_context::context::application_context::APPLICATION_CONTEXT;
+use database_mysql_seaorm::Dao;
+use sea_orm::prelude::Date;
+use sea_orm::{ColumnTrait, DbErr, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Select};
+
+/// 用交易所当日的涨停池替换已保存的数据
+pub async fn replace_stocks(
+    date: &Date,
+    exchange: &Exchange,
+    stocks: &[Model],
+) -> Result<(), DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    limit_up_model::Entity::delete_many()
+        .filter(limit_up_model::Column::Date.eq(*date))
+        .filter(limit_up_model::Column::Exchange.eq(exchange.as_ref()))
+        .exec(&dao.connection)
+        .await?;
+    let models = stocks
+        .iter()
+        .map(|stock| stock.clone().into_active_model())
+        .collect::<Vec<_>>();
+    limit_up_model::Entity::insert_many(models)
+        .on_empty_do_nothing()
+        .exec(&dao.connection)
+        .await?;
+    Ok(())
+}
+
+/// 查询日期范围内（包含两端）的涨停股，按日期升序排列
+pub async fn find_stocks_between(
+    start: &Date,
+    end: &Date,
+    exchange: Option<&Exchange>,
+) -> Result<Vec<Model>, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    filter_exchange(limit_up_model::Entity::find(), exchange)
+        .filter(limit_up_model::Column::Date.between(*start, *end))
+        .order_by_asc(limit_up_model::Column::Date)
+        .order_by_desc(limit_up_model::Column::ConsecutiveLimitUpDays)
+        .all(&dao.connection)
+        .await
+}
+
+/// 查询不晚于指定日期的最近一个已保存涨停池的日期
+pub async fn get_last_date(
+    date: &Date,
+    exchange: Option<&Exchange>,
+) -> Result<Option<Date>, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    let stock = filter_exchange(limit_up_model::Entity::find(), exchange)
+        .filter(limit_up_model::Column::Date.lte(*date))
+        .order_by_desc(limit_up_model::Column::Date)
+        .one(&dao.connection)
+        .await?;
+    Ok(stock.map(|stock| stock.date))
+}
+
+fn filter_exchange(
+    select: Select<limit_up_model::Entity>,
+    exchange: Option<&Exchange>,
+) -> Select<limit_up_model::Entity> {
+    match exchange {
+        Some(exchange) => select.filter(limit_up_model::Column::Exchange.eq(exchange.as_ref())),
+        None => select,
+    }
+}
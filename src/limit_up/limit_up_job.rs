@@ -0,0 +1,22 @@
+use crate::exchange::exchange_model::Exchange;
+use crate::limit_up::limit_up_svc::sync_limit_up_stocks;
+use application_core::lang::runnable::Runnable;
+use async_trait::async_trait;
+use tracing::{error, info};
+
+/// 收盘后保存沪深两市当日的涨停池
+pub struct SyncLimitUpStocksJob;
+
+#[async_trait]
+impl Runnable for SyncLimitUpStocksJob {
+    async fn run(&self) {
+        info!("SyncLimitUpStocksJob run ...");
+        for exchange in [Exchange::SSE, Exchange::SZSE] {
+            let r = sync_limit_up_stocks(exchange.as_ref(), None).await;
+            if let Err(e) = r {
+                error!("Sync {} limit-up stocks error {}", exchange.as_ref(), e);
+            }
+        }
+        info!("SyncLimitUpStocksJob end");
+    }
+}
@@ -0,0 +1,133 @@
+use crate::exchange::exchange_model::Exchange;
+use crate::stock::stock_api::UpperLimitStock;
+use sea_orm::entity::prelude::*;
+use sea_orm::{ActiveModelBehavior, DeriveEntityModel, DeriveRelation, EnumIter};
+use serde::{Deserialize, Serialize};
+
+/// 每日涨停池中的股票
+#[derive(Debug, Serialize, Deserialize, Clone, DeriveEntityModel)]
+#[sea_orm(table_name = "limit_up_stock")]
+pub struct Model {
+    /// 交易日期
+    #[sea_orm(primary_key)]
+    pub date: Date,
+    /// 股票代码
+    #[sea_orm(primary_key)]
+    pub code: String,
+    /// 交易所
+    pub exchange: String,
+    /// 股票名称
+    pub name: String,
+    /// 涨跌幅
+    pub change_percent: f64,
+    /// 收盘价
+    pub latest_price: f64,
+    /// 成交额
+    pub turnover_amount: f64,
+    /// 流通市值
+    pub circulating_market_cap: f64,
+    /// 总市值
+    pub total_market_cap: f64,
+    /// 换手率
+    pub turnover_rate: f64,
+    /// 封板资金
+    pub limit_up_order_fund: f64,
+    /// 首次封板时间
+    pub first_limit_up_time: String,
+    /// 最后封板时间
+    pub last_limit_up_time: String,
+    /// 炸板次数
+    pub limit_up_break_count: u32,
+    /// 涨停统计，如 3/5 表示 5 个交易日内 3 次涨停
+    pub limit_up_stats: String,
+    /// 连板数
+    pub consecutive_limit_up_days: u32,
+    /// 所属行业
+    pub industry: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    pub fn from_upper_limit_stock(
+        date: &Date,
+        exchange: &Exchange,
+        stock: &UpperLimitStock,
+    ) -> Self {
+        Model {
+            date: *date,
+            code: stock.stock_code.clone(),
+            exchange: exchange.as_ref().to_string(),
+            name: stock.stock_name.clone(),
+            change_percent: stock.change_percent,
+            latest_price: stock.latest_price,
+            turnover_amount: stock.turnover_amount,
+            circulating_market_cap: stock.circulating_market_cap,
+            total_market_cap: stock.total_market_cap,
+            turnover_rate: stock.turnover_rate,
+            limit_up_order_fund: stock.limit_up_order_fund,
+            first_limit_up_time: stock.first_limit_up_time.clone(),
+            last_limit_up_time: stock.last_limit_up_time.clone(),
+            limit_up_break_count: stock.limit_up_break_count,
+            limit_up_stats: stock.limit_up_stats.clone(),
+            consecutive_limit_up_days: stock.consecutive_limit_up_days,
+            industry: stock.industry.clone(),
+        }
+    }
+}
+
+/// 连板梯队
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LimitUpLadder {
+    pub date: Date,
+    /// 涨停股总数
+    pub count: usize,
+    /// 按连板数从高到低排列的各级梯队
+    pub levels: Vec<LadderLevel>,
+}
+
+/// 连板梯队中的一级
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LadderLevel {
+    /// 连板数
+    pub days: u32,
+    pub count: usize,
+    pub stocks: Vec<Model>,
+}
+
+/// 行业每日涨停数
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IndustryLimitUpCount {
+    pub date: Date,
+    pub industry: String,
+    pub count: usize,
+}
+
+/// 涨停股在下一交易日的晋级率
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LimitUpContinuation {
+    pub date: Date,
+    /// 下一交易日
+    pub next_date: Date,
+    /// 当日涨停股数
+    pub count: usize,
+    /// 下一交易日继续涨停的股数
+    pub continued: usize,
+    /// 晋级率
+    pub rate: f64,
+    /// 按连板数统计的晋级率，从低到高排列
+    pub levels: Vec<ContinuationLevel>,
+}
+
+/// 某一连板数的晋级率
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContinuationLevel {
+    /// 当日连板数
+    pub days: u32,
+    pub count: usize,
+    pub continued: usize,
+    pub rate: f64,
+}
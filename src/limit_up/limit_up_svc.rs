@@ -0,0 +1,353 @@
+use crate::error::AppError;
+use crate::exchange::exchange_model::Exchange;
+use crate::holiday::holiday_svc;
+use crate::limit_up::limit_up_dao;
+use crate::limit_up::limit_up_model::{
+    ContinuationLevel, IndustryLimitUpCount, LadderLevel, LimitUpContinuation, LimitUpLadder, Model,
+};
+use crate::stock::stock_api::StockApi;
+use crate::stock::{stock_model, stock_svc};
+use chrono::{Local, NaiveDate, TimeDelta};
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::str::FromStr;
+use tracing::info;
+
+/// 未指定开始日期时统计最近 30 天
+const DEFAULT_RANGE_DAYS: i64 = 30;
+
+/// 查找下一交易日时最多向后查找的天数
+const MAX_NEXT_TRADE_DAYS: usize = 31;
+
+/// 保存交易所指定交易日的涨停池，未指定日期时为当天，非交易日不保存
+///
+/// 返回保存的涨停股数量。
+pub async fn sync_limit_up_stocks(
+    exchange: &str,
+    date: Option<&str>,
+) -> Result<usize, Box<dyn Error>> {
+    let (exchange, date) = stock_svc::get_limit_pool_date(exchange, date).await?;
+    if !holiday_svc::is_trade_date(&exchange, &date).await? {
+        info!(
+            "{} is not a trade date of {}, skip",
+            date,
+            exchange.as_ref()
+        );
+        return Ok(0);
+    }
+    let stocks = exchange
+        .get_upper_limit_stocks(&date)
+        .await?
+        .iter()
+        .map(|stock| Model::from_upper_limit_stock(&date, &exchange, stock))
+        .collect::<Vec<_>>();
+    limit_up_dao::replace_stocks(&date, &exchange, &stocks).await?;
+    info!(
+        "Save {} limit-up stocks of {} on {}",
+        stocks.len(),
+        exchange.as_ref(),
+        date
+    );
+    Ok(stocks.len())
+}
+
+/// 获取连板梯队，未指定日期时为最近一个已保存涨停池的交易日
+pub async fn get_ladder(
+    date: Option<&str>,
+    exchange: Option<&str>,
+) -> Result<LimitUpLadder, Box<dyn Error>> {
+    let exchange = parse_exchange(exchange)?;
+    let date = match date {
+        Some(date) => stock_model::parse_query_date(date)?,
+        None => limit_up_dao::get_last_date(&today(), exchange.as_ref())
+            .await?
+            .ok_or_else(|| AppError::NotFound("No limit-up stocks saved".to_string()))?,
+    };
+    let stocks = limit_up_dao::find_stocks_between(&date, &date, exchange.as_ref()).await?;
+    Ok(build_ladder(date, stocks))
+}
+
+/// 统计日期范围内各行业每日的涨停数
+///
+/// 结果按日期升序排列，同一天按涨停数从多到少排列。
+pub async fn get_industry_counts(
+    start: Option<&str>,
+    end: Option<&str>,
+    exchange: Option<&str>,
+) -> Result<Vec<IndustryLimitUpCount>, Box<dyn Error>> {
+    let exchange = parse_exchange(exchange)?;
+    let (start, end) = parse_range(start, end)?;
+    let stocks = limit_up_dao::find_stocks_between(&start, &end, exchange.as_ref()).await?;
+
+    let mut counts: BTreeMap<(NaiveDate, String), usize> = BTreeMap::new();
+    for stock in stocks {
+        *counts.entry((stock.date, stock.industry)).or_default() += 1;
+    }
+    let mut result = counts
+        .into_iter()
+        .map(|((date, industry), count)| IndustryLimitUpCount {
+            date,
+            industry,
+            count,
+        })
+        .collect::<Vec<_>>();
+    result.sort_by(|a, b| a.date.cmp(&b.date).then(b.count.cmp(&a.count)));
+    Ok(result)
+}
+
+/// 统计日期范围内每个交易日涨停股在下一交易日的晋级率
+///
+/// 下一交易日继续涨停即为晋级；下一交易日的涨停池尚未保存时，该交易日不计入结果。
+pub async fn get_continuation_rates(
+    start: Option<&str>,
+    end: Option<&str>,
+    exchange: Option<&str>,
+) -> Result<Vec<LimitUpContinuation>, Box<dyn Error>> {
+    let exchange = parse_exchange(exchange)?;
+    let (start, end) = parse_range(start, end)?;
+    // 多查询一段时间，保证范围内最后一个交易日的下一交易日也在结果中
+    let last = end + TimeDelta::days(MAX_NEXT_TRADE_DAYS as i64);
+    let stocks = limit_up_dao::find_stocks_between(&start, &last, exchange.as_ref()).await?;
+    let mut stocks_by_date: BTreeMap<NaiveDate, Vec<Model>> = BTreeMap::new();
+    for stock in stocks {
+        stocks_by_date.entry(stock.date).or_default().push(stock);
+    }
+
+    let mut result = Vec::new();
+    for (date, stocks) in stocks_by_date.range(start..=end) {
+        let next_date = get_next_trade_date(date).await?;
+        if let Some(next_stocks) = stocks_by_date.get(&next_date) {
+            result.push(build_continuation(*date, next_date, stocks, next_stocks));
+        }
+    }
+    Ok(result)
+}
+
+fn today() -> NaiveDate {
+    Local::now()
+        .with_timezone(&Exchange::SSE.time_zone())
+        .date_naive()
+}
+
+fn parse_exchange(exchange: Option<&str>) -> Result<Option<Exchange>, Box<dyn Error>> {
+    match exchange {
+        Some(exchange) => Ok(Some(Exchange::from_str(exchange)?)),
+        None => Ok(None),
+    }
+}
+
+/// 解析日期范围，未指定结束日期时为当天，未指定开始日期时为结束日期前 30 天
+fn parse_range(
+    start: Option<&str>,
+    end: Option<&str>,
+) -> Result<(NaiveDate, NaiveDate), Box<dyn Error>> {
+    let end = match end {
+        Some(end) => stock_model::parse_query_date(end)?,
+        None => today(),
+    };
+    let start = match start {
+        Some(start) => stock_model::parse_query_date(start)?,
+        None => end - TimeDelta::days(DEFAULT_RANGE_DAYS),
+    };
+    if start > end {
+        return Err(AppError::InvalidParam(format!(
+            "Start date {} is after end date {}",
+            start, end
+        ))
+        .into());
+    }
+    Ok((start, end))
+}
+
+/// 沪深两市交易日历相同，按上交所的交易日历查找下一交易日
+async fn get_next_trade_date(date: &NaiveDate) -> Result<NaiveDate, Box<dyn Error>> {
+    let mut next = *date;
+    for _ in 0..MAX_NEXT_TRADE_DAYS {
+        next = next.succ_opt().ok_or("Invalid trade date")?;
+        if holiday_svc::is_trade_date(&Exchange::SSE, &next).await? {
+            return Ok(next);
+        }
+    }
+    Err(format!("No trade date found after {}", date).into())
+}
+
+/// 按连板数从高到低分组，同一级中先封板的排在前面
+fn build_ladder(date: NaiveDate, stocks: Vec<Model>) -> LimitUpLadder {
+    let count = stocks.len();
+    let mut levels: BTreeMap<u32, Vec<Model>> = BTreeMap::new();
+    for stock in stocks {
+        levels
+            .entry(stock.consecutive_limit_up_days)
+            .or_default()
+            .push(stock);
+    }
+    let levels = levels
+        .into_iter()
+        .rev()
+        .map(|(days, mut stocks)| {
+            stocks.sort_by(|a, b| a.first_limit_up_time.cmp(&b.first_limit_up_time));
+            LadderLevel {
+                days,
+                count: stocks.len(),
+                stocks,
+            }
+        })
+        .collect();
+    LimitUpLadder {
+        date,
+        count,
+        levels,
+    }
+}
+
+fn build_continuation(
+    date: NaiveDate,
+    next_date: NaiveDate,
+    stocks: &[Model],
+    next_stocks: &[Model],
+) -> LimitUpContinuation {
+    let next_codes = next_stocks
+        .iter()
+        .map(|stock| stock.code.as_str())
+        .collect::<HashSet<_>>();
+    let mut levels: BTreeMap<u32, (usize, usize)> = BTreeMap::new();
+    for stock in stocks {
+        let level = levels.entry(stock.consecutive_limit_up_days).or_default();
+        level.0 += 1;
+        if next_codes.contains(stock.code.as_str()) {
+            level.1 += 1;
+        }
+    }
+    let continued = levels.values().map(|(_, continued)| continued).sum();
+    LimitUpContinuation {
+        date,
+        next_date,
+        count: stocks.len(),
+        continued,
+        rate: rate(continued, stocks.len()),
+        levels: levels
+            .into_iter()
+            .map(|(days, (count, continued))| ContinuationLevel {
+                days,
+                count,
+                continued,
+                rate: rate(continued, count),
+            })
+            .collect(),
+    }
+}
+
+/// 晋级率，保留 4 位小数
+fn rate(continued: usize, count: usize) -> f64 {
+    if count == 0 {
+        return 0.0;
+    }
+    (continued as f64 / count as f64 * 10000.0).round() / 10000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, day).unwrap()
+    }
+
+    fn stock(day: u32, code: &str, days: u32, first_limit_up_time: &str) -> Model {
+        Model {
+            date: date(day),
+            code: code.to_string(),
+            exchange: "SSE".to_string(),
+            name: code.to_string(),
+            change_percent: 10.0,
+            latest_price: 10.0,
+            turnover_amount: 0.0,
+            circulating_market_cap: 0.0,
+            total_market_cap: 0.0,
+            turnover_rate: 0.0,
+            limit_up_order_fund: 0.0,
+            first_limit_up_time: first_limit_up_time.to_string(),
+            last_limit_up_time: first_limit_up_time.to_string(),
+            limit_up_break_count: 0,
+            limit_up_stats: format!("{}/{}", days, days),
+            consecutive_limit_up_days: days,
+            industry: "银行".to_string(),
+        }
+    }
+
+    fn codes(stocks: &[Model]) -> Vec<&str> {
+        stocks.iter().map(|stock| stock.code.as_str()).collect()
+    }
+
+    #[test]
+    fn test_build_ladder() {
+        let stocks = vec![
+            stock(4, "600001.SH", 1, "101500"),
+            stock(4, "600002.SH", 3, "093000"),
+            stock(4, "600003.SH", 1, "093500"),
+            stock(4, "600004.SH", 2, "140000"),
+        ];
+        let ladder = build_ladder(date(4), stocks);
+        assert_eq!(ladder.count, 4);
+        let days = ladder
+            .levels
+            .iter()
+            .map(|level| (level.days, level.count))
+            .collect::<Vec<_>>();
+        assert_eq!(days, vec![(3, 1), (2, 1), (1, 2)]);
+        // 同一级中先封板的排在前面
+        assert_eq!(
+            codes(&ladder.levels[2].stocks),
+            vec!["600003.SH", "600001.SH"]
+        );
+    }
+
+    #[test]
+    fn test_build_ladder_without_limit_up() {
+        let ladder = build_ladder(date(4), Vec::new());
+        assert_eq!(ladder.date, date(4));
+        assert_eq!(ladder.count, 0);
+        assert!(ladder.levels.is_empty());
+    }
+
+    #[test]
+    fn test_build_continuation() {
+        let stocks = vec![
+            stock(4, "600001.SH", 1, "093000"),
+            stock(4, "600002.SH", 1, "093000"),
+            stock(4, "600003.SH", 1, "093000"),
+            stock(4, "600004.SH", 2, "093000"),
+        ];
+        // 600002 和 600004 在下一交易日跌出涨停池
+        let next_stocks = vec![
+            stock(5, "600001.SH", 2, "093000"),
+            stock(5, "600003.SH", 2, "093000"),
+            stock(5, "600005.SH", 1, "093000"),
+        ];
+        let continuation = build_continuation(date(4), date(5), &stocks, &next_stocks);
+        assert_eq!(continuation.date, date(4));
+        assert_eq!(continuation.next_date, date(5));
+        assert_eq!(continuation.count, 4);
+        assert_eq!(continuation.continued, 2);
+        assert_eq!(continuation.rate, 0.5);
+        let levels = continuation
+            .levels
+            .iter()
+            .map(|level| (level.days, level.count, level.continued, level.rate))
+            .collect::<Vec<_>>();
+        assert_eq!(levels, vec![(1, 3, 2, 0.6667), (2, 1, 0, 0.0)]);
+    }
+
+    #[test]
+    fn test_build_continuation_without_limit_up() {
+        let stocks = vec![stock(4, "600001.SH", 1, "093000")];
+        let continuation = build_continuation(date(4), date(5), &stocks, &[]);
+        assert_eq!(continuation.continued, 0);
+        assert_eq!(continuation.rate, 0.0);
+
+        let continuation = build_continuation(date(4), date(5), &[], &stocks);
+        assert_eq!(continuation.count, 0);
+        assert_eq!(continuation.rate, 0.0);
+        assert!(continuation.levels.is_empty());
+    }
+}
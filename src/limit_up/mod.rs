@@ -0,0 +1,5 @@
+mod limit_up_ctrl;
+pub mod limit_up_dao;
+pub mod limit_up_job;
+pub mod limit_up_model;
+pub mod limit_up_svc;
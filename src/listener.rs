@@ -1,3 +1,9 @@
+use crate::alert::alert_job::EvaluateAlertsJob;
+use crate::corporate_action::corporate_action_job::SyncCorporateActionJob;
+use crate::earnings::earnings_job::SyncEarningsJob;
+use crate::exchange::exchange_registry;
+use crate::fundamental::fundamental_job::SyncFundamentalJob;
+use crate::limit_up::limit_up_job::SyncLimitUpStocksJob;
 use crate::token::token_job::SyncHKEXTokenJob;
 use crate::token::token_svc;
 use application_beans::factory::bean_factory::{BeanFactory, ConfigurableBeanFactory};
@@ -12,6 +18,7 @@ use database_common::connection::DbConnection;
 use database_mysql_seaorm::Dao;
 use redis_io::{Redis, RedisConfig};
 use std::error::Error;
+use tracing::error;
 
 pub struct ApplicationContextInitializedListener {}
 
@@ -55,6 +62,13 @@ impl ApplicationListener for ApplicationStartedEventListener {
         _application: &RustApplication,
         _event: &dyn ApplicationEvent,
     ) -> Result<(), Box<dyn Error>> {
+        // exchange 表不可用时继续使用内置的交易所元数据
+        if let Err(e) = exchange_registry::reload().await {
+            error!(
+                "Reload exchange registry error {}, use built-in exchanges",
+                e
+            );
+        }
         token_svc::reset_hkex_token().await?;
 
         load_jobs().await?;
@@ -79,6 +93,46 @@ async fn load_jobs() -> Result<(), Box<dyn Error>> {
             Box::new(SyncHKEXTokenJob),
         )
         .await;
+    let _ = scheduler
+        .add_job(
+            2,
+            "同步股票公司行动",
+            "0 0 5 * * *",
+            Box::new(SyncCorporateActionJob),
+        )
+        .await;
+    let _ = scheduler
+        .add_job(
+            3,
+            "评估价格提醒规则",
+            "0 * * * * *",
+            Box::new(EvaluateAlertsJob),
+        )
+        .await;
+    let _ = scheduler
+        .add_job(
+            4,
+            "保存沪深两市涨停池",
+            "0 30 15 * * Mon-Fri",
+            Box::new(SyncLimitUpStocksJob),
+        )
+        .await;
+    let _ = scheduler
+        .add_job(
+            5,
+            "同步美股财报日历",
+            "0 0 8 * * *",
+            Box::new(SyncEarningsJob),
+        )
+        .await;
+    let _ = scheduler
+        .add_job(
+            6,
+            "同步股票基本面快照",
+            "0 0 6 * * *",
+            Box::new(SyncFundamentalJob),
+        )
+        .await;
 
     Ok(())
 }
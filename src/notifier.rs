@@ -0,0 +1,25 @@
+use application_context::context::application_context::APPLICATION_CONTEXT;
+use application_core::env::property_resolver::PropertyResolver;
+use notification::{Notification, NotificationConfig};
+use tracing::info;
+
+/// 通过 `notification` 配置的通知服务发送消息，未配置时只记录日志
+pub async fn send(title: &str, content: &str) {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let environment = application_context.get_environment().await;
+    let result = environment.get_property::<NotificationConfig>("notification");
+    match result {
+        None => {
+            info!("Notification not configured, skip {}", title);
+        }
+        Some(notification_config) => {
+            let url = format!(
+                "{}/send/user/{}",
+                notification_config.url, notification_config.receiver
+            );
+            Notification::create(title, content)
+                .send(url.as_str(), notification_config.receiver.as_str())
+                .await
+        }
+    }
+}
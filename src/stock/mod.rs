@@ -1,9 +1,16 @@
 pub mod stock_model;
 
+pub mod stock_adjust;
 pub mod stock_api;
 pub mod stock_price_api;
+pub mod stock_price_provider;
 
 mod stock_cache;
 mod stock_ctrl;
+pub mod stock_daily_price_model;
 mod stock_dao;
+pub mod stock_listing_model;
+pub mod stock_resample;
+mod stock_search;
+mod stock_stream;
 pub mod stock_svc;
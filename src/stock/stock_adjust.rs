@@ -0,0 +1,306 @@
+use crate::corporate_action::corporate_action_model;
+use crate::corporate_action::corporate_action_model::ActionType;
+use crate::stock::stock_daily_price_model::date_to_time;
+use crate::stock::stock_model::PriceAdjust;
+use crate::stock::stock_price_api::StockDailyPrice;
+use bigdecimal::{BigDecimal, Zero};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// 复权价格保留的小数位数
+const ADJUST_SCALE: i64 = 4;
+
+/// 复权因子保留的小数位数
+const FACTOR_SCALE: i64 = 12;
+
+/// 同一除权除息日的公司行动合计，均为每股数值
+#[derive(Default)]
+struct ExRight {
+    /// 现金分红
+    cash: BigDecimal,
+    /// 送转、拆股新增股数
+    bonus: BigDecimal,
+    /// 配股股数
+    rights: BigDecimal,
+    /// 配股股数与配股价的乘积
+    rights_amount: BigDecimal,
+}
+
+impl ExRight {
+    /// 除权除息日的单日因子，即除权参考价与前收盘价之比
+    ///
+    /// 除权参考价为 `(P - D + R × r) / (1 + s + r)`，参数无效时返回 `None`。
+    fn factor(&self, pre_close: &BigDecimal) -> Option<BigDecimal> {
+        let one = BigDecimal::from(1);
+        let numerator = pre_close - &self.cash + &self.rights_amount;
+        let denominator = (&one + &self.bonus + &self.rights) * pre_close;
+        if numerator <= BigDecimal::zero() || denominator <= BigDecimal::zero() {
+            return None;
+        }
+        Some((numerator / denominator).round(FACTOR_SCALE))
+    }
+}
+
+/// 根据公司行动计算每个交易日的前复权因子
+///
+/// 交易日的前复权因子为其后所有除权除息日单日因子的乘积，最新交易日的因子为 1。
+/// 除权除息日不是交易日时，从其后第一个交易日起生效；早于第一条日线的公司行动
+/// 无法确定前收盘价，予以忽略。
+///
+/// # Arguments
+///
+/// * `prices` - 按时间升序排列的不复权日线
+/// * `actions` - 股票的公司行动
+pub fn compute_adjust_factors(
+    prices: &[StockDailyPrice],
+    actions: &[corporate_action_model::Model],
+) -> Vec<BigDecimal> {
+    let closes = prices
+        .iter()
+        .map(|price| (price.time, price.close.clone()))
+        .collect::<Vec<_>>();
+    compute_adjust_factors_by_close(&closes, actions)
+}
+
+/// 根据每个交易日的收盘价计算前复权因子，规则与 [`compute_adjust_factors`] 相同
+///
+/// 只需要交易时间和收盘价，逐批导出日线时不必先查询出全部日线。
+///
+/// # Arguments
+///
+/// * `closes` - 按时间升序排列的交易时间和不复权收盘价
+/// * `actions` - 股票的公司行动
+pub fn compute_adjust_factors_by_close(
+    closes: &[(u64, BigDecimal)],
+    actions: &[corporate_action_model::Model],
+) -> Vec<BigDecimal> {
+    let mut ex_rights: BTreeMap<u64, ExRight> = BTreeMap::new();
+    for action in actions {
+        let ex_right = ex_rights.entry(date_to_time(&action.ex_date)).or_default();
+        let ratio = action.ratio.clone().unwrap_or_default();
+        match ActionType::from_str(&action.action_type) {
+            Ok(ActionType::Dividend) => {
+                ex_right.cash += action.cash.clone().unwrap_or_default();
+            }
+            Ok(ActionType::Bonus) | Ok(ActionType::Split) => ex_right.bonus += ratio,
+            Ok(ActionType::Rights) => {
+                let price = action.price.clone().unwrap_or_default();
+                ex_right.rights_amount += &ratio * price;
+                ex_right.rights += ratio;
+            }
+            Err(_) => {}
+        }
+    }
+
+    // 每个交易日相对前一交易日的单日因子
+    let mut day_factors = vec![BigDecimal::from(1); closes.len()];
+    for (i, window) in closes.windows(2).enumerate() {
+        let ((pre_time, pre_close), (time, _)) = (&window[0], &window[1]);
+        for (_, ex_right) in ex_rights.range(pre_time + 1..=*time) {
+            if let Some(factor) = ex_right.factor(pre_close) {
+                day_factors[i + 1] = (&day_factors[i + 1] * factor).round(FACTOR_SCALE);
+            }
+        }
+    }
+
+    let mut factors = vec![BigDecimal::from(1); closes.len()];
+    for i in (0..closes.len().saturating_sub(1)).rev() {
+        factors[i] = (&factors[i + 1] * &day_factors[i + 1]).round(FACTOR_SCALE);
+    }
+    factors
+}
+
+/// 按复权因子计算复权日线
+///
+/// 前复权直接乘以复权因子；后复权再除以最早交易日的因子，使最早价格与不复权价格一致。
+/// 成交量和成交额不做调整。
+///
+/// # Arguments
+///
+/// * `prices` - 按时间升序排列的不复权日线
+/// * `factors` - 与 `prices` 一一对应的复权因子
+/// * `adjust` - 复权方式
+pub fn apply_adjust_factors(
+    prices: &[StockDailyPrice],
+    factors: &[BigDecimal],
+    adjust: PriceAdjust,
+) -> Vec<StockDailyPrice> {
+    let base = match adjust_base(factors, adjust) {
+        Some(base) => base,
+        None => return prices.to_vec(),
+    };
+    prices
+        .iter()
+        .zip(factors)
+        .map(|(price, factor)| adjust_price(price, &(factor / &base)))
+        .collect()
+}
+
+/// 复权因子的基准，前复权为 1，后复权为最早交易日的因子，不复权或因子无效时返回 `None`
+pub fn adjust_base(factors: &[BigDecimal], adjust: PriceAdjust) -> Option<BigDecimal> {
+    match (adjust, factors.first()) {
+        (PriceAdjust::Qfq, _) => Some(BigDecimal::from(1)),
+        (PriceAdjust::Hfq, Some(first)) if !first.is_zero() => Some(first.clone()),
+        _ => None,
+    }
+}
+
+/// 按已除以基准的复权因子计算一条复权日线，成交量和成交额不做调整
+pub fn adjust_price(price: &StockDailyPrice, factor: &BigDecimal) -> StockDailyPrice {
+    let adjust = |value: &BigDecimal| (value * factor).round(ADJUST_SCALE);
+    StockDailyPrice {
+        open: adjust(&price.open),
+        close: adjust(&price.close),
+        high: adjust(&price.high),
+        low: adjust(&price.low),
+        ..price.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn decimal(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    fn price(date: &str, close: &str) -> StockDailyPrice {
+        StockDailyPrice {
+            time: date_to_time(&NaiveDate::from_str(date).unwrap()),
+            open: decimal(close),
+            close: decimal(close),
+            high: decimal(close),
+            low: decimal(close),
+            volume: None,
+            amount: None,
+        }
+    }
+
+    fn action(
+        ex_date: &str,
+        action_type: ActionType,
+        cash: Option<&str>,
+        ratio: Option<&str>,
+        price: Option<&str>,
+    ) -> corporate_action_model::Model {
+        corporate_action_model::Model {
+            code: "600000.SH".to_string(),
+            ex_date: NaiveDate::from_str(ex_date).unwrap(),
+            action_type: action_type.to_string(),
+            cash: cash.map(decimal),
+            ratio: ratio.map(decimal),
+            price: price.map(decimal),
+            announce_date: None,
+            source: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_cash_dividend_factor() {
+        let prices = vec![
+            price("2024-01-02", "10"),
+            price("2024-01-03", "9.5"),
+            price("2024-01-04", "9.8"),
+        ];
+        let actions = vec![action(
+            "2024-01-03",
+            ActionType::Dividend,
+            Some("1"),
+            None,
+            None,
+        )];
+        let factors = compute_adjust_factors(&prices, &actions);
+        assert_eq!(factors, vec![decimal("0.9"), decimal("1"), decimal("1")]);
+
+        let qfq = apply_adjust_factors(&prices, &factors, PriceAdjust::Qfq);
+        assert_eq!(qfq[0].close, decimal("9"));
+        assert_eq!(qfq[2].close, decimal("9.8"));
+
+        let hfq = apply_adjust_factors(&prices, &factors, PriceAdjust::Hfq);
+        assert_eq!(hfq[0].close, decimal("10"));
+        assert_eq!(hfq[1].close, decimal("10.5556"));
+    }
+
+    #[test]
+    fn test_bonus_and_split_factor() {
+        let prices = vec![
+            price("2024-01-02", "12.5"),
+            price("2024-01-03", "10"),
+            price("2024-01-04", "5"),
+        ];
+        let actions = vec![
+            action("2024-01-03", ActionType::Bonus, None, Some("0.25"), None),
+            action("2024-01-04", ActionType::Split, None, Some("1"), None),
+        ];
+        let factors = compute_adjust_factors(&prices, &actions);
+        assert_eq!(factors[2], decimal("1"));
+        assert_eq!(factors[1], decimal("0.5"));
+        assert_eq!(factors[0], decimal("0.4"));
+
+        let qfq = apply_adjust_factors(&prices, &factors, PriceAdjust::Qfq);
+        assert_eq!(qfq[0].close, decimal("5"));
+        assert_eq!(qfq[1].close, decimal("5"));
+    }
+
+    #[test]
+    fn test_rights_issue_factor() {
+        let prices = vec![price("2024-01-02", "10"), price("2024-01-03", "8.9")];
+        let actions = vec![action(
+            "2024-01-03",
+            ActionType::Rights,
+            None,
+            Some("0.3"),
+            Some("5"),
+        )];
+        // 除权参考价 (10 + 0.3 × 5) / 1.3 ≈ 8.8462
+        let factors = compute_adjust_factors(&prices, &actions);
+        assert_eq!(factors, vec![decimal("0.884615384615"), decimal("1")]);
+    }
+
+    #[test]
+    fn test_same_day_dividend_and_bonus() {
+        let prices = vec![price("2024-01-02", "10"), price("2024-01-03", "6")];
+        let actions = vec![
+            action("2024-01-03", ActionType::Dividend, Some("1"), None, None),
+            action("2024-01-03", ActionType::Bonus, None, Some("0.5"), None),
+        ];
+        let factors = compute_adjust_factors(&prices, &actions);
+        assert_eq!(factors, vec![decimal("0.6"), decimal("1")]);
+    }
+
+    #[test]
+    fn test_ex_date_on_non_trading_day() {
+        // 2024-01-06 为周六，从下一个交易日 2024-01-08 起生效
+        let prices = vec![
+            price("2024-01-04", "10.2"),
+            price("2024-01-05", "10"),
+            price("2024-01-08", "9.1"),
+        ];
+        let actions = vec![action(
+            "2024-01-06",
+            ActionType::Dividend,
+            Some("1"),
+            None,
+            None,
+        )];
+        let factors = compute_adjust_factors(&prices, &actions);
+        assert_eq!(factors, vec![decimal("0.9"), decimal("0.9"), decimal("1")]);
+    }
+
+    #[test]
+    fn test_actions_outside_prices_are_ignored() {
+        let prices = vec![price("2024-01-02", "10"), price("2024-01-03", "10")];
+        let actions = vec![
+            action("2023-12-29", ActionType::Dividend, Some("1"), None, None),
+            action("2024-01-02", ActionType::Dividend, Some("1"), None, None),
+            action("2024-02-01", ActionType::Dividend, Some("1"), None, None),
+        ];
+        let factors = compute_adjust_factors(&prices, &actions);
+        assert_eq!(factors, vec![decimal("1"), decimal("1")]);
+
+        let none = apply_adjust_factors(&prices, &factors, PriceAdjust::None);
+        assert_eq!(none[0].close, decimal("10"));
+    }
+}
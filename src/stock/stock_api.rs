@@ -1,19 +1,23 @@
+use crate::error;
+use crate::error::AppError;
 use crate::exchange::exchange_model::Exchange;
-use crate::index::index_api::IndexApi;
+use crate::index::index_api;
 use crate::stock::stock_dao;
 use crate::stock::stock_model::{Model, StockKind};
+use crate::upstream;
 use application_context::context::application_context::APPLICATION_CONTEXT;
 use application_core::env::property_resolver::PropertyResolver;
 use async_trait::async_trait;
-use calamine::{Reader, Xls, Xlsx, open_workbook};
+use calamine::{Data, Range, Reader, open_workbook_auto_from_rs};
+use chrono::NaiveDate;
 use rand::{RngExt, rng};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashSet;
 use std::error::Error;
 use std::fs::File;
-use std::io::copy;
+use std::io::{Cursor, copy};
 use std::path::Path;
-use std::str::FromStr;
 use tempfile::tempdir;
 use tracing::info;
 use util::request::Request;
@@ -38,13 +42,100 @@ pub struct UpperLimitStock {
     pub industry: String,
 }
 
+/// 跌停股
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LowerLimitStock {
+    pub serial_no: u32,
+    pub stock_code: String,
+    pub stock_name: String,
+    pub change_percent: f64,
+    pub latest_price: f64,
+    pub turnover_amount: f64,
+    pub circulating_market_cap: f64,
+    pub total_market_cap: f64,
+    /// 动态市盈率
+    pub pe_ratio: f64,
+    pub turnover_rate: f64,
+    /// 封单资金
+    pub limit_down_order_fund: f64,
+    pub last_limit_down_time: String,
+    /// 板上成交额
+    pub limit_down_turnover_amount: f64,
+    pub consecutive_limit_down_days: u32,
+    /// 开板次数
+    pub limit_down_open_count: u32,
+    pub industry: String,
+}
+
+/// 炸板股，盘中涨停后打开涨停板的股票
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrokenLimitStock {
+    pub serial_no: u32,
+    pub stock_code: String,
+    pub stock_name: String,
+    pub change_percent: f64,
+    pub latest_price: f64,
+    pub limit_up_price: f64,
+    pub turnover_amount: f64,
+    pub circulating_market_cap: f64,
+    pub total_market_cap: f64,
+    pub turnover_rate: f64,
+    /// 涨速
+    pub speed: f64,
+    pub first_limit_up_time: String,
+    pub limit_up_break_count: u32,
+    pub limit_up_stats: String,
+    /// 振幅
+    pub amplitude: f64,
+    pub industry: String,
+}
+
+/// 昨日涨停股，上一交易日涨停的股票在当前交易日的表现
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviousUpperLimitStock {
+    pub serial_no: u32,
+    pub stock_code: String,
+    pub stock_name: String,
+    pub change_percent: f64,
+    pub latest_price: f64,
+    pub limit_up_price: f64,
+    pub turnover_amount: f64,
+    pub circulating_market_cap: f64,
+    pub total_market_cap: f64,
+    pub turnover_rate: f64,
+    /// 涨速
+    pub speed: f64,
+    /// 振幅
+    pub amplitude: f64,
+    pub previous_last_limit_up_time: String,
+    pub previous_consecutive_limit_up_days: u32,
+    pub limit_up_stats: String,
+    pub industry: String,
+}
+
 #[async_trait]
 pub trait StockApi {
     async fn get_stocks(&self) -> Result<Vec<Model>, Box<dyn Error>>;
+    /// 获取交易日的涨停池
     async fn get_upper_limit_stocks(
         &self,
-        exchange: &str,
+        date: &NaiveDate,
     ) -> Result<Vec<UpperLimitStock>, Box<dyn Error>>;
+    /// 获取交易日的跌停池
+    async fn get_lower_limit_stocks(
+        &self,
+        date: &NaiveDate,
+    ) -> Result<Vec<LowerLimitStock>, Box<dyn Error>>;
+    /// 获取交易日的炸板池
+    async fn get_broken_limit_stocks(
+        &self,
+        date: &NaiveDate,
+    ) -> Result<Vec<BrokenLimitStock>, Box<dyn Error>>;
+    /// 获取交易日的昨日涨停股池
+    async fn get_previous_upper_limit_stocks(
+        &self,
+        date: &NaiveDate,
+    ) -> Result<Vec<PreviousUpperLimitStock>, Box<dyn Error>>;
 }
 
 #[async_trait]
@@ -53,9 +144,12 @@ impl StockApi for Exchange {
         match self {
             Exchange::SSE => {
                 let dir = tempdir()?;
-                let url = "http://query.sse.com.cn/sseQuery/commonExcelDd.do?sqlId=COMMON_SSE_CP_GPJCTPZ_GPLB_GP_L&type=inParams&CSRC_CODE=&STOCK_CODE=&REG_PROVINCE=&STOCK_TYPE=1,8&COMPANY_STATUS=2,4,5,7,8";
+                let url = format!(
+                    "{}/sseQuery/commonExcelDd.do?sqlId=COMMON_SSE_CP_GPJCTPZ_GPLB_GP_L&type=inParams&CSRC_CODE=&STOCK_CODE=&REG_PROVINCE=&STOCK_TYPE=1,8&COMPANY_STATUS=2,4,5,7,8",
+                    upstream::get_url("stock.api.sh.query").await?
+                );
                 let path = dir.path().join("sh_stocks.xls");
-                download(url, path.as_path()).await?;
+                download(&url, path.as_path()).await?;
                 let path1 = path.as_path();
                 let stocks = read_stocks_from_excel(path1, self, "股票", 0, 2)?;
                 Ok(stocks)
@@ -63,7 +157,8 @@ impl StockApi for Exchange {
             Exchange::SZSE => {
                 let dir = tempdir()?;
                 let url = format!(
-                    "https://www.szse.cn/api/report/ShowReport?SHOWTYPE=xlsx&CATALOGID=1110&TABKEY=tab1&random={}",
+                    "{}/api/report/ShowReport?SHOWTYPE=xlsx&CATALOGID=1110&TABKEY=tab1&random={}",
+                    upstream::get_url("stock.api.sz.baseurl").await?,
                     rng().random::<f64>()
                 );
                 let path = dir.path().join("sz_stocks.xlsx");
@@ -72,177 +167,299 @@ impl StockApi for Exchange {
                 Ok(stocks)
             }
             Exchange::HKEX => get_stock_from_hk().await,
-            Exchange::NASDAQ => {
-                let mut stocks = Vec::new();
-                let nasdaq100_index_stocks = self.get_index_stocks("nasdaq100").await?;
-                let spx500_index_stocks = self.get_index_stocks("SPX").await?;
-                let mut stock_codes = Vec::new();
-                let mut add_unique_stocks = |index_stocks: Vec<Model>| {
-                    for stock in index_stocks {
-                        if !stock_codes.contains(&stock.code) {
-                            stock_codes.push(stock.code.clone());
-                            stocks.push(stock);
-                        }
-                    }
-                };
-
-                add_unique_stocks(nasdaq100_index_stocks);
-                add_unique_stocks(spx500_index_stocks);
-                Ok(stocks)
+            Exchange::NASDAQ | Exchange::NYSE | Exchange::AMEX => {
+                // 纳斯达克100和标普500成分股中在本交易所上市的股票
+                let stocks = get_us_stocks().await?;
+                Ok(stocks
+                    .into_iter()
+                    .filter(|stock| stock.exchange == self.as_ref())
+                    .collect())
             }
+            Exchange::BSE => get_stock_from_bse().await,
         }
     }
 
     async fn get_upper_limit_stocks(
         &self,
-        exchange: &str,
+        date: &NaiveDate,
     ) -> Result<Vec<UpperLimitStock>, Box<dyn Error>> {
-        // 只支持 SSE 和 SZSE
-        let exchange_enum = Exchange::from_str(exchange)?;
-        match exchange_enum {
-            Exchange::SSE | Exchange::SZSE => {}
-            _ => {
-                return Err(format!("Only support SSE and SZSE, got {}", exchange).into());
-            }
-        }
-        let application_context = APPLICATION_CONTEXT.read().await;
-        let environment = application_context.get_environment().await;
-        let base_url = environment
-            .get_property::<String>("stock.api.akshare.baseurl")
-            .unwrap();
-        let date = chrono::Local::now().format("%Y%m%d").to_string();
-        // 从 akshare 接口获取涨停池数据
-        let url = format!("{}/api/public/stock_zt_pool_em?date={}", base_url, date);
-        let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert(
-            "User-Agent",
-            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36"
-                .parse()?,
-        );
-        headers.insert("Referer", "https://emweb.securities.xinhua.com/".parse()?);
-        let client = reqwest::Client::builder().build()?;
-        let response = client.get(&url).headers(headers).send().await?;
-        if !response.status().is_success() {
-            return Err(
-                format!("Failed to fetch upper limit stocks: {}", response.status()).into(),
-            );
+        check_limit_pool_exchange(self)?;
+        let json = get_akshare_limit_pool("stock_zt_pool_em", date).await?;
+        let stocks = parse_upper_limit_stocks(&json, self)?;
+        Ok(retain_listed_stocks(stocks, |stock| &stock.stock_code).await)
+    }
+
+    async fn get_lower_limit_stocks(
+        &self,
+        date: &NaiveDate,
+    ) -> Result<Vec<LowerLimitStock>, Box<dyn Error>> {
+        check_limit_pool_exchange(self)?;
+        let json = get_akshare_limit_pool("stock_zt_pool_dtgc_em", date).await?;
+        let stocks = parse_lower_limit_stocks(&json, self)?;
+        Ok(retain_listed_stocks(stocks, |stock| &stock.stock_code).await)
+    }
+
+    async fn get_broken_limit_stocks(
+        &self,
+        date: &NaiveDate,
+    ) -> Result<Vec<BrokenLimitStock>, Box<dyn Error>> {
+        check_limit_pool_exchange(self)?;
+        let json = get_akshare_limit_pool("stock_zt_pool_zbgc_em", date).await?;
+        let stocks = parse_broken_limit_stocks(&json, self)?;
+        Ok(retain_listed_stocks(stocks, |stock| &stock.stock_code).await)
+    }
+
+    async fn get_previous_upper_limit_stocks(
+        &self,
+        date: &NaiveDate,
+    ) -> Result<Vec<PreviousUpperLimitStock>, Box<dyn Error>> {
+        check_limit_pool_exchange(self)?;
+        let json = get_akshare_limit_pool("stock_zt_pool_previous_em", date).await?;
+        let stocks = parse_previous_upper_limit_stocks(&json, self)?;
+        Ok(retain_listed_stocks(stocks, |stock| &stock.stock_code).await)
+    }
+}
+
+/// 涨跌停股池只支持 SSE 和 SZSE
+fn check_limit_pool_exchange(exchange: &Exchange) -> Result<(), AppError> {
+    match exchange {
+        Exchange::SSE | Exchange::SZSE => Ok(()),
+        _ => Err(AppError::UnsupportedExchange(format!(
+            "Only support SSE and SZSE, got {}",
+            exchange.as_ref()
+        ))),
+    }
+}
+
+/// 请求 akshare 的涨跌停股池接口，股池包含沪深两市的全部股票
+async fn get_akshare_limit_pool(name: &str, date: &NaiveDate) -> Result<Value, Box<dyn Error>> {
+    let url = format!(
+        "{}/api/public/{}?date={}",
+        upstream::get_url("stock.api.akshare.baseurl").await?,
+        name,
+        date.format("%Y%m%d")
+    );
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        "User-Agent",
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36"
+            .parse()?,
+    );
+    headers.insert("Referer", "https://emweb.securities.xinhua.com/".parse()?);
+    let client = reqwest::Client::builder().build()?;
+    let response = client.get(&url).headers(headers).send().await?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to fetch {}: {}", name, response.status()).into());
+    }
+    Ok(response.json().await?)
+}
+
+/// 只保留 `stock` 表中存在的股票，股池中其他交易所的股票加上本交易所的后缀后不存在，会被过滤掉
+async fn retain_listed_stocks<T: Send>(stocks: Vec<T>, code: fn(&T) -> &String) -> Vec<T> {
+    let mut listed = Vec::new();
+    for stock in stocks {
+        if let Ok(Some(_stock)) = stock_dao::get_stock_by_code(code(&stock)).await {
+            listed.push(stock);
         }
-        let json_data: Value = response.json().await?;
-
-        // 解析 akshare 返回的数据
-        let data_array = json_data
-            .as_array()
-            .ok_or("Expected an array in response")?;
-
-        let mut upper_limit_stocks = Vec::new();
-        for item in data_array {
-            // 提取股票代码（不含交易所后缀）
-            let stock_code = item
-                .get("代码")
-                .and_then(|v| v.as_str())
-                .ok_or("Missing stock code")?
-                .to_string();
-
-            // 拼接交易所代码后缀
-            let full_code = format!("{}{}", stock_code, exchange_enum.stock_code_suffix());
-
-            // 通过 stock_dao 检查股票是否存在于交易所
-            if let Ok(Some(_stock)) = stock_dao::get_stock_by_code(&full_code).await {
-                // 股票存在，添加到结果中
-                let upper_limit_stock = UpperLimitStock {
-                    serial_no: item.get("序号").and_then(|v| v.as_u64()).unwrap_or(0) as u32,
-                    stock_code: full_code, // 使用拼接后的代码
-                    stock_name: item
-                        .get("名称")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string(),
-                    change_percent: item.get("涨跌幅").and_then(|v| v.as_f64()).unwrap_or(0.0),
-                    latest_price: item.get("最新价").and_then(|v| v.as_f64()).unwrap_or(0.0),
-                    turnover_amount: item.get("成交额").and_then(|v| v.as_f64()).unwrap_or(0.0),
-                    circulating_market_cap: item
-                        .get("流通市值")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0),
-                    total_market_cap: item.get("总市值").and_then(|v| v.as_f64()).unwrap_or(0.0),
-                    turnover_rate: item.get("换手率").and_then(|v| v.as_f64()).unwrap_or(0.0),
-                    limit_up_order_fund: item
-                        .get("封板资金")
-                        .and_then(|v| v.as_f64())
-                        .unwrap_or(0.0),
-                    first_limit_up_time: item
-                        .get("首次封板时间")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string(),
-                    last_limit_up_time: item
-                        .get("最后封板时间")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string(),
-                    limit_up_break_count: item.get("炸板次数").and_then(|v| v.as_u64()).unwrap_or(0)
-                        as u32,
-                    limit_up_stats: item
-                        .get("涨停统计")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string(),
-                    consecutive_limit_up_days: item
-                        .get("连板数")
-                        .and_then(|v| v.as_u64())
-                        .unwrap_or(0) as u32,
-                    industry: item
-                        .get("所属行业")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string(),
-                };
-                upper_limit_stocks.push(upper_limit_stock);
+    }
+    listed
+}
+
+fn pool_items(json: &Value) -> Result<&Vec<Value>, AppError> {
+    json.as_array()
+        .ok_or_else(|| AppError::parse("akshare", "array"))
+}
+
+/// 股池中的股票代码，加上交易所的后缀
+fn pool_stock_code(item: &Value, exchange: &Exchange) -> Result<String, AppError> {
+    let code = error::str_field(item, "代码", "akshare")?;
+    Ok(format!("{}{}", code, exchange.stock_code_suffix()))
+}
+
+fn pool_f64(item: &Value, name: &str) -> f64 {
+    item.get(name).and_then(|v| v.as_f64()).unwrap_or(0.0)
+}
+
+fn pool_u32(item: &Value, name: &str) -> u32 {
+    item.get(name).and_then(|v| v.as_u64()).unwrap_or(0) as u32
+}
+
+fn pool_str(item: &Value, name: &str) -> String {
+    item.get(name)
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string()
+}
+
+/// 解析 akshare 涨停池
+fn parse_upper_limit_stocks(
+    json: &Value,
+    exchange: &Exchange,
+) -> Result<Vec<UpperLimitStock>, AppError> {
+    let mut stocks = Vec::new();
+    for item in pool_items(json)? {
+        stocks.push(UpperLimitStock {
+            serial_no: pool_u32(item, "序号"),
+            stock_code: pool_stock_code(item, exchange)?,
+            stock_name: pool_str(item, "名称"),
+            change_percent: pool_f64(item, "涨跌幅"),
+            latest_price: pool_f64(item, "最新价"),
+            turnover_amount: pool_f64(item, "成交额"),
+            circulating_market_cap: pool_f64(item, "流通市值"),
+            total_market_cap: pool_f64(item, "总市值"),
+            turnover_rate: pool_f64(item, "换手率"),
+            limit_up_order_fund: pool_f64(item, "封板资金"),
+            first_limit_up_time: pool_str(item, "首次封板时间"),
+            last_limit_up_time: pool_str(item, "最后封板时间"),
+            limit_up_break_count: pool_u32(item, "炸板次数"),
+            limit_up_stats: pool_str(item, "涨停统计"),
+            consecutive_limit_up_days: pool_u32(item, "连板数"),
+            industry: pool_str(item, "所属行业"),
+        });
+    }
+    Ok(stocks)
+}
+
+/// 解析 akshare 跌停池
+fn parse_lower_limit_stocks(
+    json: &Value,
+    exchange: &Exchange,
+) -> Result<Vec<LowerLimitStock>, AppError> {
+    let mut stocks = Vec::new();
+    for item in pool_items(json)? {
+        stocks.push(LowerLimitStock {
+            serial_no: pool_u32(item, "序号"),
+            stock_code: pool_stock_code(item, exchange)?,
+            stock_name: pool_str(item, "名称"),
+            change_percent: pool_f64(item, "涨跌幅"),
+            latest_price: pool_f64(item, "最新价"),
+            turnover_amount: pool_f64(item, "成交额"),
+            circulating_market_cap: pool_f64(item, "流通市值"),
+            total_market_cap: pool_f64(item, "总市值"),
+            pe_ratio: pool_f64(item, "动态市盈率"),
+            turnover_rate: pool_f64(item, "换手率"),
+            limit_down_order_fund: pool_f64(item, "封单资金"),
+            last_limit_down_time: pool_str(item, "最后封板时间"),
+            limit_down_turnover_amount: pool_f64(item, "板上成交额"),
+            consecutive_limit_down_days: pool_u32(item, "连续跌停"),
+            limit_down_open_count: pool_u32(item, "开板次数"),
+            industry: pool_str(item, "所属行业"),
+        });
+    }
+    Ok(stocks)
+}
+
+/// 解析 akshare 炸板池
+fn parse_broken_limit_stocks(
+    json: &Value,
+    exchange: &Exchange,
+) -> Result<Vec<BrokenLimitStock>, AppError> {
+    let mut stocks = Vec::new();
+    for item in pool_items(json)? {
+        stocks.push(BrokenLimitStock {
+            serial_no: pool_u32(item, "序号"),
+            stock_code: pool_stock_code(item, exchange)?,
+            stock_name: pool_str(item, "名称"),
+            change_percent: pool_f64(item, "涨跌幅"),
+            latest_price: pool_f64(item, "最新价"),
+            limit_up_price: pool_f64(item, "涨停价"),
+            turnover_amount: pool_f64(item, "成交额"),
+            circulating_market_cap: pool_f64(item, "流通市值"),
+            total_market_cap: pool_f64(item, "总市值"),
+            turnover_rate: pool_f64(item, "换手率"),
+            speed: pool_f64(item, "涨速"),
+            first_limit_up_time: pool_str(item, "首次封板时间"),
+            limit_up_break_count: pool_u32(item, "炸板次数"),
+            limit_up_stats: pool_str(item, "涨停统计"),
+            amplitude: pool_f64(item, "振幅"),
+            industry: pool_str(item, "所属行业"),
+        });
+    }
+    Ok(stocks)
+}
+
+/// 解析 akshare 昨日涨停股池
+fn parse_previous_upper_limit_stocks(
+    json: &Value,
+    exchange: &Exchange,
+) -> Result<Vec<PreviousUpperLimitStock>, AppError> {
+    let mut stocks = Vec::new();
+    for item in pool_items(json)? {
+        stocks.push(PreviousUpperLimitStock {
+            serial_no: pool_u32(item, "序号"),
+            stock_code: pool_stock_code(item, exchange)?,
+            stock_name: pool_str(item, "名称"),
+            change_percent: pool_f64(item, "涨跌幅"),
+            latest_price: pool_f64(item, "最新价"),
+            limit_up_price: pool_f64(item, "涨停价"),
+            turnover_amount: pool_f64(item, "成交额"),
+            circulating_market_cap: pool_f64(item, "流通市值"),
+            total_market_cap: pool_f64(item, "总市值"),
+            turnover_rate: pool_f64(item, "换手率"),
+            speed: pool_f64(item, "涨速"),
+            amplitude: pool_f64(item, "振幅"),
+            previous_last_limit_up_time: pool_str(item, "昨日封板时间"),
+            previous_consecutive_limit_up_days: pool_u32(item, "昨日连板数"),
+            limit_up_stats: pool_str(item, "涨停统计"),
+            industry: pool_str(item, "所属行业"),
+        });
+    }
+    Ok(stocks)
+}
+
+/// 获取纳斯达克100和标普500的全部成分股，按主板上市地区分纳斯达克、纽交所和美交所
+///
+/// 三个美股交易所共用同一份上市地映射和成分股列表，同步时只需获取一次。
+pub(crate) async fn get_us_stocks() -> Result<Vec<Model>, Box<dyn Error>> {
+    let venues = index_api::get_us_listing_venues().await?;
+    let mut stocks = Vec::new();
+    let mut stock_codes = HashSet::new();
+    for index_code in ["nasdaq100", "SPX"] {
+        for stock in index_api::get_us_index_stocks(index_code, &venues).await? {
+            if stock_codes.insert(stock.code.clone()) {
+                stocks.push(stock);
             }
         }
-
-        Ok(upper_limit_stocks)
     }
+    Ok(stocks)
 }
 
 async fn get_stock_from_hk() -> Result<Vec<Model>, Box<dyn Error>> {
     let url = format!(
-        "https://www.hsi.com.hk/data/schi/rt/index-series/hsi/constituents.do?{}",
+        "{}/data/schi/rt/index-series/hsi/constituents.do?{}",
+        upstream::get_url("stock.api.hsi.baseurl").await?,
         rng().random_range(1000..9999)
     );
     let response = Request::get_response(&url).await?;
     let data: Value = response.json().await?;
-    let index_series_list = data.get("indexSeriesList").unwrap().as_array().unwrap();
-    let index_series = index_series_list.first().unwrap().as_object().unwrap();
-    let index_list = index_series.get("indexList").unwrap().as_array().unwrap();
-    let index_stocks = index_list
-        .first()
-        .unwrap()
-        .get("constituentContent")
-        .unwrap()
-        .as_array()
-        .unwrap();
+    Ok(index_api::parse_hsi_constituents(&data, &Exchange::HKEX)?)
+}
+
+/// 北交所股票列表，北交所官网没有可直接下载的列表文件，取自 akshare
+async fn get_stock_from_bse() -> Result<Vec<Model>, Box<dyn Error>> {
+    let url = format!(
+        "{}/api/public/stock_info_bj_name_code",
+        upstream::get_url("stock.api.akshare.baseurl").await?
+    );
+    info!("Get BSE stocks from url = {}", url);
+    let response = Request::get_response(&url).await?;
+    let json: Value = response.json().await?;
+    Ok(parse_bse_stocks(&json, &Exchange::BSE)?)
+}
+
+/// 解析 akshare 北交所股票列表
+fn parse_bse_stocks(json: &Value, exchange: &Exchange) -> Result<Vec<Model>, AppError> {
     let mut stocks = Vec::new();
-    for index_stock in index_stocks {
-        let code = index_stock
-            .get("code")
-            .unwrap()
-            .as_str()
-            .unwrap()
-            .to_string();
-        let stock = Model {
-            code: format!("{}{}", code, Exchange::HKEX.stock_code_suffix()),
-            name: index_stock
-                .get("constituentName")
-                .unwrap()
-                .as_str()
-                .unwrap()
-                .to_string(),
-            exchange: Exchange::HKEX.as_ref().to_string(),
-            stock_type: "Stock".to_string(),
-            stock_code: code,
-        };
-        stocks.push(stock);
+    for item in pool_items(json)? {
+        let stock_code = error::str_field(item, "证券代码", "akshare")?;
+        stocks.push(Model {
+            code: format!("{}{}", stock_code, exchange.stock_code_suffix()),
+            name: error::str_field(item, "证券简称", "akshare")?.to_string(),
+            exchange: exchange.as_ref().to_string(),
+            stock_type: StockKind::Stock.to_string(),
+            stock_code: stock_code.to_string(),
+            delisted_at: None,
+        });
     }
     Ok(stocks)
 }
@@ -277,7 +494,7 @@ pub async fn get_earnings_surprise(code: &str) -> Result<Vec<EarningsSurpriseRow
     let environment = application_context.get_environment().await;
     let base_url = environment
         .get_property::<String>("stock.api.nasdaq.baseurl")
-        .unwrap();
+        .ok_or_else(|| AppError::missing_property("stock.api.nasdaq.baseurl"))?;
     let url = format!("{}/api/company/{}/earnings-surprise", base_url, code);
 
     info!("Fetching earnings surprise data from {}", url);
@@ -308,13 +525,13 @@ pub async fn get_earnings_surprise(code: &str) -> Result<Vec<EarningsSurpriseRow
 pub async fn download(url: &str, path: &Path) -> Result<(), Box<dyn Error>> {
     let mut headers = reqwest::header::HeaderMap::new();
     headers.insert("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36".parse()?);
-    headers.insert("X-Requested-With", "XMLHttpRequest".parse().unwrap());
+    headers.insert("X-Requested-With", "XMLHttpRequest".parse()?);
     headers.insert(
         "Referer",
         "http://www.sse.com.cn/assortment/stock/list/share/".parse()?,
     );
-    headers.insert("Connection", "keep-alive".parse().unwrap());
-    let client = reqwest::Client::builder().build().unwrap();
+    headers.insert("Connection", "keep-alive".parse()?);
+    let client = reqwest::Client::builder().build()?;
     let response = client.get(url).headers(headers).send().await;
     match response {
         Ok(response) => {
@@ -327,6 +544,7 @@ pub async fn download(url: &str, path: &Path) -> Result<(), Box<dyn Error>> {
     }
 }
 
+/// 读取交易所下载的股票列表，按文件内容识别 xls 或 xlsx 格式
 fn read_stocks_from_excel(
     path: &Path,
     exchange: &Exchange,
@@ -334,16 +552,24 @@ fn read_stocks_from_excel(
     stock_code_index: usize,
     stock_name_index: usize,
 ) -> Result<Vec<Model>, Box<dyn Error>> {
-    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
-    let mut stocks = Vec::new();
-    let data = if ext == "xls" {
-        let mut excel_xls: Xls<_> = open_workbook(path)?;
-        excel_xls.worksheet_range(sheet_name)?
-    } else {
-        let mut excel_xlsx: Xlsx<_> = open_workbook(path)?;
-        excel_xlsx.worksheet_range(sheet_name)?
-    };
+    let mut workbook = open_workbook_auto_from_rs(Cursor::new(std::fs::read(path)?))?;
+    let data = workbook.worksheet_range(sheet_name)?;
+    Ok(read_stocks_from_range(
+        &data,
+        exchange,
+        stock_code_index,
+        stock_name_index,
+    ))
+}
 
+/// 读取交易所股票列表表格，第一行为表头
+fn read_stocks_from_range(
+    data: &Range<Data>,
+    exchange: &Exchange,
+    stock_code_index: usize,
+    stock_name_index: usize,
+) -> Vec<Model> {
+    let mut stocks = Vec::new();
     for (i, row) in data.rows().enumerate() {
         if i == 0 {
             continue;
@@ -354,7 +580,117 @@ fn read_stocks_from_excel(
             exchange: exchange.as_ref().to_string(),
             stock_type: StockKind::Stock.to_string(),
             stock_code: row[stock_code_index].to_string(),
+            delisted_at: None,
         });
     }
-    Ok(stocks)
+    stocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(path: &str) -> String {
+        format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), path)
+    }
+
+    #[test]
+    fn test_read_stocks_from_szse_excel() {
+        let path = fixture("szse/stock_list.xlsx");
+        let stocks =
+            read_stocks_from_excel(Path::new(&path), &Exchange::SZSE, "A股列表", 4, 5).unwrap();
+        assert_eq!(stocks.len(), 2);
+        assert_eq!(stocks[0].code, "000001.SZ");
+        assert_eq!(stocks[0].stock_code, "000001");
+        assert_eq!(stocks[0].name, "平安银行");
+        assert_eq!(stocks[0].exchange, "SZSE");
+        assert_eq!(stocks[0].stock_type, "Stock");
+        assert_eq!(stocks[1].code, "300750.SZ");
+    }
+
+    #[test]
+    fn test_read_stocks_from_sse_excel() {
+        let path = fixture("sse/stock_list.xlsx");
+        let stocks =
+            read_stocks_from_excel(Path::new(&path), &Exchange::SSE, "股票", 0, 2).unwrap();
+        assert_eq!(stocks.len(), 2);
+        assert_eq!(stocks[0].code, "600000.SH");
+        assert_eq!(stocks[0].name, "浦发银行");
+        assert_eq!(stocks[1].code, "688981.SH");
+        assert_eq!(stocks[1].name, "中芯国际");
+    }
+
+    #[test]
+    fn test_parse_bse_stocks() {
+        let json = serde_json::from_str(include_str!(
+            "../../tests/fixtures/akshare/stock_info_bj_name_code.json"
+        ))
+        .unwrap();
+        let stocks = parse_bse_stocks(&json, &Exchange::BSE).unwrap();
+        assert_eq!(stocks.len(), 2);
+        assert_eq!(stocks[0].code, "430017.BJ");
+        assert_eq!(stocks[0].stock_code, "430017");
+        assert_eq!(stocks[0].name, "星昊医药");
+        assert_eq!(stocks[0].exchange, "BSE");
+        assert_eq!(stocks[1].code, "920002.BJ");
+    }
+
+    #[test]
+    fn test_parse_upper_limit_stocks() {
+        let json = serde_json::from_str(include_str!("../../tests/fixtures/akshare/zt_pool.json"))
+            .unwrap();
+        let stocks = parse_upper_limit_stocks(&json, &Exchange::SSE).unwrap();
+        assert_eq!(stocks.len(), 1);
+        assert_eq!(stocks[0].stock_code, "600000.SH");
+        assert_eq!(stocks[0].stock_name, "浦发银行");
+        assert_eq!(stocks[0].first_limit_up_time, "093512");
+        assert_eq!(stocks[0].consecutive_limit_up_days, 1);
+        assert_eq!(stocks[0].industry, "银行");
+    }
+
+    #[test]
+    fn test_parse_lower_limit_stocks() {
+        let json =
+            serde_json::from_str(include_str!("../../tests/fixtures/akshare/dtgc_pool.json"))
+                .unwrap();
+        let stocks = parse_lower_limit_stocks(&json, &Exchange::SZSE).unwrap();
+        assert_eq!(stocks.len(), 2);
+        assert_eq!(stocks[0].stock_code, "000004.SZ");
+        assert_eq!(stocks[0].consecutive_limit_down_days, 2);
+        assert_eq!(stocks[0].limit_down_open_count, 1);
+        // 缺少的数值字段按 0 处理
+        assert_eq!(stocks[1].pe_ratio, 0.0);
+    }
+
+    #[test]
+    fn test_parse_broken_limit_stocks() {
+        let json =
+            serde_json::from_str(include_str!("../../tests/fixtures/akshare/zbgc_pool.json"))
+                .unwrap();
+        let stocks = parse_broken_limit_stocks(&json, &Exchange::SSE).unwrap();
+        assert_eq!(stocks.len(), 1);
+        assert_eq!(stocks[0].stock_code, "601127.SH");
+        assert_eq!(stocks[0].limit_up_price, 93.5);
+        assert_eq!(stocks[0].limit_up_break_count, 3);
+    }
+
+    #[test]
+    fn test_parse_previous_upper_limit_stocks() {
+        let json = serde_json::from_str(include_str!(
+            "../../tests/fixtures/akshare/previous_zt_pool.json"
+        ))
+        .unwrap();
+        let stocks = parse_previous_upper_limit_stocks(&json, &Exchange::SSE).unwrap();
+        assert_eq!(stocks.len(), 1);
+        assert_eq!(stocks[0].stock_code, "600000.SH");
+        assert_eq!(stocks[0].previous_consecutive_limit_up_days, 1);
+        assert_eq!(stocks[0].previous_last_limit_up_time, "100203");
+    }
+
+    #[test]
+    fn test_parse_limit_pool_missing_code() {
+        let json = serde_json::json!([{"序号": 1, "名称": "浦发银行"}]);
+        let e = parse_upper_limit_stocks(&json, &Exchange::SSE).unwrap_err();
+        assert_eq!(e, AppError::parse("akshare", "代码"));
+    }
 }
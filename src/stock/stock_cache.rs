@@ -1,5 +1,6 @@
+use crate::error::AppError;
 use crate::stock::stock_model::Model as Stock;
-use crate::stock::stock_price_api::StockDailyPrice;
+use crate::stock::stock_price_api::{StockDailyPrice, StockMinutePrice};
 use crate::stock::{stock_dao, stock_model};
 use application_cache::CacheManager;
 use redis::Commands;
@@ -20,7 +21,9 @@ pub async fn get_stock(code: &str) -> Result<stock_model::Model, Box<dyn Error>>
     let stock = stock_dao::get_stock_by_code(code).await?;
 
     if stock.is_none() {
-        return Err(format!("Stock {} not found or not supported", code).into());
+        return Err(
+            AppError::NotFound(format!("Stock {} not found or not supported", code)).into(),
+        );
     }
     let stock = stock.unwrap();
     // 将查询结果存入缓存
@@ -66,3 +69,46 @@ pub async fn set_stock_daily_prices(
     )?;
     Ok(())
 }
+
+pub async fn get_stock_intraday_prices(
+    stock: &Stock,
+) -> Result<Vec<StockMinutePrice>, Box<dyn Error>> {
+    let client = Redis::get_client();
+    let mut con = client.get_connection()?;
+    let key = "Stock:Price:K:M:".to_string() + &stock.code;
+    let value = con.get::<&str, Option<String>>(&key)?;
+
+    // 缓存命中，直接返回结果
+    if let Some(value) = value {
+        info!("Get stock intraday price from cache, code = {}", stock.code);
+        let prices: Vec<StockMinutePrice> = serde_json::from_str(&value)?;
+        return Ok(prices);
+    }
+
+    Ok(Vec::new())
+}
+
+pub async fn set_stock_intraday_prices(
+    stock: &Stock,
+    prices: &Vec<StockMinutePrice>,
+    seconds: usize,
+) -> Result<(), Box<dyn Error>> {
+    let client = Redis::get_client();
+    let mut con = client.get_connection()?;
+    let key = "Stock:Price:K:M:".to_string() + &stock.code;
+    con.set_ex::<&str, String, String>(&key, serde_json::to_string(&prices)?, seconds as u64)?;
+    Ok(())
+}
+
+/// 删除股票信息、日线和分钟线的缓存，股票代码或交易所变化后调用
+pub async fn evict_stock(code: &str) -> Result<(), Box<dyn Error>> {
+    let client = Redis::get_client();
+    let mut con = client.get_connection()?;
+    let keys = [
+        format!("Stock:{}", code),
+        "Stock:Price:K:D:".to_string() + code,
+        "Stock:Price:K:M:".to_string() + code,
+    ];
+    con.del::<_, ()>(&keys[..])?;
+    Ok(())
+}
@@ -1,9 +1,20 @@
-use crate::stock::stock_svc;
+use crate::error;
+use crate::export;
+use crate::export::{ExportFormat, PriceBatch, PriceKind, PriceRow};
+use crate::stock::stock_model::{AdjustedDailyPrices, DailyPriceQuery};
+use crate::stock::stock_stream::QuoteSubscription;
+use crate::stock::{stock_search, stock_stream, stock_svc};
 use application_web::response::RespBody;
-use application_web_macros::get;
+use application_web_macros::{get, post};
+use axum::Json;
 use axum::extract::Query;
-use axum::response::IntoResponse;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
+use std::error::Error;
 use tracing::info;
 
 #[derive(Serialize, Deserialize)]
@@ -16,7 +27,7 @@ struct StockParams {
 async fn stock_base_info(Query(params): Query<StockParams>) -> impl IntoResponse {
     info!("Get stock base info, code = {}", params.code);
     let r = stock_svc::get_stock(&params.code).await;
-    RespBody::result(&r).response()
+    error::with_status(&r, RespBody::result(&r).response())
 }
 
 /// 获取股票当前价格
@@ -24,31 +35,356 @@ async fn stock_base_info(Query(params): Query<StockParams>) -> impl IntoResponse
 async fn stock_price(Query(params): Query<StockParams>) -> impl IntoResponse {
     info!("Query stock price, code = {}", params.code);
     let r = stock_svc::get_stock_price(&params.code).await;
-    RespBody::result(&r).response()
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+#[derive(Serialize, Deserialize)]
+struct StockSearchParams {
+    /// 关键字：代码、名称、全拼或拼音首字母
+    q: String,
+    /// 最多返回的结果数，默认 20，最大 100
+    limit: Option<usize>,
+}
+
+/// 按代码、名称和拼音搜索股票、指数和基金，结果按匹配程度排序
+///
+/// ```text
+/// GET /stock/search?q=gzmt
+/// ```
+#[get("/stock/search")]
+async fn search(Query(params): Query<StockSearchParams>) -> impl IntoResponse {
+    info!("Search stocks, q = {}", params.q);
+    let r = stock_search::search(&params.q, params.limit).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+#[derive(Serialize, Deserialize)]
+struct StockPricesParams {
+    /// 股票代码，多个代码以逗号分隔
+    codes: String,
+}
+
+/// 批量获取股票当前价格
+///
+/// 结果按请求顺序返回，单只股票查询失败时该项的 `error` 为失败原因：
+///
+/// ```text
+/// GET /stock/prices?codes=600000.SH,000001.SZ,AAPL.NS
+/// ```
+#[get("/stock/prices")]
+async fn stock_prices(Query(params): Query<StockPricesParams>) -> impl IntoResponse {
+    let codes = split_codes(&params.codes);
+    info!("Query stock prices, count = {}", codes.len());
+    let r = stock_svc::get_stock_prices_batch(&codes).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+#[derive(Serialize, Deserialize)]
+struct StockPricesBody {
+    codes: Vec<String>,
+}
+
+/// 批量获取股票当前价格，代码较多时使用 POST 避免 URL 过长
+///
+/// ```text
+/// POST /stock/prices
+/// {"codes": ["600000.SH", "000001.SZ", "AAPL.NS"]}
+/// ```
+#[post("/stock/prices")]
+async fn stock_prices_post(Json(body): Json<StockPricesBody>) -> impl IntoResponse {
+    info!("Query stock prices, count = {}", body.codes.len());
+    let r = stock_svc::get_stock_prices_batch(&body.codes).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+fn split_codes(codes: &str) -> Vec<String> {
+    codes
+        .split(',')
+        .map(|code| code.trim().to_string())
+        .filter(|code| !code.is_empty())
+        .collect()
+}
+
+/// 订阅失败时按错误类型返回状态码
+fn subscribe_error(e: Box<dyn Error>) -> Response {
+    let r: Result<(), Box<dyn Error>> = Err(e);
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+/// 通过 Server-Sent Events 推送股票实时行情
+///
+/// 连接建立后先推送已有的最新价格，之后交易时段内价格变化时推送，休市时暂停推送：
+///
+/// ```text
+/// GET /stock/price/stream?codes=600000.SH,000001.SZ,AAPL.NS
+/// ```
+#[get("/stock/price/stream")]
+async fn stock_price_stream(Query(params): Query<StockPricesParams>) -> Response {
+    let codes = split_codes(&params.codes);
+    info!("Subscribe stock price stream, count = {}", codes.len());
+    match stock_stream::subscribe(&codes).await {
+        Ok(subscription) => {
+            let stream = subscription
+                .into_stream()
+                .map(|price| Event::default().event("price").json_data(&price));
+            Sse::new(stream)
+                .keep_alive(KeepAlive::default())
+                .into_response()
+        }
+        Err(e) => subscribe_error(e),
+    }
+}
+
+/// 通过 WebSocket 推送股票实时行情，每条消息为一只股票价格的 JSON
+///
+/// ```text
+/// GET /stock/price/ws?codes=600000.SH,000001.SZ,AAPL.NS
+/// ```
+#[get("/stock/price/ws")]
+async fn stock_price_ws(ws: WebSocketUpgrade, Query(params): Query<StockPricesParams>) -> Response {
+    let codes = split_codes(&params.codes);
+    info!("Subscribe stock price websocket, count = {}", codes.len());
+    match stock_stream::subscribe(&codes).await {
+        Ok(subscription) => ws.on_upgrade(move |socket| push_prices(socket, subscription)),
+        Err(e) => subscribe_error(e),
+    }
+}
+
+async fn push_prices(mut socket: WebSocket, subscription: QuoteSubscription) {
+    let mut prices = Box::pin(subscription.into_stream());
+    loop {
+        tokio::select! {
+            price = prices.next() => {
+                let Some(price) = price else { break };
+                let text = match serde_json::to_string(&price) {
+                    Ok(text) => text,
+                    Err(_) => continue,
+                };
+                if socket.send(Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                _ => {}
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StockDailyPriceParams {
+    code: String,
+    /// 开始日期，格式 yyyy-MM-dd 或 yyyyMMdd
+    start: Option<String>,
+    /// 结束日期，格式 yyyy-MM-dd 或 yyyyMMdd
+    end: Option<String>,
+    /// 只返回时间范围内最近的 limit 条
+    limit: Option<usize>,
+    /// 排序方向：asc/desc，默认 asc
+    order: Option<String>,
+    /// 复权方式：none/qfq/hfq，默认 none
+    adjust: Option<String>,
+    /// 导出格式：csv/xlsx/parquet，默认返回 JSON
+    format: Option<String>,
 }
 
 /// 获取股票日线价格
+///
+/// 支持按日期范围、条数和排序方向过滤，并可选择复权方式，返回结果中包含实际采用的
+/// 复权方式。例如获取最近 60 条前复权日线：
+///
+/// ```text
+/// GET /stock/price/daily?code=600000.SH&limit=60&adjust=qfq
+/// ```
+///
+/// 通过 `format` 参数或 `Accept` 请求头（`text/csv` 等）可以导出为 CSV、XLSX 或 Parquet 文件：
+///
+/// ```text
+/// GET /stock/price/daily?code=600000.SH&adjust=qfq&format=parquet
+/// ```
 #[get("/stock/price/daily")]
-async fn stock_daily_price(Query(params): Query<StockParams>) -> impl IntoResponse {
-    let r = stock_svc::get_stock_prices(&params.code).await;
-    RespBody::result(&r).response()
+async fn stock_daily_price(
+    headers: HeaderMap,
+    Query(params): Query<StockDailyPriceParams>,
+) -> impl IntoResponse {
+    let query = match DailyPriceQuery::parse(
+        params.start.as_deref(),
+        params.end.as_deref(),
+        params.limit,
+        params.order.as_deref(),
+        params.adjust.as_deref(),
+    ) {
+        Ok(query) => query,
+        Err(e) => {
+            let r: Result<AdjustedDailyPrices, Box<dyn Error>> = Err(e);
+            return error::with_status(&r, RespBody::result(&r).response());
+        }
+    };
+    let format = match ExportFormat::from_request(params.format.as_deref(), &headers) {
+        Ok(format) => format,
+        Err(e) => {
+            let r: Result<AdjustedDailyPrices, Box<dyn Error>> = Err(e.into());
+            return error::with_status(&r, RespBody::result(&r).response());
+        }
+    };
+    if let Some(format) = format {
+        return match stock_svc::stream_stock_prices(&params.code, &query).await {
+            Ok(prices) => {
+                let code = params.code.clone();
+                let batches = prices
+                    .map(move |prices| prices.and_then(|prices| PriceBatch::daily(&code, &prices)));
+                export::export_batches(format, PriceKind::Daily, &params.code, batches).await
+            }
+            Err(e) => {
+                let r: Result<AdjustedDailyPrices, Box<dyn Error>> = Err(e);
+                error::with_status(&r, RespBody::result(&r).response())
+            }
+        };
+    }
+    let r = stock_svc::get_stock_prices(&params.code, &query).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+#[derive(Serialize, Deserialize)]
+struct StockKlineParams {
+    code: String,
+    /// 周期：W/M/Q/Y
+    period: String,
+    /// 开始日期，格式 yyyy-MM-dd 或 yyyyMMdd
+    start: Option<String>,
+    /// 结束日期，格式 yyyy-MM-dd 或 yyyyMMdd
+    end: Option<String>,
+    /// 只返回时间范围内最近的 limit 条
+    limit: Option<usize>,
+    /// 排序方向：asc/desc，默认 asc
+    order: Option<String>,
+    /// 复权方式：none/qfq/hfq，默认 none
+    adjust: Option<String>,
+}
+
+/// 获取股票周线、月线、季线或年线
+///
+/// 由日线按交易日历合并，K 线时间为周期内最后一个交易日，例如获取最近 12 条月线：
+///
+/// ```text
+/// GET /stock/price/kline?code=600000.SH&period=M&limit=12
+/// ```
+#[get("/stock/price/kline")]
+async fn stock_kline(Query(params): Query<StockKlineParams>) -> impl IntoResponse {
+    info!(
+        "Get stock kline, code = {}, period = {}",
+        params.code, params.period
+    );
+    let query = match DailyPriceQuery::parse(
+        params.start.as_deref(),
+        params.end.as_deref(),
+        params.limit,
+        params.order.as_deref(),
+        params.adjust.as_deref(),
+    ) {
+        Ok(query) => query,
+        Err(e) => {
+            let r: Result<AdjustedDailyPrices, Box<dyn Error>> = Err(e);
+            return error::with_status(&r, RespBody::result(&r).response());
+        }
+    };
+    let r = stock_svc::get_stock_kline(&params.code, &params.period, &query).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+#[derive(Serialize, Deserialize)]
+struct StockIntradayPriceParams {
+    code: String,
+    /// 周期：1m/5m/15m/60m，默认 1m
+    interval: Option<String>,
+    /// 导出格式：csv/xlsx/parquet，默认返回 JSON
+    format: Option<String>,
+}
+
+/// 获取股票当日分钟线，可以像日线一样导出为文件
+///
+/// ```text
+/// GET /stock/price/intraday?code=600000.SH&interval=5m
+/// GET /stock/price/intraday?code=600000.SH&interval=5m&format=csv
+/// ```
+#[get("/stock/price/intraday")]
+async fn stock_intraday_price(
+    headers: HeaderMap,
+    Query(params): Query<StockIntradayPriceParams>,
+) -> impl IntoResponse {
+    info!("Get stock intraday price, code = {}", params.code);
+    let format = match ExportFormat::from_request(params.format.as_deref(), &headers) {
+        Ok(format) => format,
+        Err(e) => {
+            let r: Result<(), Box<dyn Error>> = Err(e.into());
+            return error::with_status(&r, RespBody::result(&r).response());
+        }
+    };
+    let interval = params.interval.unwrap_or_else(|| "1m".to_string());
+    let r = stock_svc::get_stock_intraday_prices(&params.code, &interval).await;
+    if let (Some(format), Ok(prices)) = (format, &r) {
+        let rows = prices
+            .iter()
+            .map(|price| PriceRow::from_minute(&params.code, price))
+            .collect::<Result<Vec<_>, _>>();
+        let name = format!("{}-{}", params.code, interval);
+        return match rows {
+            Ok(rows) => export::export_rows(format, PriceKind::Minute, &name, rows),
+            Err(e) => {
+                let r: Result<(), Box<dyn Error>> = Err(e);
+                error::with_status(&r, RespBody::result(&r).response())
+            }
+        };
+    }
+    error::with_status(&r, RespBody::result(&r).response())
 }
 
 #[get("/stock/earnings-surprise")]
 async fn earnings_surprise(Query(params): Query<StockParams>) -> impl IntoResponse {
     info!("Get earnings surprise, code = {}", params.code);
     let r = stock_svc::get_earnings_surprise(&params.code).await;
-    RespBody::result(&r).response()
+    error::with_status(&r, RespBody::result(&r).response())
 }
 
 #[derive(Serialize, Deserialize)]
-struct UpperLimitStocksParams {
+struct LimitPoolParams {
     pub exchange: String,
+    /// 交易日，格式 yyyy-MM-dd 或 yyyyMMdd，默认当天
+    pub date: Option<String>,
 }
 
-
+/// 获取沪深交易所的涨停池
+///
+/// ```text
+/// GET /stock/upper-limit?exchange=SSE&date=2024-06-03
+/// ```
 #[get("/stock/upper-limit")]
-async fn get_uppper_limit_stocks(Query(params): Query<UpperLimitStocksParams>) -> impl IntoResponse {
-    let r = stock_svc::get_uppper_limit_stocks(&params.exchange).await;
-    RespBody::result(&r).response()
+async fn get_uppper_limit_stocks(Query(params): Query<LimitPoolParams>) -> impl IntoResponse {
+    let r = stock_svc::get_uppper_limit_stocks(&params.exchange, params.date.as_deref()).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+/// 获取沪深交易所的跌停池
+#[get("/stock/lower-limit")]
+async fn get_lower_limit_stocks(Query(params): Query<LimitPoolParams>) -> impl IntoResponse {
+    let r = stock_svc::get_lower_limit_stocks(&params.exchange, params.date.as_deref()).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+/// 获取沪深交易所的炸板池
+#[get("/stock/broken-limit")]
+async fn get_broken_limit_stocks(Query(params): Query<LimitPoolParams>) -> impl IntoResponse {
+    let r = stock_svc::get_broken_limit_stocks(&params.exchange, params.date.as_deref()).await;
+    error::with_status(&r, RespBody::result(&r).response())
+}
+
+/// 获取沪深交易所的昨日涨停股池，即上一交易日涨停的股票在 `date` 当天的表现
+#[get("/stock/previous-upper-limit")]
+async fn get_previous_upper_limit_stocks(
+    Query(params): Query<LimitPoolParams>,
+) -> impl IntoResponse {
+    let r =
+        stock_svc::get_previous_upper_limit_stocks(&params.exchange, params.date.as_deref()).await;
+    error::with_status(&r, RespBody::result(&r).response())
 }
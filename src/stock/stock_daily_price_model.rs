@@ -0,0 +1,86 @@
+use crate::stock::stock_price_api::StockDailyPrice;
+use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
+use sea_orm::entity::prelude::*;
+use sea_orm::{ActiveModelBehavior, DeriveEntityModel, DeriveRelation, EnumIter};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// 股票日线价格
+#[derive(Debug, Serialize, Deserialize, Clone, DeriveEntityModel)]
+#[sea_orm(table_name = "stock_daily_price")]
+pub struct Model {
+    /// 股票代码
+    #[sea_orm(primary_key)]
+    pub code: String,
+    /// 交易日期
+    #[sea_orm(primary_key)]
+    pub date: Date,
+    /// 开盘价
+    pub open: BigDecimal,
+    /// 收盘价
+    pub close: BigDecimal,
+    /// 最高价
+    pub high: BigDecimal,
+    /// 最低价
+    pub low: BigDecimal,
+    /// 成交量
+    pub volume: Option<BigDecimal>,
+    /// 成交额
+    pub amount: Option<BigDecimal>,
+    /// 数据来源
+    pub source: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// 由接口返回的日线价格创建入库记录
+    pub fn from_stock_daily_price(
+        code: &str,
+        price: &StockDailyPrice,
+        source: &str,
+    ) -> Result<Model, Box<dyn Error>> {
+        Ok(Model {
+            code: code.to_string(),
+            date: time_to_date(price.time)?,
+            open: price.open.clone(),
+            close: price.close.clone(),
+            high: price.high.clone(),
+            low: price.low.clone(),
+            volume: price.volume.clone(),
+            amount: price.amount.clone(),
+            source: source.to_string(),
+        })
+    }
+
+    /// 转换为接口返回的日线价格，时间沿用 `yyyyMMdd093000` 格式
+    pub fn to_stock_daily_price(&self) -> StockDailyPrice {
+        StockDailyPrice {
+            time: date_to_time(&self.date),
+            open: self.open.clone(),
+            close: self.close.clone(),
+            high: self.high.clone(),
+            low: self.low.clone(),
+            volume: self.volume.clone(),
+            amount: self.amount.clone(),
+        }
+    }
+}
+
+/// 将 `yyyyMMddHHmmss` 格式的时间转换为日期
+pub fn time_to_date(time: u64) -> Result<NaiveDate, Box<dyn Error>> {
+    let date = (time / 1_000_000).to_string();
+    Ok(NaiveDate::parse_from_str(&date, "%Y%m%d")?)
+}
+
+/// 将日期转换为 `yyyyMMdd093000` 格式的时间
+pub fn date_to_time(date: &NaiveDate) -> u64 {
+    date.format("%Y%m%d093000")
+        .to_string()
+        .parse::<u64>()
+        .unwrap_or_default()
+}
@@ -1,27 +1,267 @@
+use crate::alert::alert_model;
+use crate::corporate_action::corporate_action_model;
+use crate::earnings::earnings_model;
 use crate::exchange::exchange_model::Exchange;
-use crate::stock::stock_model;
-use crate::stock::stock_model::StockKind;
+use crate::fundamental::fundamental_model;
+use crate::index::index_constituent_model;
+use crate::stock::stock_listing_model::StockRelocation;
+use crate::stock::stock_model::{SortOrder, StockKind};
+use crate::stock::{stock_daily_price_model, stock_listing_model, stock_model};
 use application_beans::factory::bean_factory::BeanFactory;
 use application_context::context::application_context::APPLICATION_CONTEXT;
+use bigdecimal::BigDecimal;
 use database_mysql_seaorm::Dao;
 use sea_orm::EntityTrait;
 use sea_orm::QueryFilter;
-use sea_orm::{ColumnTrait, DbErr, DeleteResult};
+use sea_orm::prelude::{Date, DateTime};
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DatabaseTransaction, DbErr, IntoActiveModel,
+    QueryOrder, QuerySelect, TransactionTrait,
+};
 
-pub async fn delete_stocks_by_exchange_stock_kind(
-    exchange: &Exchange,
-    stock_kind: &StockKind,
-) -> Result<DeleteResult, DbErr> {
+/// 开启事务，同步股票列表的读取和写入都在同一个事务中完成
+pub async fn begin() -> Result<DatabaseTransaction, DbErr> {
     let application_context = APPLICATION_CONTEXT.read().await;
     let dao = application_context.get_bean_factory().get::<Dao>();
+    dao.connection.begin().await
+}
 
-    stock_model::Entity::delete_many()
+/// 查询交易所指定类型的全部股票，包括已退市的股票
+pub async fn find_stocks_by_exchange_stock_kind<C: ConnectionTrait>(
+    db: &C,
+    exchange: &Exchange,
+    stock_kind: &StockKind,
+) -> Result<Vec<stock_model::Model>, DbErr> {
+    stock_model::Entity::find()
         .filter(stock_model::Column::Exchange.eq(exchange.as_ref()))
         .filter(stock_model::Column::StockType.eq(stock_kind.to_string()))
-        .exec(&dao.connection)
+        .all(db)
         .await
 }
 
+/// 批量新增或更新股票，已存在的股票更新名称和代码并清除退市时间
+pub async fn upsert_stocks<C: ConnectionTrait>(
+    db: &C,
+    stocks: &[stock_model::Model],
+) -> Result<(), DbErr> {
+    for chunk in stocks.chunks(500) {
+        let models = chunk
+            .iter()
+            .map(|stock| stock.clone().into_active_model())
+            .collect::<Vec<_>>();
+        stock_model::Entity::insert_many(models)
+            .on_conflict(
+                OnConflict::column(stock_model::Column::Code)
+                    .update_columns([
+                        stock_model::Column::Name,
+                        stock_model::Column::Exchange,
+                        stock_model::Column::StockType,
+                        stock_model::Column::StockCode,
+                        stock_model::Column::DelistedAt,
+                    ])
+                    .to_owned(),
+            )
+            .on_empty_do_nothing()
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
+
+/// 将股票标记为已退市
+pub async fn delist_stocks<C: ConnectionTrait>(
+    db: &C,
+    codes: &[String],
+    delisted_at: &DateTime,
+) -> Result<(), DbErr> {
+    if codes.is_empty() {
+        return Ok(());
+    }
+    stock_model::Entity::update_many()
+        .col_expr(stock_model::Column::DelistedAt, Expr::value(*delisted_at))
+        .filter(stock_model::Column::Code.is_in(codes.iter().cloned()))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// 将上市地变化的股票改为新代码，股票引用的日线、公司行动、财报、基本面、提醒规则、
+/// 指数成分股和上市退市历史一并修改
+///
+/// 新代码下已有与旧代码主键冲突的数据时先删除新代码的数据，保留旧代码的历史，
+/// 基本面快照只保留日期较新的一条。
+pub async fn relocate_stocks<C: ConnectionTrait>(
+    db: &C,
+    relocations: &[StockRelocation],
+) -> Result<(), DbErr> {
+    for relocation in relocations {
+        let old_code = relocation.old_code.as_str();
+        delete_relocation_conflicts(db, relocation).await?;
+        let code = || Expr::value(relocation.code.clone());
+        stock_model::Entity::update_many()
+            .col_expr(stock_model::Column::Code, code())
+            .col_expr(
+                stock_model::Column::Exchange,
+                Expr::value(relocation.exchange.clone()),
+            )
+            .filter(stock_model::Column::Code.eq(old_code))
+            .exec(db)
+            .await?;
+        stock_daily_price_model::Entity::update_many()
+            .col_expr(stock_daily_price_model::Column::Code, code())
+            .filter(stock_daily_price_model::Column::Code.eq(old_code))
+            .exec(db)
+            .await?;
+        corporate_action_model::Entity::update_many()
+            .col_expr(corporate_action_model::Column::Code, code())
+            .filter(corporate_action_model::Column::Code.eq(old_code))
+            .exec(db)
+            .await?;
+        earnings_model::Entity::update_many()
+            .col_expr(earnings_model::Column::Code, code())
+            .filter(earnings_model::Column::Code.eq(old_code))
+            .exec(db)
+            .await?;
+        fundamental_model::Entity::update_many()
+            .col_expr(fundamental_model::Column::Code, code())
+            .filter(fundamental_model::Column::Code.eq(old_code))
+            .exec(db)
+            .await?;
+        alert_model::Entity::update_many()
+            .col_expr(alert_model::Column::Code, code())
+            .filter(alert_model::Column::Code.eq(old_code))
+            .exec(db)
+            .await?;
+        index_constituent_model::Entity::update_many()
+            .col_expr(index_constituent_model::Column::StockCode, code())
+            .filter(index_constituent_model::Column::StockCode.eq(old_code))
+            .exec(db)
+            .await?;
+        stock_listing_model::Entity::update_many()
+            .col_expr(stock_listing_model::Column::Code, code())
+            .col_expr(
+                stock_listing_model::Column::Exchange,
+                Expr::value(relocation.exchange.clone()),
+            )
+            .filter(stock_listing_model::Column::Code.eq(old_code))
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
+
+/// 删除新代码下与旧代码主键冲突的数据，避免改为新代码时违反主键约束
+async fn delete_relocation_conflicts<C: ConnectionTrait>(
+    db: &C,
+    relocation: &StockRelocation,
+) -> Result<(), DbErr> {
+    let old_code = relocation.old_code.as_str();
+    let code = relocation.code.as_str();
+    stock_model::Entity::delete_by_id(code).exec(db).await?;
+
+    let dates = stock_daily_price_model::Entity::find()
+        .select_only()
+        .column(stock_daily_price_model::Column::Date)
+        .filter(stock_daily_price_model::Column::Code.eq(old_code))
+        .into_tuple::<Date>()
+        .all(db)
+        .await?;
+    for chunk in dates.chunks(500) {
+        stock_daily_price_model::Entity::delete_many()
+            .filter(stock_daily_price_model::Column::Code.eq(code))
+            .filter(stock_daily_price_model::Column::Date.is_in(chunk.to_vec()))
+            .exec(db)
+            .await?;
+    }
+
+    let actions = corporate_action_model::Entity::find()
+        .select_only()
+        .column(corporate_action_model::Column::ExDate)
+        .column(corporate_action_model::Column::ActionType)
+        .filter(corporate_action_model::Column::Code.eq(old_code))
+        .into_tuple::<(Date, String)>()
+        .all(db)
+        .await?;
+    for chunk in actions.chunks(500) {
+        let keys = chunk
+            .iter()
+            .fold(Condition::any(), |condition, (ex_date, action_type)| {
+                condition.add(
+                    Condition::all()
+                        .add(corporate_action_model::Column::ExDate.eq(*ex_date))
+                        .add(corporate_action_model::Column::ActionType.eq(action_type)),
+                )
+            });
+        corporate_action_model::Entity::delete_many()
+            .filter(corporate_action_model::Column::Code.eq(code))
+            .filter(keys)
+            .exec(db)
+            .await?;
+    }
+
+    let report_dates = earnings_model::Entity::find()
+        .select_only()
+        .column(earnings_model::Column::ReportDate)
+        .filter(earnings_model::Column::Code.eq(old_code))
+        .into_tuple::<Date>()
+        .all(db)
+        .await?;
+    for chunk in report_dates.chunks(500) {
+        earnings_model::Entity::delete_many()
+            .filter(earnings_model::Column::Code.eq(code))
+            .filter(earnings_model::Column::ReportDate.is_in(chunk.to_vec()))
+            .exec(db)
+            .await?;
+    }
+
+    let old_fundamental = fundamental_model::Entity::find_by_id(old_code)
+        .one(db)
+        .await?;
+    let fundamental = fundamental_model::Entity::find_by_id(code).one(db).await?;
+    if let (Some(old_fundamental), Some(fundamental)) = (old_fundamental, fundamental) {
+        // 保留日期较新的快照，旧代码的快照较旧时直接删除，不再改为新代码
+        let outdated = if fundamental.date > old_fundamental.date {
+            old_code
+        } else {
+            code
+        };
+        fundamental_model::Entity::delete_by_id(outdated)
+            .exec(db)
+            .await?;
+    }
+
+    let index_codes = index_constituent_model::Entity::find()
+        .select_only()
+        .column(index_constituent_model::Column::IndexCode)
+        .filter(index_constituent_model::Column::StockCode.eq(old_code))
+        .into_tuple::<String>()
+        .all(db)
+        .await?;
+    if !index_codes.is_empty() {
+        index_constituent_model::Entity::delete_many()
+            .filter(index_constituent_model::Column::StockCode.eq(code))
+            .filter(index_constituent_model::Column::IndexCode.is_in(index_codes))
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
+
+/// 保存上市退市历史
+pub async fn save_listing_histories<C: ConnectionTrait>(
+    db: &C,
+    histories: Vec<stock_listing_model::ActiveModel>,
+) -> Result<(), DbErr> {
+    for chunk in histories.chunks(500) {
+        stock_listing_model::Entity::insert_many(chunk.to_vec())
+            .on_empty_do_nothing()
+            .exec(db)
+            .await?;
+    }
+    Ok(())
+}
+
 pub async fn get_stock_by_code(code: &str) -> Result<Option<stock_model::Model>, DbErr> {
     let application_context = APPLICATION_CONTEXT.read().await;
     let dao = application_context.get_bean_factory().get::<Dao>();
@@ -29,3 +269,164 @@ pub async fn get_stock_by_code(code: &str) -> Result<Option<stock_model::Model>,
         .one(&dao.connection)
         .await
 }
+
+/// 交易所是否有代码不以 `suffix` 结尾的股票，包括已退市的股票
+pub async fn exists_stock_without_suffix(exchange: &str, suffix: &str) -> Result<bool, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    let stock = stock_model::Entity::find()
+        .filter(stock_model::Column::Exchange.eq(exchange))
+        .filter(stock_model::Column::Code.not_like(format!("%{}", suffix)))
+        .one(&dao.connection)
+        .await?;
+    Ok(stock.is_some())
+}
+
+/// 查询全部上市中的股票，包括个股、指数和基金
+pub async fn find_all_stocks() -> Result<Vec<stock_model::Model>, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    stock_model::Entity::find()
+        .filter(stock_model::Column::DelistedAt.is_null())
+        .all(&dao.connection)
+        .await
+}
+
+/// 查询指定类型的全部上市中的股票
+pub async fn find_stocks_by_kind(stock_kind: &StockKind) -> Result<Vec<stock_model::Model>, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    stock_model::Entity::find()
+        .filter(stock_model::Column::StockType.eq(stock_kind.to_string()))
+        .filter(stock_model::Column::DelistedAt.is_null())
+        .all(&dao.connection)
+        .await
+}
+
+/// 按日期升序查询股票已入库的全部日线
+pub async fn find_daily_prices(code: &str) -> Result<Vec<stock_daily_price_model::Model>, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    stock_daily_price_model::Entity::find()
+        .filter(stock_daily_price_model::Column::Code.eq(code))
+        .order_by_asc(stock_daily_price_model::Column::Date)
+        .all(&dao.connection)
+        .await
+}
+
+/// 按日期升序查询股票已入库日线的交易日和收盘价，用于计算复权因子
+pub async fn find_daily_closes(code: &str) -> Result<Vec<(Date, BigDecimal)>, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    stock_daily_price_model::Entity::find()
+        .select_only()
+        .column(stock_daily_price_model::Column::Date)
+        .column(stock_daily_price_model::Column::Close)
+        .filter(stock_daily_price_model::Column::Code.eq(code))
+        .order_by_asc(stock_daily_price_model::Column::Date)
+        .into_tuple::<(Date, BigDecimal)>()
+        .all(&dao.connection)
+        .await
+}
+
+/// 按日期范围分页查询股票已入库的日线
+///
+/// 按日期而不是偏移量翻页，`after` 为上一页最后一条日线的日期，第一页为 `None`。
+pub async fn find_daily_prices_page(
+    code: &str,
+    start: Option<Date>,
+    end: Option<Date>,
+    after: Option<Date>,
+    order: SortOrder,
+    limit: u64,
+) -> Result<Vec<stock_daily_price_model::Model>, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    let mut select = stock_daily_price_model::Entity::find()
+        .filter(stock_daily_price_model::Column::Code.eq(code));
+    if let Some(start) = start {
+        select = select.filter(stock_daily_price_model::Column::Date.gte(start));
+    }
+    if let Some(end) = end {
+        select = select.filter(stock_daily_price_model::Column::Date.lte(end));
+    }
+    select = match (order, after) {
+        (SortOrder::Asc, Some(after)) => {
+            select.filter(stock_daily_price_model::Column::Date.gt(after))
+        }
+        (SortOrder::Desc, Some(after)) => {
+            select.filter(stock_daily_price_model::Column::Date.lt(after))
+        }
+        (_, None) => select,
+    };
+    select = match order {
+        SortOrder::Asc => select.order_by_asc(stock_daily_price_model::Column::Date),
+        SortOrder::Desc => select.order_by_desc(stock_daily_price_model::Column::Date),
+    };
+    select.limit(limit).all(&dao.connection).await
+}
+
+/// 查询日期范围内最近的第 `n` 条日线的日期，不足 `n` 条时返回 `None`
+pub async fn find_nth_latest_daily_price_date(
+    code: &str,
+    start: Option<Date>,
+    end: Option<Date>,
+    n: u64,
+) -> Result<Option<Date>, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    let mut select = stock_daily_price_model::Entity::find()
+        .select_only()
+        .column(stock_daily_price_model::Column::Date)
+        .filter(stock_daily_price_model::Column::Code.eq(code));
+    if let Some(start) = start {
+        select = select.filter(stock_daily_price_model::Column::Date.gte(start));
+    }
+    if let Some(end) = end {
+        select = select.filter(stock_daily_price_model::Column::Date.lte(end));
+    }
+    select
+        .order_by_desc(stock_daily_price_model::Column::Date)
+        .offset(n.saturating_sub(1))
+        .into_tuple::<Date>()
+        .one(&dao.connection)
+        .await
+}
+
+/// 查询股票已入库日线的最后一个交易日
+pub async fn get_last_daily_price_date(code: &str) -> Result<Option<Date>, DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    let last = stock_daily_price_model::Entity::find()
+        .filter(stock_daily_price_model::Column::Code.eq(code))
+        .order_by_desc(stock_daily_price_model::Column::Date)
+        .one(&dao.connection)
+        .await?;
+    Ok(last.map(|price| price.date))
+}
+
+/// 批量保存日线，已存在的 (code, date) 记录保持不变
+pub async fn save_daily_prices(prices: &[stock_daily_price_model::Model]) -> Result<(), DbErr> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let dao = application_context.get_bean_factory().get::<Dao>();
+    // 分批写入，避免全量历史数据超出单条 SQL 的参数上限
+    for chunk in prices.chunks(500) {
+        let models = chunk
+            .iter()
+            .map(|price| price.clone().into_active_model())
+            .collect::<Vec<_>>();
+        stock_daily_price_model::Entity::insert_many(models)
+            .on_conflict(
+                OnConflict::columns([
+                    stock_daily_price_model::Column::Code,
+                    stock_daily_price_model::Column::Date,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .on_empty_do_nothing()
+            .exec(&dao.connection)
+            .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,334 @@
+use crate::stock::stock_model::Model as Stock;
+use sea_orm::ActiveValue::{NotSet, Set};
+use sea_orm::entity::prelude::*;
+use sea_orm::{ActiveModelBehavior, DeriveEntityModel, DeriveRelation, EnumIter};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+/// 股票上市、退市和更名历史
+#[derive(Debug, Serialize, Deserialize, Clone, DeriveEntityModel)]
+#[sea_orm(table_name = "stock_listing_history")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: u64,
+    /// 股票代码
+    pub code: String,
+    /// 变化后的股票名称，退市时为退市前的名称
+    pub name: String,
+    /// 交易所
+    pub exchange: String,
+    /// 股票类型：Stock/Fund
+    pub stock_type: String,
+    /// 变化类型：Listed/Delisted/Renamed
+    pub change_type: String,
+    /// 更名前的名称
+    pub old_name: Option<String>,
+    /// 变化时间
+    pub changed_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+/// 上市状态变化类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListingChangeType {
+    /// 新上市，或退市后重新出现在交易所的列表中
+    Listed,
+    /// 退市
+    Delisted,
+    /// 更名
+    Renamed,
+}
+
+impl Display for ListingChangeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            ListingChangeType::Listed => "Listed",
+            ListingChangeType::Delisted => "Delisted",
+            ListingChangeType::Renamed => "Renamed",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// 交易所最新股票列表与已保存股票的差异
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct StockListingChanges {
+    /// 是否首次同步，首次同步没有已保存的股票，不发送通知
+    pub first_sync: bool,
+    /// 新上市或重新上市的股票
+    pub listed: Vec<Stock>,
+    /// 不在最新列表中的上市股票
+    pub delisted: Vec<Stock>,
+    /// 更名的股票及更名前的名称
+    pub renamed: Vec<(String, Stock)>,
+}
+
+impl StockListingChanges {
+    /// 对比已保存的股票（包括已退市的）和交易所最新的股票列表
+    ///
+    /// 最新列表为空时视为数据源异常，不产生任何变化，避免把全部股票标记为退市。
+    pub fn diff(current: &[Stock], latest: &[Stock]) -> Self {
+        if latest.is_empty() {
+            return StockListingChanges::default();
+        }
+        let current_by_code = current
+            .iter()
+            .map(|stock| (stock.code.as_str(), stock))
+            .collect::<HashMap<_, _>>();
+        let latest_codes = latest
+            .iter()
+            .map(|stock| stock.code.as_str())
+            .collect::<HashSet<_>>();
+
+        let mut changes = StockListingChanges {
+            first_sync: current.is_empty(),
+            ..Default::default()
+        };
+        for stock in latest {
+            match current_by_code.get(stock.code.as_str()) {
+                None => changes.listed.push(stock.clone()),
+                Some(old) if old.delisted_at.is_some() => changes.listed.push(stock.clone()),
+                Some(old) if old.name != stock.name => {
+                    changes.renamed.push((old.name.clone(), stock.clone()))
+                }
+                Some(_) => {}
+            }
+        }
+        for stock in current {
+            if stock.delisted_at.is_none() && !latest_codes.contains(&stock.code.as_str()) {
+                changes.delisted.push(stock.clone());
+            }
+        }
+        changes
+    }
+
+    /// 合并多个交易所的变化，全部都是首次同步时才视为首次同步
+    pub fn merge<'a>(changes: impl IntoIterator<Item = &'a StockListingChanges>) -> Self {
+        let mut merged: Option<StockListingChanges> = None;
+        for changes in changes {
+            let merged = merged.get_or_insert_with(|| StockListingChanges {
+                first_sync: true,
+                ..Default::default()
+            });
+            merged.first_sync &= changes.first_sync;
+            merged.listed.extend(changes.listed.iter().cloned());
+            merged.delisted.extend(changes.delisted.iter().cloned());
+            merged.renamed.extend(changes.renamed.iter().cloned());
+        }
+        merged.unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.listed.is_empty() && self.delisted.is_empty() && self.renamed.is_empty()
+    }
+
+    /// 需要新增或更新的股票
+    pub fn upserts(&self) -> Vec<Stock> {
+        self.listed
+            .iter()
+            .chain(self.renamed.iter().map(|(_, stock)| stock))
+            .cloned()
+            .collect()
+    }
+
+    /// 转换为上市退市历史记录
+    pub fn to_histories(&self, changed_at: &DateTime) -> Vec<ActiveModel> {
+        let history = |stock: &Stock, change_type: ListingChangeType, old_name: Option<&String>| {
+            ActiveModel {
+                id: NotSet,
+                code: Set(stock.code.clone()),
+                name: Set(stock.name.clone()),
+                exchange: Set(stock.exchange.clone()),
+                stock_type: Set(stock.stock_type.clone()),
+                change_type: Set(change_type.to_string()),
+                old_name: Set(old_name.cloned()),
+                changed_at: Set(*changed_at),
+            }
+        };
+        let mut histories = Vec::new();
+        for stock in &self.listed {
+            histories.push(history(stock, ListingChangeType::Listed, None));
+        }
+        for stock in &self.delisted {
+            histories.push(history(stock, ListingChangeType::Delisted, None));
+        }
+        for (old_name, stock) in &self.renamed {
+            histories.push(history(stock, ListingChangeType::Renamed, Some(old_name)));
+        }
+        histories
+    }
+}
+
+/// 上市地变化的股票，以旧代码保存的数据全部改为新代码
+///
+/// 美股按上市地区分交易所之前都以纳斯达克的 `.NS` 后缀保存，转板时后缀也会变化。
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct StockRelocation {
+    /// 原来的股票代码
+    pub old_code: String,
+    /// 新的股票代码
+    pub code: String,
+    /// 新的交易所
+    pub exchange: String,
+}
+
+impl StockRelocation {
+    /// 找出交易所内代码相同但代码后缀或交易所变化的已保存股票
+    ///
+    /// 新代码已经保存过时不迁移，避免主键冲突，由列表对比按退市和上市处理。
+    pub fn find(current: &[Stock], latest: &[Stock]) -> Vec<Self> {
+        let current_codes = current
+            .iter()
+            .map(|stock| stock.code.as_str())
+            .collect::<HashSet<_>>();
+        let latest_by_stock_code = latest
+            .iter()
+            .map(|stock| (stock.stock_code.as_str(), stock))
+            .collect::<HashMap<_, _>>();
+        current
+            .iter()
+            .filter_map(|stock| {
+                let latest = latest_by_stock_code.get(stock.stock_code.as_str())?;
+                if latest.code == stock.code || current_codes.contains(latest.code.as_str()) {
+                    return None;
+                }
+                Some(StockRelocation {
+                    old_code: stock.code.clone(),
+                    code: latest.code.clone(),
+                    exchange: latest.exchange.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// 交易所股票和基金列表的同步结果
+#[derive(Debug, Serialize, Clone)]
+pub struct StockListingSyncResult {
+    /// 是否只对比差异而不提交
+    pub dry_run: bool,
+    /// 上市地变化而改为新代码的股票
+    pub relocated: Vec<StockRelocation>,
+    /// 股票列表的变化
+    pub stocks: StockListingChanges,
+    /// 基金列表的变化
+    pub funds: StockListingChanges,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn stock(code: &str, name: &str) -> Stock {
+        Stock {
+            code: code.to_string(),
+            name: name.to_string(),
+            exchange: "SSE".to_string(),
+            stock_type: "Stock".to_string(),
+            stock_code: code.split('.').next().unwrap_or_default().to_string(),
+            delisted_at: None,
+        }
+    }
+
+    fn delisted(code: &str, name: &str) -> Stock {
+        Stock {
+            delisted_at: NaiveDate::from_ymd_opt(2024, 1, 2)
+                .and_then(|date| date.and_hms_opt(15, 0, 0)),
+            ..stock(code, name)
+        }
+    }
+
+    fn codes(stocks: &[Stock]) -> Vec<&str> {
+        stocks.iter().map(|stock| stock.code.as_str()).collect()
+    }
+
+    #[test]
+    fn test_diff_first_sync() {
+        let latest = vec![
+            stock("600000.SH", "浦发银行"),
+            stock("600519.SH", "贵州茅台"),
+        ];
+        let changes = StockListingChanges::diff(&[], &latest);
+        assert!(changes.first_sync);
+        assert_eq!(codes(&changes.listed), vec!["600000.SH", "600519.SH"]);
+        assert!(changes.delisted.is_empty());
+        assert!(changes.renamed.is_empty());
+    }
+
+    #[test]
+    fn test_diff_relisted() {
+        let current = vec![delisted("600001.SH", "邯郸钢铁")];
+        let latest = vec![stock("600001.SH", "邯郸钢铁")];
+        let changes = StockListingChanges::diff(&current, &latest);
+        assert!(!changes.first_sync);
+        assert_eq!(codes(&changes.listed), vec!["600001.SH"]);
+        assert!(changes.listed[0].delisted_at.is_none());
+        assert!(changes.renamed.is_empty());
+    }
+
+    #[test]
+    fn test_diff_renamed_and_delisted() {
+        let current = vec![
+            stock("600000.SH", "浦发银行"),
+            stock("600837.SH", "海通证券"),
+            delisted("600001.SH", "邯郸钢铁"),
+            stock("600519.SH", "贵州茅台"),
+        ];
+        let latest = vec![stock("600000.SH", "浦发"), stock("600519.SH", "贵州茅台")];
+        let changes = StockListingChanges::diff(&current, &latest);
+        assert!(changes.listed.is_empty());
+        assert_eq!(changes.renamed.len(), 1);
+        assert_eq!(changes.renamed[0].0, "浦发银行");
+        assert_eq!(changes.renamed[0].1.name, "浦发");
+        // 已退市的股票不再重复退市
+        assert_eq!(codes(&changes.delisted), vec!["600837.SH"]);
+        assert_eq!(codes(&changes.upserts()), vec!["600000.SH"]);
+    }
+
+    #[test]
+    fn test_diff_empty_latest() {
+        let current = vec![stock("600000.SH", "浦发银行")];
+        let changes = StockListingChanges::diff(&current, &[]);
+        assert!(changes.is_empty());
+        assert!(!changes.first_sync);
+        assert!(StockListingChanges::diff(&[], &[]).is_empty());
+    }
+
+    fn us_stock(code: &str, exchange: &str) -> Stock {
+        Stock {
+            exchange: exchange.to_string(),
+            ..stock(code, "")
+        }
+    }
+
+    #[test]
+    fn test_find_relocations() {
+        let current = vec![
+            us_stock("IBM.NS", "NASDAQ"),
+            us_stock("AAPL.NS", "NASDAQ"),
+            us_stock("BRK.NS", "NASDAQ"),
+            us_stock("BRK.N", "NYSE"),
+        ];
+        let latest = vec![
+            us_stock("IBM.N", "NYSE"),
+            us_stock("AAPL.NS", "NASDAQ"),
+            us_stock("BRK.N", "NYSE"),
+        ];
+        // 代码未变化的不迁移，新代码已保存的不迁移
+        assert_eq!(
+            StockRelocation::find(&current, &latest),
+            vec![StockRelocation {
+                old_code: "IBM.NS".to_string(),
+                code: "IBM.N".to_string(),
+                exchange: "NYSE".to_string(),
+            }]
+        );
+        assert!(StockRelocation::find(&current, &[]).is_empty());
+    }
+}
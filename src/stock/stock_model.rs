@@ -2,8 +2,13 @@ use sea_orm::entity::prelude::*;
 use sea_orm::{ActiveModelBehavior, DeriveEntityModel, DeriveRelation, EnumIter};
 use std::fmt::Display;
 
+use crate::error::AppError;
+use crate::stock::stock_price_api::StockDailyPrice;
 use bigdecimal::BigDecimal;
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::str::FromStr;
 
 /**
  * 表示股票的结构体。
@@ -27,6 +32,8 @@ pub struct Model {
     pub stock_type: String,
     /// 股票在交易中的代码
     pub stock_code: String,
+    /// 退市时间，上市中的股票为空
+    pub delisted_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -34,7 +41,7 @@ pub enum Relation {}
 
 impl ActiveModelBehavior for ActiveModel {}
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct StockPrice {
     pub code: String,
     pub open: Option<BigDecimal>,
@@ -45,6 +52,29 @@ pub struct StockPrice {
     pub time: String,
 }
 
+/// 批量查询股票价格的单项结果
+///
+/// # 属性
+/// - `code`：请求中的股票代码，可能与 `price.code` 中的标准代码大小写不同
+/// - `price`：股票价格，查询失败时为空
+/// - `error`：查询失败的原因，查询成功时为空
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StockPriceResult {
+    pub code: String,
+    pub price: Option<StockPrice>,
+    pub error: Option<String>,
+}
+
+impl StockPriceResult {
+    pub fn error(code: &str, error: String) -> Self {
+        StockPriceResult {
+            code: code.to_string(),
+            price: None,
+            error: Some(error),
+        }
+    }
+}
+
 pub enum StockKind {
     Stock,
     Fund,
@@ -61,3 +91,146 @@ impl Display for StockKind {
         write!(f, "{}", str)
     }
 }
+
+/// 排序方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl FromStr for SortOrder {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "asc" => Ok(SortOrder::Asc),
+            "desc" => Ok(SortOrder::Desc),
+            _ => Err(
+                AppError::InvalidParam(format!("Invalid order: {}, expect asc or desc", s)).into(),
+            ),
+        }
+    }
+}
+
+/// 复权方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceAdjust {
+    /// 不复权
+    #[default]
+    None,
+    /// 前复权，最新价格与不复权价格一致
+    Qfq,
+    /// 后复权，最早价格与不复权价格一致
+    Hfq,
+}
+
+impl FromStr for PriceAdjust {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "" | "none" => Ok(PriceAdjust::None),
+            "qfq" => Ok(PriceAdjust::Qfq),
+            "hfq" => Ok(PriceAdjust::Hfq),
+            _ => Err(AppError::InvalidParam(format!(
+                "Invalid adjust: {}, expect none, qfq or hfq",
+                s
+            ))
+            .into()),
+        }
+    }
+}
+
+/// 带复权方式的日线数据
+///
+/// # 属性
+/// - `adjust`：实际采用的复权方式，指数、基金等不支持复权的品种始终为 `none`
+/// - `prices`：日线数据
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdjustedDailyPrices {
+    pub adjust: PriceAdjust,
+    pub prices: Vec<StockDailyPrice>,
+}
+
+/// 日线查询条件
+///
+/// # 属性
+/// - `start`：开始时间（包含），格式为 `yyyyMMddHHmmss`
+/// - `end`：结束时间（包含），格式为 `yyyyMMddHHmmss`
+/// - `limit`：在时间范围内只返回最近的 `limit` 条数据
+/// - `order`：返回数据按时间排序的方向
+/// - `adjust`：复权方式
+#[derive(Debug, Clone, Default)]
+pub struct DailyPriceQuery {
+    pub start: Option<u64>,
+    pub end: Option<u64>,
+    pub limit: Option<usize>,
+    pub order: SortOrder,
+    pub adjust: PriceAdjust,
+}
+
+impl DailyPriceQuery {
+    /// 解析请求参数，日期支持 `yyyy-MM-dd` 和 `yyyyMMdd` 两种格式
+    pub fn parse(
+        start: Option<&str>,
+        end: Option<&str>,
+        limit: Option<usize>,
+        order: Option<&str>,
+        adjust: Option<&str>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let start = match start {
+            Some(start) => Some(
+                parse_query_date(start)?
+                    .format("%Y%m%d000000")
+                    .to_string()
+                    .parse::<u64>()?,
+            ),
+            None => None,
+        };
+        let end = match end {
+            Some(end) => Some(
+                parse_query_date(end)?
+                    .format("%Y%m%d235959")
+                    .to_string()
+                    .parse::<u64>()?,
+            ),
+            None => None,
+        };
+        if start.zip(end).is_some_and(|(start, end)| start > end) {
+            return Err(AppError::InvalidParam(
+                "Invalid date range, start is after end".to_string(),
+            )
+            .into());
+        }
+        let order = match order {
+            Some(order) => SortOrder::from_str(order)?,
+            None => SortOrder::Asc,
+        };
+        let adjust = match adjust {
+            Some(adjust) => PriceAdjust::from_str(adjust)?,
+            None => PriceAdjust::None,
+        };
+        Ok(DailyPriceQuery {
+            start,
+            end,
+            limit,
+            order,
+            adjust,
+        })
+    }
+}
+
+pub(crate) fn parse_query_date(date: &str) -> Result<NaiveDate, Box<dyn Error>> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(date, "%Y%m%d"))
+        .map_err(|_| {
+            AppError::InvalidParam(format!(
+                "Invalid date: {}, expect yyyy-MM-dd or yyyyMMdd",
+                date
+            ))
+            .into()
+        })
+}
@@ -1,24 +1,29 @@
+use crate::error;
+use crate::error::AppError;
 use crate::exchange::exchange_model::Exchange;
 use crate::exchange::exchange_svc;
 use crate::exchange::exchange_svc::is_market_closed;
 use crate::holiday::holiday_svc::is_holiday;
 use crate::stock::stock_model;
+use crate::stock::stock_price_provider;
 use crate::token::token_svc;
+use crate::upstream;
 use application_cache::CacheManager;
 use application_context::context::application_context::APPLICATION_CONTEXT;
 use application_core::env::property_resolver::PropertyResolver;
 use async_trait::async_trait;
 use bigdecimal::BigDecimal;
 use bigdecimal::num_traits::Bounded;
-use chrono::{DateTime, Datelike, Local, NaiveDateTime, Utc};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, Utc};
+use futures::{StreamExt, stream};
 use rand::{RngExt, rng};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::error::Error;
 use std::str::FromStr;
-use tracing::info;
+use tracing::{error, info};
 use util::request::Request;
-use yahoo_finance_api as yahoo;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StockDailyPriceDTO {
@@ -48,17 +53,50 @@ pub struct StockDailyPrice {
     pub low: BigDecimal,
     /// 当日成交量，可能为空
     pub volume: Option<BigDecimal>,
+    /// 当日成交额，可能为空
+    #[serde(default)]
+    pub amount: Option<BigDecimal>,
 }
 
-fn create_stock_daily_price(dto: &StockDailyPriceDTO) -> StockDailyPrice {
-    StockDailyPrice {
-        time: dto.t.parse::<u64>().unwrap(),
-        open: BigDecimal::from_str(&dto.o).unwrap(),
-        close: BigDecimal::from_str(&dto.c).unwrap(),
-        high: BigDecimal::from_str(&dto.h).unwrap(),
-        low: BigDecimal::from_str(&dto.l).unwrap(),
-        volume: Some(BigDecimal::from_str(&dto.v).unwrap()),
-    }
+/// 分钟线价格
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StockMinutePrice {
+    /// 分钟线时间，格式为 yyyyMMddHHmmss
+    pub time: u64,
+    /// 开盘价
+    pub open: BigDecimal,
+    /// 收盘价
+    pub close: BigDecimal,
+    /// 最高价
+    pub high: BigDecimal,
+    /// 最低价
+    pub low: BigDecimal,
+    /// 成交量，可能为空
+    pub volume: Option<BigDecimal>,
+    /// 成交额，可能为空
+    pub amount: Option<BigDecimal>,
+}
+
+/// 将上游返回的日线转换为 `StockDailyPrice`，字段格式不正确时返回解析错误
+fn create_stock_daily_price(
+    dto: &StockDailyPriceDTO,
+    upstream: &str,
+) -> Result<StockDailyPrice, AppError> {
+    let decimal = |value: &str, field: &str| {
+        BigDecimal::from_str(value).map_err(|_| AppError::parse(upstream, field))
+    };
+    Ok(StockDailyPrice {
+        time: dto
+            .t
+            .parse::<u64>()
+            .map_err(|_| AppError::parse(upstream, "time"))?,
+        open: decimal(&dto.o, "open")?,
+        close: decimal(&dto.c, "close")?,
+        high: decimal(&dto.h, "high")?,
+        low: decimal(&dto.l, "low")?,
+        volume: Some(decimal(&dto.v, "volume")?),
+        amount: BigDecimal::from_str(&dto.e).ok(),
+    })
 }
 
 /// Helper function to convert ISO date string to u64 date format
@@ -68,12 +106,8 @@ fn iso_date_to_u64(date_str: &str) -> Result<u64, Box<dyn Error>> {
 }
 
 /// Helper function to get akshare base URL from environment
-async fn get_akshare_base_url() -> Result<String, Box<dyn Error>> {
-    let application_context = APPLICATION_CONTEXT.read().await;
-    let environment = application_context.get_environment().await;
-    environment
-        .get_property::<String>("stock.api.akshare.baseurl")
-        .ok_or_else(|| "Missing property: stock.api.akshare.baseurl".into())
+pub(crate) async fn get_akshare_base_url() -> Result<String, Box<dyn Error>> {
+    Ok(upstream::get_url("stock.api.akshare.baseurl").await?)
 }
 
 /// Helper function to transform stock code to akshare format
@@ -81,6 +115,7 @@ fn to_akshare_symbol(exchange: &Exchange, stock_code: &str) -> String {
     match exchange {
         Exchange::SSE => format!("sh{}", stock_code),
         Exchange::SZSE => format!("sz{}", stock_code),
+        Exchange::BSE => format!("bj{}", stock_code),
         _ => stock_code.to_string(),
     }
 }
@@ -90,26 +125,31 @@ async fn parse_akshare_kline(url: &str) -> Result<Vec<StockDailyPrice>, Box<dyn
     info!("Get stock daily price from akshare: {}", url);
     let response = Request::get_response(url).await?;
     let data: Value = response.json().await?;
+    parse_akshare_daily_prices(&data)
+}
+
+/// 解析 akshare 返回的日线
+fn parse_akshare_daily_prices(data: &Value) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
     let kline = data.as_array();
     let mut stock_prices = Vec::new();
     if let Some(kline) = kline {
         stock_prices.reserve(kline.len());
         for k in kline {
-            let date_str = k["date"].as_str().unwrap();
+            let date_str = error::str_field(k, "date", "akshare")?;
             let date = iso_date_to_u64(date_str)?;
             let price = StockDailyPriceDTO {
                 t: date.to_string() + "093000",
-                o: k["open"].as_f64().unwrap().to_string(),
-                c: k["close"].as_f64().unwrap().to_string(),
-                l: k["low"].as_f64().unwrap().to_string(),
-                h: k["high"].as_f64().unwrap().to_string(),
+                o: error::number_field(k, "open", "akshare")?,
+                c: error::number_field(k, "close", "akshare")?,
+                l: error::number_field(k, "low", "akshare")?,
+                h: error::number_field(k, "high", "akshare")?,
                 zd: String::new(),
                 zdf: String::new(),
-                v: k["volume"].as_number().unwrap().to_string(),
+                v: error::number_field(k, "volume", "akshare")?,
                 e: String::new(),
                 hs: String::new(),
             };
-            let price = create_stock_daily_price(&price);
+            let price = create_stock_daily_price(&price, "akshare")?;
             stock_prices.push(price);
         }
     }
@@ -122,6 +162,18 @@ pub trait StockPriceApi {
         &self,
         stock: &stock_model::Model,
     ) -> Result<StockPriceDTO, Box<dyn Error>>;
+
+    /// 获取当日 1 分钟线
+    async fn get_stock_intraday_prices(
+        &self,
+        stock: &stock_model::Model,
+    ) -> Result<Vec<StockMinutePrice>, Box<dyn Error>>;
+
+    /// 批量获取股票当前价格，结果与 `stocks` 一一对应，单只股票失败时返回错误信息
+    async fn get_stock_prices(
+        &self,
+        stocks: &[stock_model::Model],
+    ) -> Vec<Result<StockPriceDTO, String>>;
 }
 
 #[async_trait]
@@ -130,38 +182,674 @@ impl StockPriceApi for Exchange {
         &self,
         stock: &stock_model::Model,
     ) -> Result<StockPriceDTO, Box<dyn Error>> {
-        match self {
-            Exchange::SSE => get_current_price_from_sse(&stock.stock_code).await,
-            Exchange::SZSE => get_current_price_from_szse(&stock.stock_code).await,
-            Exchange::HKEX => {
-                if stock.stock_type == "Index" {
-                    get_current_index_price_from_hk(self, &stock.stock_code).await
-                } else {
-                    get_current_stock_price_from_hk(self, &stock.stock_code).await
-                }
+        stock_price_provider::get_latest_price(stock).await
+    }
+
+    async fn get_stock_intraday_prices(
+        &self,
+        stock: &stock_model::Model,
+    ) -> Result<Vec<StockMinutePrice>, Box<dyn Error>> {
+        stock_price_provider::get_intraday_prices(stock).await
+    }
+
+    async fn get_stock_prices(
+        &self,
+        stocks: &[stock_model::Model],
+    ) -> Vec<Result<StockPriceDTO, String>> {
+        // 上交所和美股支持一次查询多只股票，批量接口未返回的股票再逐个查询
+        let prices = match self {
+            Exchange::SSE => get_current_prices_from_sse(stocks).await,
+            Exchange::NASDAQ | Exchange::NYSE | Exchange::AMEX => {
+                get_current_prices_from_nasdaq(self, stocks).await
             }
-            Exchange::NASDAQ => {
-                // SPX 指数走 Yahoo Finance，nasdaq.com 接口对这类代码无数据
-                let yahoo_symbol = match stock.code.as_str() {
-                    "SPX.NS" => Some("^GSPC"),
-                    _ => None,
+            _ => Ok(HashMap::new()),
+        };
+        let mut prices = prices.unwrap_or_else(|e| {
+            error!("Batch get {} stock prices error {}", self.as_ref(), e);
+            HashMap::new()
+        });
+
+        let missing = stocks
+            .iter()
+            .filter(|stock| !prices.contains_key(&stock.code))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut missing_prices = stream::iter(missing)
+            .map(|stock| async move {
+                self.get_stock_price(&stock)
+                    .await
+                    .map_err(|e| e.to_string())
+            })
+            .buffered(BATCH_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter();
+
+        stocks
+            .iter()
+            .map(|stock| match prices.remove(&stock.code) {
+                Some(price) => Ok(price),
+                None => missing_prices
+                    .next()
+                    .unwrap_or_else(|| Err(format!("No price found for {}", stock.code))),
+            })
+            .collect()
+    }
+}
+
+/// 批量查询时逐个请求上游接口的最大并发数
+const BATCH_CONCURRENCY: usize = 8;
+
+/// 纳斯达克自选股接口单次查询的最大代码数
+const NASDAQ_WATCHLIST_SIZE: usize = 50;
+
+/// 通过上交所 A 股行情列表批量获取个股当前价格，返回以股票代码为键的价格
+async fn get_current_prices_from_sse(
+    stocks: &[stock_model::Model],
+) -> Result<HashMap<String, StockPriceDTO>, Box<dyn Error>> {
+    let stocks = stocks
+        .iter()
+        .filter(|stock| stock.stock_type == "Stock")
+        .map(|stock| (stock.stock_code.as_str(), stock.code.as_str()))
+        .collect::<HashMap<_, _>>();
+    if stocks.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let environment = application_context.get_environment().await;
+    let base_url = environment
+        .get_property::<String>("stock.api.sh.baseurl")
+        .ok_or_else(|| AppError::missing_property("stock.api.sh.baseurl"))?;
+    let url = format!(
+        "{}/v1/sh1/list/exchange/equity?select=code,prev_close,open,high,low,last,chg_rate,change,volume,amount&begin=0&end=5000&_={}",
+        base_url,
+        Local::now().timestamp_millis()
+    );
+    info!("Get stock prices from url = {}", url);
+    let response = Request::get_response(&url).await?;
+    let json: Value = response.json().await?;
+    let date = json["date"].to_string();
+    let time = format!("{:0>6}", json["time"].to_string());
+    let t = NaiveDateTime::parse_from_str(&format!("{}{}", date, time), "%Y%m%d%H%M%S")?
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+    let list = error::array_field(&json, "list", "sse")?;
+
+    let mut prices = HashMap::new();
+    for row in list {
+        let code = match row[0].as_str().and_then(|code| stocks.get(code)) {
+            Some(code) => code,
+            None => continue,
+        };
+        prices.insert(
+            code.to_string(),
+            StockPriceDTO {
+                h: row[3].to_string(),
+                l: row[4].to_string(),
+                o: row[2].to_string(),
+                pc: row[6].to_string(),
+                p: row[5].to_string(),
+                cje: row[9].to_string(),
+                ud: row[7].to_string(),
+                v: row[8].to_string(),
+                yc: row[1].to_string(),
+                t: t.clone(),
+            },
+        );
+    }
+    Ok(prices)
+}
+
+/// 通过纳斯达克自选股接口批量获取当前价格，返回以股票代码为键的价格
+///
+/// 该接口只返回最新价、涨跌额、涨跌幅和成交量，开盘价、最高价和最低价为空。
+async fn get_current_prices_from_nasdaq(
+    exchange: &Exchange,
+    stocks: &[stock_model::Model],
+) -> Result<HashMap<String, StockPriceDTO>, Box<dyn Error>> {
+    // SPX 在 nasdaq.com 没有数据，留给逐个查询走 Yahoo Finance
+    let stocks = stocks
+        .iter()
+        .filter(|stock| stock.code != "SPX.NS")
+        .collect::<Vec<_>>();
+    if stocks.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let environment = application_context.get_environment().await;
+    let base_url = environment
+        .get_property::<String>("stock.api.nasdaq.baseurl")
+        .ok_or_else(|| AppError::missing_property("stock.api.nasdaq.baseurl"))?;
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        "User-Agent",
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36"
+            .parse()?,
+    );
+    headers.insert("Accept", "*/*".parse()?);
+    headers.insert("Accept-Language", "en-US,en;q=0.9".parse()?);
+    let client = reqwest::Client::builder().cookie_store(true).build()?;
+    let t = Local::now()
+        .with_timezone(&exchange.time_zone())
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+
+    let mut prices = HashMap::new();
+    for chunk in stocks.chunks(NASDAQ_WATCHLIST_SIZE) {
+        let symbols = chunk
+            .iter()
+            .map(|stock| {
+                let asset_class = match stock.stock_type.as_str() {
+                    "Index" => "index",
+                    "Fund" => "etf",
+                    _ => "stocks",
                 };
-                if let Some(sym) = yahoo_symbol {
-                    get_current_price_from_yahoo(sym, &self).await
-                } else {
-                    get_current_price_from_nasdaq(self, stock).await
-                }
+                let symbol = format!("{}|{}", stock.stock_code.to_lowercase(), asset_class);
+                ("symbol", symbol)
+            })
+            .collect::<Vec<_>>();
+        let url = format!("{}/api/quote/watchlist", base_url);
+        info!("Get {} stock prices from url = {}", chunk.len(), url);
+        let response = client
+            .get(&url)
+            .query(&symbols)
+            .headers(headers.clone())
+            .send()
+            .await?;
+        let json: Value = response.json().await?;
+        let data = match json["data"].as_array() {
+            Some(data) => data,
+            None => continue,
+        };
+        for item in data {
+            let symbol = item["symbol"].as_str().unwrap_or_default();
+            let stock = match chunk
+                .iter()
+                .find(|stock| stock.stock_code.eq_ignore_ascii_case(symbol))
+            {
+                Some(stock) => stock,
+                None => continue,
+            };
+            let price = item["lastSalePrice"]
+                .as_str()
+                .unwrap_or_default()
+                .replace(['$', ','], "");
+            if price.is_empty() {
+                continue;
             }
+            prices.insert(
+                stock.code.clone(),
+                StockPriceDTO {
+                    h: "".to_string(),
+                    l: "".to_string(),
+                    o: "".to_string(),
+                    pc: item["percentageChange"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .replace(['%', '+'], ""),
+                    p: price,
+                    cje: "".to_string(),
+                    ud: item["netChange"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .replace(['$', ',', '+'], ""),
+                    v: item["volume"].as_str().unwrap_or_default().replace(',', ""),
+                    yc: "".to_string(),
+                    t: t.clone(),
+                },
+            );
         }
     }
+    Ok(prices)
 }
 
-async fn get_current_price_from_sse(code: &str) -> Result<StockPriceDTO, Box<dyn Error>> {
+pub(crate) async fn get_stock_intraday_price_from_sse(
+    stock: &stock_model::Model,
+) -> Result<Vec<StockMinutePrice>, Box<dyn Error>> {
     let application_context = APPLICATION_CONTEXT.read().await;
     let environment = application_context.get_environment().await;
     let base_url = environment
         .get_property::<String>("stock.api.sh.baseurl")
-        .unwrap();
+        .ok_or_else(|| AppError::missing_property("stock.api.sh.baseurl"))?;
+    let url = format!(
+        "{}/v1/sh1/line/{}?begin=0&end=-1&select=time,price,volume,amount&_={}",
+        base_url,
+        &stock.stock_code,
+        Local::now().timestamp_millis()
+    );
+    info!("Get stock {} intraday price from url = {}", stock.code, url);
+    let response = Request::get_response(&url).await?;
+    let json: Value = response.json().await?;
+    Ok(parse_sse_intraday(&json)?)
+}
+
+/// 解析上交所分时数据，每条为 [时间, 价格, 成交量, 成交额]，时间与 `date` 拼接为 yyyyMMddHHmmss
+fn parse_sse_intraday(json: &Value) -> Result<Vec<StockMinutePrice>, AppError> {
+    let date = error::u64_field(json, "date", "sse")?;
+    let line = error::field(json, "line", "sse")?.as_array();
+    let mut stock_prices = Vec::new();
+    if let Some(line) = line {
+        for l in line {
+            let l = l.as_array().ok_or_else(|| AppError::parse("sse", "line"))?;
+            let item = |index: usize| {
+                l.get(index)
+                    .ok_or_else(|| AppError::parse("sse", &format!("line[{}]", index)))
+            };
+            // 分时数据每分钟只有一个价格
+            let time = item(0)?
+                .as_u64()
+                .ok_or_else(|| AppError::parse("sse", "line[0]"))?;
+            let price = BigDecimal::from_str(&item(1)?.to_string())
+                .map_err(|_| AppError::parse("sse", "line[1]"))?;
+            stock_prices.push(StockMinutePrice {
+                time: date * 1_000_000 + time,
+                open: price.clone(),
+                close: price.clone(),
+                high: price.clone(),
+                low: price,
+                volume: BigDecimal::from_str(&item(2)?.to_string()).ok(),
+                amount: BigDecimal::from_str(&item(3)?.to_string()).ok(),
+            });
+        }
+    }
+    Ok(stock_prices)
+}
+
+pub(crate) async fn get_stock_intraday_price_from_szse(
+    stock: &stock_model::Model,
+) -> Result<Vec<StockMinutePrice>, Box<dyn Error>> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let environment = application_context.get_environment().await;
+    let base_url = environment
+        .get_property::<String>("stock.api.sz.baseurl")
+        .ok_or_else(|| AppError::missing_property("stock.api.sz.baseurl"))?;
+    let url = format!(
+        "{}/api/market/ssjjhq/getTimeData?random={}&marketId=1&code={}",
+        base_url,
+        rng().random::<f64>(),
+        &stock.stock_code
+    );
+    info!("Get stock {} intraday price from url = {}", stock.code, url);
+    let response = Request::get_response(&url).await?;
+    let json: Value = response.json().await?;
+    Ok(parse_szse_intraday(&json)?)
+}
+
+/// 解析深交所分时数据，日期取自 `marketTime`，成交量由手换算为股
+fn parse_szse_intraday(json: &Value) -> Result<Vec<StockMinutePrice>, AppError> {
+    let data = error::field(json, "data", "szse")?;
+    let market_time = error::str_field(data, "marketTime", "szse")?;
+    let date = NaiveDateTime::parse_from_str(market_time, "%Y-%m-%d %H:%M:%S")
+        .map_err(|_| AppError::parse("szse", "marketTime"))?
+        .format("%Y%m%d")
+        .to_string();
+    let kline = error::field(data, "picupdata", "szse")?.as_array();
+    let mut stock_prices = Vec::new();
+    if let Some(kline) = kline {
+        for k in kline {
+            // [时间, 最新价, 均价, 涨跌, 涨跌幅, 成交量(手), 成交额]
+            let k = k
+                .as_array()
+                .ok_or_else(|| AppError::parse("szse", "picupdata"))?;
+            let item = |index: usize| {
+                k.get(index)
+                    .ok_or_else(|| AppError::parse("szse", &format!("picupdata[{}]", index)))
+            };
+            let time = item(0)?
+                .as_str()
+                .ok_or_else(|| AppError::parse("szse", "picupdata[0]"))?
+                .replace(':', "");
+            let price = item(1)?
+                .as_str()
+                .and_then(|price| BigDecimal::from_str(price).ok())
+                .ok_or_else(|| AppError::parse("szse", "picupdata[1]"))?;
+            let volume = item(5)?.as_f64().unwrap_or(0.0) * 100.0;
+            stock_prices.push(StockMinutePrice {
+                time: format!("{}{}00", date, time)
+                    .parse::<u64>()
+                    .map_err(|_| AppError::parse("szse", "picupdata[0]"))?,
+                open: price.clone(),
+                close: price.clone(),
+                high: price.clone(),
+                low: price,
+                volume: BigDecimal::from_str(&volume.to_string()).ok(),
+                amount: BigDecimal::from_str(&item(6)?.to_string()).ok(),
+            });
+        }
+    }
+    Ok(stock_prices)
+}
+
+pub(crate) async fn get_stock_intraday_price_from_hkex(
+    exchange: &Exchange,
+    stock: &stock_model::Model,
+) -> Result<Vec<StockMinutePrice>, Box<dyn Error>> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let environment = application_context.get_environment().await;
+    let base_url = environment
+        .get_property::<String>("stock.api.hk.baseurl")
+        .ok_or_else(|| AppError::missing_property("stock.api.hk.baseurl"))?;
+    let token = token_svc::get_hkex_token().await;
+    let timestamp = Local::now().timestamp_millis();
+    let code = if stock.stock_type == "Index" {
+        format!(".{}", stock.stock_code)
+    } else {
+        format!("{:0>4}.HK", stock.stock_code)
+    };
+    let url = format!(
+        "{}/hkexwidget/data/getchartdata2?hchart=1&span=0&int=0&ric={}&token={}&qid={}&callback=jQuery_{}&_={}",
+        base_url, code, token, timestamp, timestamp, timestamp,
+    );
+    info!("Get stock {} intraday price from url = {}", stock.code, url);
+    let response = Request::get_response(&url).await?;
+    let text = response.text().await?;
+    let json = remove_jquery_wrapping_fn_call(&text, "hkex")?;
+    Ok(parse_hkex_intraday(&json, exchange)?)
+}
+
+/// 解析港交所分时数据，每条为 [时间戳毫秒, 开盘, 最高, 最低, 收盘, 成交量, 成交额]，
+/// 开盘价为空的分钟尚未成交，直接跳过
+fn parse_hkex_intraday(
+    json: &Value,
+    exchange: &Exchange,
+) -> Result<Vec<StockMinutePrice>, AppError> {
+    let data = error::field(json, "data", "hkex")?;
+    let kline = error::field(data, "datalist", "hkex")?.as_array();
+    let mut stock_prices = Vec::new();
+    if let Some(kline) = kline {
+        for k in kline {
+            let k = k
+                .as_array()
+                .ok_or_else(|| AppError::parse("hkex", "datalist"))?;
+            let item = |index: usize| {
+                k.get(index)
+                    .ok_or_else(|| AppError::parse("hkex", &format!("datalist[{}]", index)))
+            };
+            let decimal = |index: usize| {
+                item(index).and_then(|value| {
+                    BigDecimal::from_str(&value.to_string())
+                        .map_err(|_| AppError::parse("hkex", &format!("datalist[{}]", index)))
+                })
+            };
+            if item(1)?.is_null() {
+                continue;
+            }
+            let dt: DateTime<Utc> = item(0)?
+                .as_i64()
+                .and_then(DateTime::from_timestamp_millis)
+                .ok_or_else(|| AppError::parse("hkex", "datalist[0]"))?;
+            let time = dt
+                .with_timezone(&exchange.time_zone())
+                .format("%Y%m%d%H%M%S")
+                .to_string();
+            stock_prices.push(StockMinutePrice {
+                time: time
+                    .parse::<u64>()
+                    .map_err(|_| AppError::parse("hkex", "datalist[0]"))?,
+                open: decimal(1)?,
+                high: decimal(2)?,
+                low: decimal(3)?,
+                close: decimal(4)?,
+                volume: decimal(5).ok(),
+                amount: decimal(6).ok(),
+            });
+        }
+    }
+    Ok(stock_prices)
+}
+
+/// Yahoo Finance 图表接口的一根 K 线，价格已按拆股调整
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct YahooQuote {
+    pub timestamp: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: u64,
+}
+
+/// Yahoo Finance 的现金分红
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct YahooDividend {
+    pub date: i64,
+    pub amount: f64,
+}
+
+/// Yahoo Finance 的拆股或合股，`denominator` 股变为 `numerator` 股
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct YahooSplit {
+    pub date: i64,
+    pub numerator: f64,
+    pub denominator: f64,
+}
+
+/// Yahoo Finance 图表接口返回的 K 线、分红和拆股，分红和拆股按日期升序
+#[derive(Debug, Clone, Default)]
+pub(crate) struct YahooChart {
+    pub quotes: Vec<YahooQuote>,
+    pub dividends: Vec<YahooDividend>,
+    pub splits: Vec<YahooSplit>,
+}
+
+/// 从 Yahoo Finance 图表接口获取 K 线、分红和拆股，地址配置在 `stock.api.yahoo.baseurl`
+///
+/// # Arguments
+///
+/// * `yahoo_symbol` - Yahoo Finance 的代码
+/// * `interval` - K 线周期，如 `1m`、`1d`
+/// * `range` - 时间范围，如 `1d`、`1mo`、`max`
+pub(crate) async fn get_yahoo_chart(
+    yahoo_symbol: &str,
+    interval: &str,
+    range: &str,
+) -> Result<YahooChart, Box<dyn Error>> {
+    let url = format!(
+        "{}/v8/finance/chart/{}?interval={}&range={}&events=div%7Csplit",
+        upstream::get_url("stock.api.yahoo.baseurl").await?,
+        yahoo_symbol,
+        interval,
+        range
+    );
+    info!("Get Yahoo Finance chart from url = {}", url);
+    let client = Request::client().await;
+    let response = client
+        .get(url)
+        .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36")
+        .send()
+        .await?;
+    let json: Value = response.json().await?;
+    Ok(parse_yahoo_chart(&json)?)
+}
+
+/// 解析 Yahoo Finance 图表接口，停牌或没有成交的 K 线价格为 `null`，跳过这些 K 线
+fn parse_yahoo_chart(json: &Value) -> Result<YahooChart, AppError> {
+    let chart = error::field(json, "chart", "yahoo")?;
+    let result = error::array_field(chart, "result", "yahoo")?
+        .first()
+        .ok_or_else(|| AppError::parse("yahoo", "result"))?;
+    let mut yahoo_chart = YahooChart::default();
+
+    // 没有任何成交的股票没有 timestamp
+    if let Some(timestamps) = result.get("timestamp").and_then(Value::as_array) {
+        let indicators = error::field(result, "indicators", "yahoo")?;
+        let quote = error::array_field(indicators, "quote", "yahoo")?
+            .first()
+            .ok_or_else(|| AppError::parse("yahoo", "quote"))?;
+        let open = error::array_field(quote, "open", "yahoo")?;
+        let high = error::array_field(quote, "high", "yahoo")?;
+        let low = error::array_field(quote, "low", "yahoo")?;
+        let close = error::array_field(quote, "close", "yahoo")?;
+        let volume = error::array_field(quote, "volume", "yahoo")?;
+        for (i, timestamp) in timestamps.iter().enumerate() {
+            let timestamp = timestamp
+                .as_i64()
+                .ok_or_else(|| AppError::parse("yahoo", "timestamp"))?;
+            let value = |series: &Vec<Value>| series.get(i).and_then(Value::as_f64);
+            let (Some(open), Some(high), Some(low), Some(close)) =
+                (value(open), value(high), value(low), value(close))
+            else {
+                continue;
+            };
+            yahoo_chart.quotes.push(YahooQuote {
+                timestamp,
+                open,
+                high,
+                low,
+                close,
+                volume: volume.get(i).and_then(Value::as_u64).unwrap_or(0),
+            });
+        }
+    }
+
+    let events = result.get("events");
+    let event_values = |name: &str| {
+        events
+            .and_then(|events| events.get(name))
+            .and_then(Value::as_object)
+            .map(|events| events.values().collect::<Vec<_>>())
+            .unwrap_or_default()
+    };
+    let f64_field = |value: &Value, name: &str| {
+        error::field(value, name, "yahoo")?
+            .as_f64()
+            .ok_or_else(|| AppError::parse("yahoo", name))
+    };
+    let i64_field = |value: &Value, name: &str| {
+        error::field(value, name, "yahoo")?
+            .as_i64()
+            .ok_or_else(|| AppError::parse("yahoo", name))
+    };
+    for dividend in event_values("dividends") {
+        yahoo_chart.dividends.push(YahooDividend {
+            date: i64_field(dividend, "date")?,
+            amount: f64_field(dividend, "amount")?,
+        });
+    }
+    for split in event_values("splits") {
+        yahoo_chart.splits.push(YahooSplit {
+            date: i64_field(split, "date")?,
+            numerator: f64_field(split, "numerator")?,
+            denominator: f64_field(split, "denominator")?,
+        });
+    }
+    yahoo_chart.dividends.sort_by_key(|dividend| dividend.date);
+    yahoo_chart.splits.sort_by_key(|split| split.date);
+    Ok(yahoo_chart)
+}
+
+/// 将 Yahoo Finance K 线的时间戳转换为交易所时区的时间，格式为 `format`
+fn format_yahoo_timestamp(timestamp: i64, exchange: &Exchange, format: &str) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .with_timezone(&exchange.time_zone())
+        .format(format)
+        .to_string()
+}
+
+pub(crate) async fn get_stock_intraday_price_from_yahoo(
+    yahoo_symbol: &str,
+    exchange: &Exchange,
+) -> Result<Vec<StockMinutePrice>, Box<dyn Error>> {
+    info!(
+        "Get stock intraday price from Yahoo Finance, symbol: {}",
+        yahoo_symbol
+    );
+    let chart = get_yahoo_chart(yahoo_symbol, "1m", "1d").await?;
+    let mut stock_prices = Vec::new();
+    for quote in chart.quotes {
+        let time = format_yahoo_timestamp(quote.timestamp, exchange, "%Y%m%d%H%M%S");
+        stock_prices.push(StockMinutePrice {
+            time: time.parse::<u64>()?,
+            open: BigDecimal::from_str(&format!("{:.3}", quote.open))?,
+            close: BigDecimal::from_str(&format!("{:.3}", quote.close))?,
+            high: BigDecimal::from_str(&format!("{:.3}", quote.high))?,
+            low: BigDecimal::from_str(&format!("{:.3}", quote.low))?,
+            volume: Some(BigDecimal::from(quote.volume)),
+            amount: None,
+        });
+    }
+    Ok(stock_prices)
+}
+
+/// 将 Yahoo Finance 按拆股调整过的 K 线还原为不复权价格
+///
+/// 拆股当日及之后的 K 线不变，之前的价格乘以之后全部拆股的比例、成交量除以该比例，
+/// 复权时再由公司行动中的拆股计算复权因子，避免重复调整。
+fn unadjust_yahoo_splits(quotes: Vec<YahooQuote>, splits: &[YahooSplit]) -> Vec<YahooQuote> {
+    quotes
+        .into_iter()
+        .map(|quote| {
+            let factor = splits
+                .iter()
+                .filter(|split| split.date > quote.timestamp)
+                .filter(|split| split.numerator > 0.0 && split.denominator > 0.0)
+                .map(|split| split.numerator / split.denominator)
+                .product::<f64>();
+            YahooQuote {
+                open: quote.open * factor,
+                high: quote.high * factor,
+                low: quote.low * factor,
+                close: quote.close * factor,
+                volume: (quote.volume as f64 / factor).round() as u64,
+                ..quote
+            }
+        })
+        .collect()
+}
+
+/// 从 Yahoo Finance 获取港股、美股的全部历史日线
+///
+/// Yahoo 的价格已按拆股调整，按同一接口返回的拆股还原为不复权价格后再入库。
+pub(crate) async fn get_stock_daily_price_from_yahoo(
+    yahoo_symbol: &str,
+    exchange: &Exchange,
+) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
+    info!(
+        "Get stock daily price from Yahoo Finance, symbol: {}",
+        yahoo_symbol
+    );
+    let chart = get_yahoo_chart(yahoo_symbol, "1d", "max").await?;
+    let quotes = unadjust_yahoo_splits(chart.quotes, &chart.splits);
+    let mut stock_prices = Vec::with_capacity(quotes.len());
+    for quote in quotes {
+        let date = format_yahoo_timestamp(quote.timestamp, exchange, "%Y%m%d");
+        stock_prices.push(StockDailyPrice {
+            time: format!("{}093000", date).parse::<u64>()?,
+            open: BigDecimal::from_str(&format!("{:.3}", quote.open))?,
+            close: BigDecimal::from_str(&format!("{:.3}", quote.close))?,
+            high: BigDecimal::from_str(&format!("{:.3}", quote.high))?,
+            low: BigDecimal::from_str(&format!("{:.3}", quote.low))?,
+            volume: Some(BigDecimal::from(quote.volume)),
+            amount: None,
+        });
+    }
+    Ok(stock_prices)
+}
+
+/// 转换为 Yahoo Finance 的代码，指数使用 Yahoo 的指数代码，港股使用 4 位数字加 `.HK`
+pub(crate) fn to_yahoo_symbol(stock: &stock_model::Model) -> String {
+    match stock.code.as_str() {
+        "SPX.NS" => "^GSPC".to_string(),
+        "NDX.NS" => "^NDX".to_string(),
+        "IXIC.NS" => "^IXIC".to_string(),
+        _ if stock.exchange == Exchange::HKEX.as_ref() => {
+            format!("{:0>4}.HK", stock.stock_code.trim_start_matches('0'))
+        }
+        _ => stock.stock_code.replace('.', "-"),
+    }
+}
+
+pub(crate) async fn get_current_price_from_sse(
+    code: &str,
+) -> Result<StockPriceDTO, Box<dyn Error>> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let environment = application_context.get_environment().await;
+    let base_url = environment
+        .get_property::<String>("stock.api.sh.baseurl")
+        .ok_or_else(|| AppError::missing_property("stock.api.sh.baseurl"))?;
     let url = format!(
         "{}/v1/sh1/snap/{}?_={}",
         base_url,
@@ -172,36 +860,49 @@ async fn get_current_price_from_sse(code: &str) -> Result<StockPriceDTO, Box<dyn
     let client = Request::client().await;
     let response = client.get(url).send().await?;
     let json: Value = response.json().await?;
-    let snap = json.get("snap").unwrap();
-    let date = json.get("date").unwrap().to_string();
-    let time = json.get("time").unwrap().to_string();
+    Ok(parse_sse_snap(&json)?)
+}
+
+/// 解析上交所实时行情快照
+fn parse_sse_snap(json: &Value) -> Result<StockPriceDTO, AppError> {
+    let snap = error::array_field(json, "snap", "sse")?;
+    let date = error::field(json, "date", "sse")?.to_string();
+    let time = error::field(json, "time", "sse")?.to_string();
     let time = if time.len() < 6 {
         format!("{}{}", 0, time)
     } else {
         time
     };
+    let snap_field = |index: usize| {
+        snap.get(index)
+            .map(|value| value.to_string())
+            .ok_or_else(|| AppError::parse("sse", &format!("snap[{}]", index)))
+    };
     Ok(StockPriceDTO {
-        h: snap.get(3).unwrap().to_string(),
-        l: snap.get(4).unwrap().to_string(),
-        o: snap.get(2).unwrap().to_string(),
-        pc: snap.get(6).unwrap().to_string(),
-        p: snap.get(5).unwrap().to_string(),
-        cje: snap.get(9).unwrap().to_string(),
-        ud: snap.get(7).unwrap().to_string(),
-        v: snap.get(8).unwrap().to_string(),
-        yc: snap.get(1).unwrap().to_string(),
-        t: NaiveDateTime::parse_from_str(&format!("{}{}", date, time), "%Y%m%d%H%M%S")?
+        h: snap_field(3)?,
+        l: snap_field(4)?,
+        o: snap_field(2)?,
+        pc: snap_field(6)?,
+        p: snap_field(5)?,
+        cje: snap_field(9)?,
+        ud: snap_field(7)?,
+        v: snap_field(8)?,
+        yc: snap_field(1)?,
+        t: NaiveDateTime::parse_from_str(&format!("{}{}", date, time), "%Y%m%d%H%M%S")
+            .map_err(|_| AppError::parse("sse", "date"))?
             .format("%Y-%m-%d %H:%M:%S")
             .to_string(),
     })
 }
 
-async fn get_current_price_from_szse(code: &str) -> Result<StockPriceDTO, Box<dyn Error>> {
+pub(crate) async fn get_current_price_from_szse(
+    code: &str,
+) -> Result<StockPriceDTO, Box<dyn Error>> {
     let application_context = APPLICATION_CONTEXT.read().await;
     let environment = application_context.get_environment().await;
     let base_url = environment
         .get_property::<String>("stock.api.sz.baseurl")
-        .unwrap();
+        .ok_or_else(|| AppError::missing_property("stock.api.sz.baseurl"))?;
     let url = format!(
         "{}/api/market/ssjjhq/getTimeData?random={}&marketId=1&code={}",
         base_url,
@@ -212,73 +913,134 @@ async fn get_current_price_from_szse(code: &str) -> Result<StockPriceDTO, Box<dy
     let client = Request::client().await;
     let response = client.get(url).send().await?;
     let json: Value = response.json().await?;
-    let data = json.get("data").unwrap();
+    Ok(parse_szse_time_data(&json)?)
+}
+
+/// 解析深交所实时行情
+fn parse_szse_time_data(json: &Value) -> Result<StockPriceDTO, AppError> {
+    let data = error::field(json, "data", "szse")?;
     // 成交量原单位是手，需要乘以 100 转换为股数
     let volume_raw = data["volume"].as_f64().unwrap_or(0.0);
     let volume = (volume_raw * 100.0).to_string();
     Ok(StockPriceDTO {
-        h: data["high"].as_str().unwrap().to_string(),
-        l: data["low"].as_str().unwrap().to_string(),
-        o: data["open"].as_str().unwrap().to_string(),
-        pc: data["deltaPercent"].as_str().unwrap().to_string(),
-        p: data["now"].as_str().unwrap().to_string(),
-        cje: data["amount"].as_number().unwrap().to_string(),
-        ud: data["delta"].as_str().unwrap().to_string(),
+        h: error::str_field(data, "high", "szse")?.to_string(),
+        l: error::str_field(data, "low", "szse")?.to_string(),
+        o: error::str_field(data, "open", "szse")?.to_string(),
+        pc: error::str_field(data, "deltaPercent", "szse")?.to_string(),
+        p: error::str_field(data, "now", "szse")?.to_string(),
+        cje: error::number_field(data, "amount", "szse")?,
+        ud: error::str_field(data, "delta", "szse")?.to_string(),
         v: volume,
         yc: "".to_string(),
-        t: data["marketTime"].as_str().unwrap().to_string(),
+        t: error::str_field(data, "marketTime", "szse")?.to_string(),
     })
 }
 
-async fn get_stock_daily_price_from_sse(
+/// 上交所日 K 线接口单次返回的最大 K 线数
+const SSE_DAYK_PAGE: i64 = 1000;
+
+/// 从上交所获取日线，单次最多返回 `SSE_DAYK_PAGE` 根，从最近的 K 线开始逐页向前查询，
+/// 查询到 `since` 之前的 K 线或全部历史后停止，`since` 为空时获取全部历史
+pub(crate) async fn get_stock_daily_price_from_sse(
     stock: &stock_model::Model,
+    since: Option<NaiveDate>,
 ) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
     let application_context = APPLICATION_CONTEXT.read().await;
     let environment = application_context.get_environment().await;
-    let mut stock_prices = Vec::new();
-    let url = environment
+    let base_url = environment
         .get_property::<String>("stock.api.sh.baseurl")
-        .unwrap();
-    let url = format!(
-        "{}/v1/sh1/dayk/{}?begin=-1000&end=-1&period=day&_={}",
-        url,
-        &stock.stock_code,
-        Local::now().timestamp_millis()
-    );
-    let response = Request::get_response(&url).await?;
-    let json: Value = response.json().await?;
-    let kline = json.get("kline").unwrap().as_array();
+        .ok_or_else(|| AppError::missing_property("stock.api.sh.baseurl"))?;
+    let since = since
+        .map(|since| format!("{}093000", since.format("%Y%m%d")).parse::<u64>())
+        .transpose()?;
+
+    let mut pages = Vec::new();
+    let mut count = 0;
+    let mut previous = None;
+    for page in 0.. {
+        let url = sse_dayk_url(
+            &base_url,
+            &stock.stock_code,
+            page,
+            Local::now().timestamp_millis(),
+        );
+        let response = Request::get_response(&url).await?;
+        let json: Value = response.json().await?;
+        let prices = parse_sse_daily_prices(&json)?;
+        let total = json.get("total").and_then(|total| total.as_u64());
+        // 接口忽略分页参数时会重复返回同一页，此时停止查询
+        let oldest = match prices.first() {
+            Some(price) if previous.is_none_or(|previous| price.time < previous) => price.time,
+            _ => break,
+        };
+        previous = Some(oldest);
+        count += prices.len() as u64;
+        let full_page = prices.len() as i64 >= SSE_DAYK_PAGE;
+        pages.push(prices);
+        if !full_page
+            || total.is_some_and(|total| count >= total)
+            || since.is_some_and(|since| oldest <= since)
+        {
+            break;
+        }
+    }
+    Ok(pages.into_iter().rev().flatten().collect())
+}
+
+/// 上交所日 K 线的查询地址，`begin` 和 `end` 为从最近一根 K 线倒数的序号，第 `page` 页
+/// 为倒数第 `page * SSE_DAYK_PAGE + 1` 到 `(page + 1) * SSE_DAYK_PAGE` 根
+fn sse_dayk_url(base_url: &str, stock_code: &str, page: i64, timestamp: i64) -> String {
+    format!(
+        "{}/v1/sh1/dayk/{}?begin={}&end={}&period=day&_={}",
+        base_url,
+        stock_code,
+        -(page + 1) * SSE_DAYK_PAGE,
+        -page * SSE_DAYK_PAGE - 1,
+        timestamp
+    )
+}
+
+/// 解析上交所日 K 线，每根 K 线为 [日期, 开盘, 最高, 最低, 收盘, 成交量, 成交额]
+fn parse_sse_daily_prices(json: &Value) -> Result<Vec<StockDailyPrice>, AppError> {
+    let mut stock_prices = Vec::new();
+    let kline = error::field(json, "kline", "sse")?.as_array();
     if let Some(kline) = kline {
         for k in kline {
-            let k = k.as_array().unwrap();
+            let k = k
+                .as_array()
+                .ok_or_else(|| AppError::parse("sse", "kline"))?;
+            let item = |index: usize| {
+                k.get(index)
+                    .map(|value| value.to_string())
+                    .ok_or_else(|| AppError::parse("sse", &format!("kline[{}]", index)))
+            };
             let price = StockDailyPriceDTO {
-                t: k.first().unwrap().to_string() + "093000",
-                o: k.get(1).unwrap().to_string(),
-                h: k.get(2).unwrap().to_string(),
-                l: k.get(3).unwrap().to_string(),
-                c: k.get(4).unwrap().to_string(),
-                v: k.get(5).unwrap().to_string(),
-                e: k.get(6).unwrap().to_string(),
+                t: item(0)? + "093000",
+                o: item(1)?,
+                h: item(2)?,
+                l: item(3)?,
+                c: item(4)?,
+                v: item(5)?,
+                e: item(6)?,
                 zd: "".to_string(),
                 zdf: "".to_string(),
                 hs: "".to_string(),
             };
-            let price = create_stock_daily_price(&price);
+            let price = create_stock_daily_price(&price, "sse")?;
             stock_prices.push(price);
         }
     }
     Ok(stock_prices)
 }
 
-async fn get_stock_daily_price_from_szse(
+pub(crate) async fn get_stock_daily_price_from_szse(
     stock: &stock_model::Model,
 ) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
     let application_context = APPLICATION_CONTEXT.read().await;
     let environment = application_context.get_environment().await;
-    let mut stock_prices = Vec::new();
     let url = environment
         .get_property::<String>("stock.api.sz.baseurl")
-        .unwrap();
+        .ok_or_else(|| AppError::missing_property("stock.api.sz.baseurl"))?;
     let url = format!(
         "{}/api/market/ssjjhq/getHistoryData?random={}&cycleType=32&marketId=1&code={}",
         url,
@@ -287,50 +1049,59 @@ async fn get_stock_daily_price_from_szse(
     );
     let response = Request::get_response(&url).await?;
     let json: Value = response.json().await?;
-    let kline = json
-        .get("data")
-        .unwrap()
-        .get("picupdata")
-        .unwrap()
-        .as_array();
+    Ok(parse_szse_daily_prices(&json)?)
+}
+
+/// 解析深交所日 K 线，每根 K 线为 [日期, 开盘, 收盘, 最低, 最高, 涨跌额, 涨跌幅, 成交量, 成交额]
+fn parse_szse_daily_prices(json: &Value) -> Result<Vec<StockDailyPrice>, AppError> {
+    let mut stock_prices = Vec::new();
+    let data = error::field(json, "data", "szse")?;
+    let kline = error::field(data, "picupdata", "szse")?.as_array();
     if let Some(kline) = kline {
         for k in kline {
-            let k = k.as_array().unwrap();
-            let price = StockDailyPriceDTO {
-                t: k.first()
-                    .unwrap()
+            let k = k
+                .as_array()
+                .ok_or_else(|| AppError::parse("szse", "picupdata"))?;
+            let item = |index: usize| {
+                k.get(index)
+                    .ok_or_else(|| AppError::parse("szse", &format!("picupdata[{}]", index)))
+            };
+            let text = |index: usize| {
+                item(index)?
                     .as_str()
-                    .unwrap()
-                    .to_string()
-                    .replace('-', "")
-                    + "093000",
-                o: k.get(1).unwrap().as_str().unwrap().to_string(),
-                c: k.get(2).unwrap().as_str().unwrap().to_string(),
-                l: k.get(3).unwrap().as_str().unwrap().to_string(),
-                h: k.get(4).unwrap().as_str().unwrap().to_string(),
-                zd: k.get(5).unwrap().as_str().unwrap().to_string(),
-                zdf: k.get(6).unwrap().as_str().unwrap().to_string(),
-                v: k.get(7).unwrap().to_string(),
-                e: k.get(8).unwrap().to_string(),
+                    .map(|value| value.to_string())
+                    .ok_or_else(|| AppError::parse("szse", &format!("picupdata[{}]", index)))
+            };
+            let price = StockDailyPriceDTO {
+                t: text(0)?.replace('-', "") + "093000",
+                o: text(1)?,
+                c: text(2)?,
+                l: text(3)?,
+                h: text(4)?,
+                zd: text(5)?,
+                zdf: text(6)?,
+                v: item(7)?.to_string(),
+                e: item(8)?.to_string(),
                 hs: "".to_string(),
             };
-            let price = create_stock_daily_price(&price);
+            let price = create_stock_daily_price(&price, "szse")?;
             stock_prices.push(price);
         }
     }
     Ok(stock_prices)
 }
 
-async fn get_stock_daily_price_from_hkex(
+/// 从港交所获取日线，港交所的图表接口只返回最近数年的日线，更早的日线由数据源链中
+/// 后续的数据源补齐
+pub(crate) async fn get_stock_daily_price_from_hkex(
     stock: &stock_model::Model,
 ) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
     let exchange = Exchange::from_str(stock.exchange.as_str())?;
     let application_context = APPLICATION_CONTEXT.read().await;
     let environment = application_context.get_environment().await;
-    let mut stock_prices = Vec::new();
     let url = environment
         .get_property::<String>("stock.api.hk.baseurl")
-        .unwrap();
+        .ok_or_else(|| AppError::missing_property("stock.api.hk.baseurl"))?;
     let token = token_svc::get_hkex_token().await;
     let timestramp = Local::now().timestamp_millis();
     let code = if stock.stock_type == "Index" {
@@ -344,141 +1115,163 @@ async fn get_stock_daily_price_from_hkex(
     );
     let response = Request::get_response(&url).await?;
     let text = response.text().await?;
-    let json = remove_jquery_wrapping_fn_call(&text);
-    let kline = json
-        .get("data")
-        .unwrap()
-        .get("datalist")
-        .unwrap()
-        .as_array();
+    let json = remove_jquery_wrapping_fn_call(&text, "hkex")?;
+    let mut stock_prices = parse_hkex_daily_prices(&json, &exchange)?;
+    let date = Local::now()
+        .with_timezone(&exchange.time_zone())
+        .format("%Y%m%d")
+        .to_string()
+        + "093000";
+    let today = date.parse::<u64>()?;
+    let holiday_result = is_holiday(exchange.as_ref()).await?;
+    let market_closed = is_market_closed(&exchange).await?;
+    if !holiday_result && market_closed && stock_prices.iter().all(|p| p.time != today) {
+        // append today price
+        let stock_price = exchange.get_stock_price(&stock).await?;
+        let date = NaiveDateTime::parse_from_str(&stock_price.t, "%Y-%m-%d %H:%M:%S")?
+            .format("%Y%m%d")
+            .to_string()
+            + "093000";
+        let dto = StockDailyPriceDTO {
+            t: date,
+            o: stock_price.o,
+            h: stock_price.h,
+            l: stock_price.l,
+            c: stock_price.p,
+            v: stock_price.v,
+            e: stock_price.cje,
+            zd: stock_price.ud,
+            zdf: stock_price.pc,
+            hs: "".to_string(),
+        };
+        let price = create_stock_daily_price(&dto, "hkex")?;
+        stock_prices.push(price);
+    }
+    Ok(stock_prices)
+}
+
+/// 解析港交所日 K 线，每根 K 线为 [时间戳毫秒, 开盘, 最高, 最低, 收盘, 成交量, 成交额]，
+/// 开盘价为空或为负数的 K 线表示停牌，直接跳过
+fn parse_hkex_daily_prices(
+    json: &Value,
+    exchange: &Exchange,
+) -> Result<Vec<StockDailyPrice>, AppError> {
+    let mut stock_prices = Vec::new();
+    let data = error::field(json, "data", "hkex")?;
+    let kline = error::field(data, "datalist", "hkex")?.as_array();
     if let Some(kline) = kline {
-        let mut dates = Vec::new();
         for k in kline {
-            let k = k.as_array().unwrap();
-            let o = k.get(1).unwrap();
-            if o.is_null() {
-                continue;
-            }
-            if o.as_f64().unwrap() < 0.0 {
+            let k = k
+                .as_array()
+                .ok_or_else(|| AppError::parse("hkex", "datalist"))?;
+            let number = |index: usize| {
+                k.get(index)
+                    .and_then(|value| value.as_number())
+                    .ok_or_else(|| AppError::parse("hkex", &format!("datalist[{}]", index)))
+            };
+            let o = match k.get(1).and_then(|value| value.as_number()) {
+                Some(o) => o,
+                None => continue,
+            };
+            if o.as_f64().is_none_or(|o| o < 0.0) {
                 continue;
             }
-            let o = o.as_number().unwrap().to_string();
-            let dt: DateTime<Utc> =
-                DateTime::from_timestamp_millis(k.first().unwrap().as_i64().unwrap()).unwrap();
+            let dt: DateTime<Utc> = number(0)?
+                .as_i64()
+                .and_then(DateTime::from_timestamp_millis)
+                .ok_or_else(|| AppError::parse("hkex", "datalist[0]"))?;
             let date = dt
                 .with_timezone(&exchange.time_zone())
                 .format("%Y%m%d")
                 .to_string()
                 + "093000";
-            dates.push(date.clone());
             let price = StockDailyPriceDTO {
                 t: date,
-                o,
-                c: k.get(4).unwrap().as_number().unwrap().to_string(),
-                l: k.get(3).unwrap().as_number().unwrap().to_string(),
-                h: k.get(2).unwrap().as_number().unwrap().to_string(),
+                o: o.to_string(),
+                c: number(4)?.to_string(),
+                l: number(3)?.to_string(),
+                h: number(2)?.to_string(),
                 zd: "".to_string(),
                 zdf: "".to_string(),
-                v: k.get(5).unwrap().as_number().unwrap().to_string(),
-                e: k.get(6).unwrap().as_number().unwrap().to_string(),
-                hs: "".to_string(),
-            };
-            let price = create_stock_daily_price(&price);
-            stock_prices.push(price);
-        }
-        let date = Local::now()
-            .with_timezone(&exchange.time_zone())
-            .format("%Y%m%d")
-            .to_string()
-            + "093000";
-        let holiday_result = is_holiday(exchange.as_ref()).await?;
-        let market_closed = is_market_closed(&exchange).await?;
-        if !holiday_result && market_closed && !dates.contains(&date) {
-            // append today price
-            let stock_price = exchange.get_stock_price(&stock).await?;
-            let date = NaiveDateTime::parse_from_str(&stock_price.t, "%Y-%m-%d %H:%M:%S")?
-                .format("%Y%m%d")
-                .to_string()
-                + "093000";
-            let dto = StockDailyPriceDTO {
-                t: date,
-                o: stock_price.o,
-                h: stock_price.h,
-                l: stock_price.l,
-                c: stock_price.p,
-                v: stock_price.v,
-                e: stock_price.cje,
-                zd: stock_price.ud,
-                zdf: stock_price.pc,
+                v: number(5)?.to_string(),
+                e: number(6)?.to_string(),
                 hs: "".to_string(),
             };
-            let price = create_stock_daily_price(&dto);
+            let price = create_stock_daily_price(&price, "hkex")?;
             stock_prices.push(price);
         }
     }
     Ok(stock_prices)
 }
 
-async fn get_stock_daily_price_from_akshare_zh_a(
+/// 获取 A 股不复权日线，复权由公司行动在本地计算
+pub(crate) async fn get_stock_daily_price_from_akshare_zh_a(
     exchange: &Exchange,
     stock: &stock_model::Model,
 ) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
     let base_url = get_akshare_base_url().await?;
     let symbol = to_akshare_symbol(exchange, &stock.stock_code);
     let url = format!(
-        "{}/api/public/stock_zh_a_daily?symbol={}&adjust=qfq",
+        "{}/api/public/stock_zh_a_daily?symbol={}&adjust=",
         base_url, symbol
     );
     parse_akshare_kline(&url).await
 }
 
-pub async fn get_stock_daily_price(
-    stock: &stock_model::Model,
-) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
-    let exchange = Exchange::from_str(stock.exchange.as_str())?;
-    info!(
-        "Get stock daily price from {}, code = {}, type = {}",
-        exchange.as_ref(),
-        stock.stock_code,
-        stock.stock_type
+/// 从 akshare 的东方财富盘口获取当前价格，北交所没有可用的官方行情接口
+pub(crate) async fn get_current_price_from_akshare(
+    exchange: &Exchange,
+    code: &str,
+) -> Result<StockPriceDTO, Box<dyn Error>> {
+    let url = format!(
+        "{}/api/public/stock_bid_ask_em?symbol={}",
+        get_akshare_base_url().await?,
+        code
     );
-    match exchange {
-        Exchange::SSE => {
-            if stock.stock_type == "Stock" {
-                get_stock_daily_price_from_akshare_zh_a(&exchange, stock).await
-            } else {
-                get_stock_daily_price_from_sse(stock).await
-            }
-        }
-        Exchange::SZSE => {
-            if stock.stock_type == "Stock" {
-                get_stock_daily_price_from_akshare_zh_a(&exchange, stock).await
-            } else {
-                get_stock_daily_price_from_szse(stock).await
-            }
-        }
-        Exchange::HKEX => get_stock_daily_price_from_hkex(stock).await,
-        Exchange::NASDAQ => {
-            let code = &stock.code;
-            if code == "NDX.NS" || code == "SPX.NS" || code == "IXIC.NS" {
-                let symbol = if code == "SPX.NS" {
-                    ".INX"
-                } else if code == "NDX.NS" {
-                    ".NDX"
-                } else {
-                    ".IXIC"
-                };
-                get_index_stock_daily_price_from_akshare(&exchange, symbol).await
-            } else if regex::Regex::new(r"^[A-Z]+\.[A-Z]+\.NS$")?.is_match(code) {
-                get_stock_daily_price_from_akshare(&exchange, stock).await
-            } else {
-                get_stock_daily_price_from_nasdaq(&exchange, stock).await
-            }
-        }
-    }
+    info!("Get stock {} current price from url = {}", code, url);
+    let response = Request::get_response(&url).await?;
+    let json: Value = response.json().await?;
+    let time = Utc::now()
+        .with_timezone(&exchange.time_zone())
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+    Ok(parse_akshare_bid_ask(&json, time)?)
+}
+
+/// 解析 akshare 盘口，每行为 `{"item": 名称, "value": 数值}`，成交量由手转换为股数
+fn parse_akshare_bid_ask(json: &Value, time: String) -> Result<StockPriceDTO, AppError> {
+    let items = json
+        .as_array()
+        .ok_or_else(|| AppError::parse("akshare", "array"))?;
+    let values = items
+        .iter()
+        .filter_map(|item| Some((item["item"].as_str()?, item["value"].as_f64()?)))
+        .collect::<HashMap<_, _>>();
+    let value = |name: &str| {
+        values
+            .get(name)
+            .map(|value| value.to_string())
+            .ok_or_else(|| AppError::parse("akshare", name))
+    };
+    let volume = values
+        .get("总手")
+        .map(|volume| volume * 100.0)
+        .unwrap_or(0.0);
+    Ok(StockPriceDTO {
+        h: value("最高")?,
+        l: value("最低")?,
+        o: value("今开")?,
+        pc: value("涨幅")?,
+        p: value("最新")?,
+        cje: value("金额")?,
+        ud: value("涨跌")?,
+        v: volume.to_string(),
+        yc: value("昨收")?,
+        t: time,
+    })
 }
 
-async fn get_index_stock_daily_price_from_akshare(
+pub(crate) async fn get_index_stock_daily_price_from_akshare(
     _exchange: &Exchange,
     symbol: &str,
 ) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
@@ -490,7 +1283,7 @@ async fn get_index_stock_daily_price_from_akshare(
     parse_akshare_kline(&url).await
 }
 
-async fn get_stock_daily_price_from_nasdaq(
+pub(crate) async fn get_stock_daily_price_from_nasdaq(
     exchange: &Exchange,
     stock: &stock_model::Model,
 ) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
@@ -498,7 +1291,7 @@ async fn get_stock_daily_price_from_nasdaq(
     let environment = application_context.get_environment().await;
     let url = environment
         .get_property::<String>("stock.api.nasdaq.charting")
-        .unwrap();
+        .ok_or_else(|| AppError::missing_property("stock.api.nasdaq.charting"))?;
     let now = Utc::now().with_timezone(&exchange.time_zone());
     let today = now.format("%Y-%m-%d").to_string();
     let five_years_ago_year = now.year() - 5;
@@ -526,27 +1319,33 @@ async fn get_stock_daily_price_from_nasdaq(
     let client = reqwest::Client::builder().cookie_store(true).build()?;
     let response = client.get(&url).headers(headers).send().await?;
     let data: Value = response.json().await?;
-    let kline = data.get("marketData").unwrap().as_array();
+    Ok(parse_nasdaq_daily_prices(&data)?)
+}
+
+/// 解析纳斯达克图表接口返回的历史日线
+fn parse_nasdaq_daily_prices(data: &Value) -> Result<Vec<StockDailyPrice>, AppError> {
+    let kline = error::field(data, "marketData", "nasdaq")?.as_array();
     let mut stock_prices = Vec::new();
     if let Some(kline) = kline {
         for k in kline {
-            let datetime = k["Date"].as_str().unwrap();
-            let datetime = NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M:%S");
-            let date = datetime?.format("%Y%m%d").to_string();
+            let datetime = error::str_field(k, "Date", "nasdaq")?;
+            let datetime = NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M:%S")
+                .map_err(|_| AppError::parse("nasdaq", "Date"))?;
+            let date = datetime.format("%Y%m%d").to_string();
             let date = format!("{}093000", date);
             let price = StockDailyPriceDTO {
                 t: date,
-                o: k["Open"].as_number().unwrap().to_string(),
-                c: k["Close"].as_number().unwrap().to_string(),
-                l: k["Low"].as_number().unwrap().to_string(),
-                h: k["High"].as_number().unwrap().to_string(),
+                o: error::number_field(k, "Open", "nasdaq")?,
+                c: error::number_field(k, "Close", "nasdaq")?,
+                l: error::number_field(k, "Low", "nasdaq")?,
+                h: error::number_field(k, "High", "nasdaq")?,
                 zd: "".to_string(),
                 zdf: "".to_string(),
-                v: k["Volume"].as_number().unwrap().to_string(),
+                v: error::number_field(k, "Volume", "nasdaq")?,
                 e: "".to_string(),
                 hs: "".to_string(),
             };
-            let price = create_stock_daily_price(&price);
+            let price = create_stock_daily_price(&price, "nasdaq")?;
             stock_prices.push(price);
         }
     }
@@ -577,7 +1376,7 @@ pub struct StockPriceDTO {
     pub t: String,
 }
 
-async fn get_current_stock_price_from_hk(
+pub(crate) async fn get_current_stock_price_from_hk(
     exchange: &Exchange,
     code: &str,
 ) -> Result<StockPriceDTO, Box<dyn Error>> {
@@ -585,7 +1384,7 @@ async fn get_current_stock_price_from_hk(
     let environment = application_context.get_environment().await;
     let base_url = environment
         .get_property::<String>("stock.api.hk.baseurl")
-        .unwrap();
+        .ok_or_else(|| AppError::missing_property("stock.api.hk.baseurl"))?;
     let token = token_svc::get_hkex_token().await;
     let timestamp = Utc::now()
         .with_timezone(&exchange.time_zone())
@@ -598,34 +1397,38 @@ async fn get_current_stock_price_from_hk(
     let client = Request::client().await;
     let response = client.get(url).send().await?;
     let text = response.text().await?;
-    let json = remove_jquery_wrapping_fn_call(&text);
-    let data = json.get("data").unwrap();
-    let data = data.get("quote").unwrap();
-    let v = data["vo"].as_str().unwrap().to_string();
-    let vo_u = data["vo_u"].as_str().unwrap().to_string();
-    let v = cal_value(&v, &vo_u);
-    let am = data["am"].as_str().unwrap().to_string();
-    let am_u = data["am_u"].as_str().unwrap().to_string();
-    let am = cal_value(&am, &am_u);
-    let update_time = data["updatetime"].as_str().unwrap().to_string();
-    let t = NaiveDateTime::parse_from_str(&update_time, "%Y年%m月%d日%H:%M")?
+    let json = remove_jquery_wrapping_fn_call(&text, "hkex")?;
+    Ok(parse_hkex_equity_quote(&json)?)
+}
+
+/// 解析港交所股票实时报价，成交量和成交额按单位（K、M、B）换算
+fn parse_hkex_equity_quote(json: &Value) -> Result<StockPriceDTO, AppError> {
+    let data = error::field(json, "data", "hkex")?;
+    let data = error::field(data, "quote", "hkex")?;
+    let text = |name: &str| error::str_field(data, name, "hkex").map(|value| value.to_string());
+    let v = cal_value(&text("vo")?, &text("vo_u")?).ok_or_else(|| AppError::parse("hkex", "vo"))?;
+    let am =
+        cal_value(&text("am")?, &text("am_u")?).ok_or_else(|| AppError::parse("hkex", "am"))?;
+    let update_time = text("updatetime")?;
+    let t = NaiveDateTime::parse_from_str(&update_time, "%Y年%m月%d日%H:%M")
+        .map_err(|_| AppError::parse("hkex", "updatetime"))?
         .format("%Y-%m-%d %H:%M:%S")
         .to_string();
     Ok(StockPriceDTO {
-        h: data["hi"].as_str().unwrap().to_string(),
-        l: data["lo"].as_str().unwrap().to_string(),
-        o: data["op"].as_str().unwrap().to_string(),
-        pc: data["pc"].as_str().unwrap().to_string(),
-        p: data["ls"].as_str().unwrap().to_string(),
+        h: text("hi")?,
+        l: text("lo")?,
+        o: text("op")?,
+        pc: text("pc")?,
+        p: text("ls")?,
         cje: am.to_string(),
-        ud: data["nc"].as_str().unwrap().to_string(),
+        ud: text("nc")?,
         v: v.to_string(),
-        yc: data["hc"].as_str().unwrap().to_string(),
+        yc: text("hc")?,
         t,
     })
 }
 
-async fn get_current_index_price_from_hk(
+pub(crate) async fn get_current_index_price_from_hk(
     exchange: &Exchange,
     code: &str,
 ) -> Result<StockPriceDTO, Box<dyn Error>> {
@@ -633,7 +1436,7 @@ async fn get_current_index_price_from_hk(
     let environment = application_context.get_environment().await;
     let base_url = environment
         .get_property::<String>("stock.api.hk.baseurl")
-        .unwrap();
+        .ok_or_else(|| AppError::missing_property("stock.api.hk.baseurl"))?;
     let token = token_svc::get_hkex_token().await;
     let timestamp = Utc::now()
         .with_timezone(&exchange.time_zone())
@@ -646,47 +1449,58 @@ async fn get_current_index_price_from_hk(
     let client = Request::client().await;
     let response = client.get(url).send().await?;
     let text = response.text().await?;
-    let json = remove_jquery_wrapping_fn_call(&text);
-    let data = json.get("data").unwrap();
-    let datalist = data.get("datalist").unwrap().as_array();
+    let json = remove_jquery_wrapping_fn_call(&text, "hkex")?;
+    let data = error::field(&json, "data", "hkex")?;
+    let datalist = error::field(data, "datalist", "hkex")?.as_array();
     let mut open = 0f64;
     let mut high = 0f64;
-    let mut low = f64::max_value();
+    let mut low = f64::MAX;
     let mut volume = 0f64;
     let mut amount = 0f64;
     let mut t = "".to_string();
     let mut price = 0f64;
     if let Some(klines) = datalist {
         for k in klines {
-            let k = k.as_array().unwrap();
-            let o = k.get(1).unwrap();
-            if o.is_null() {
+            let k = k
+                .as_array()
+                .ok_or_else(|| AppError::parse("hkex", "datalist"))?;
+            let item = |index: usize| {
+                k.get(index)
+                    .ok_or_else(|| AppError::parse("hkex", &format!("datalist[{}]", index)))
+            };
+            let number = |index: usize| {
+                item(index).and_then(|value| {
+                    value
+                        .as_f64()
+                        .ok_or_else(|| AppError::parse("hkex", &format!("datalist[{}]", index)))
+                })
+            };
+            if item(1)?.is_null() {
                 continue;
             }
-            let dt: DateTime<Utc> =
-                DateTime::from_timestamp_millis(k.first().unwrap().as_i64().unwrap()).unwrap();
+            let dt: DateTime<Utc> = item(0)?
+                .as_i64()
+                .and_then(DateTime::from_timestamp_millis)
+                .ok_or_else(|| AppError::parse("hkex", "datalist[0]"))?;
             t = dt
                 .with_timezone(&Local)
                 .format("%Y-%m-%d %H:%M:%S")
                 .to_string();
-            let o = o.as_f64().unwrap();
+            let o = number(1)?;
             if open == 0f64 {
                 open = o;
             }
-            let h = k.get(2).unwrap().as_f64().unwrap();
+            let h = number(2)?;
             if h > high {
                 high = h;
             }
-            let l = k.get(3).unwrap().as_f64().unwrap();
+            let l = number(3)?;
             if l < low {
                 low = l;
             }
-            let v = k.get(5).unwrap().as_f64().unwrap();
-            volume += v;
-            let e = k.get(6).unwrap().as_f64().unwrap();
-            amount += e;
-            let c = k.get(4).unwrap().as_f64().unwrap();
-            price = c;
+            volume += number(5)?;
+            amount += number(6)?;
+            price = number(4)?;
         }
     }
     Ok(StockPriceDTO {
@@ -711,7 +1525,7 @@ async fn get_latest_intraday_data_from_nasdaq(
     let environment = application_context.get_environment().await;
     let url = environment
         .get_property::<String>("stock.api.nasdaq.charting")
-        .unwrap();
+        .ok_or_else(|| AppError::missing_property("stock.api.nasdaq.charting"))?;
     let now = Utc::now().with_timezone(&exchange.time_zone());
     let today = now.format("%Y-%m-%d").to_string();
     let url = format!(
@@ -736,21 +1550,28 @@ async fn get_latest_intraday_data_from_nasdaq(
     let client = reqwest::Client::builder().cookie_store(true).build()?;
     let response = client.get(&url).headers(headers).send().await?;
     let data: Value = response.json().await?;
-    let latest_intraday_data = data.get("latestIntradayData").unwrap();
+    let latest_intraday_data = error::field(&data, "latestIntradayData", "nasdaq")?;
+    let number = |name: &str| {
+        error::field(latest_intraday_data, name, "nasdaq").and_then(|value| {
+            value
+                .as_f64()
+                .ok_or_else(|| AppError::parse("nasdaq", name))
+        })
+    };
     // Round to 3 decimal places before converting to string
-    let open_value = latest_intraday_data.get("Open").unwrap().as_f64().unwrap();
+    let open_value = number("Open")?;
     let open = (open_value * 1000.0).round() / 1000.0;
     let open = open.to_string();
 
-    let close_value = latest_intraday_data.get("Close").unwrap().as_f64().unwrap();
+    let close_value = number("Close")?;
     let close = (close_value * 1000.0).round() / 1000.0;
     let close = close.to_string();
 
-    let low_value = latest_intraday_data.get("Low").unwrap().as_f64().unwrap();
+    let low_value = number("Low")?;
     let low = (low_value * 1000.0).round() / 1000.0;
     let low = low.to_string();
 
-    let high_value = latest_intraday_data.get("High").unwrap().as_f64().unwrap();
+    let high_value = number("High")?;
     let high = (high_value * 1000.0).round() / 1000.0;
     let high = high.to_string();
 
@@ -759,18 +1580,8 @@ async fn get_latest_intraday_data_from_nasdaq(
         .and_then(|v| v.as_f64())
         .unwrap_or(0.0)
         .to_string();
-    let ud: String = latest_intraday_data
-        .get("Change")
-        .unwrap()
-        .as_f64()
-        .unwrap()
-        .to_string();
-    let date = latest_intraday_data
-        .get("Date")
-        .unwrap()
-        .as_str()
-        .unwrap()
-        .to_string();
+    let ud = number("Change")?.to_string();
+    let date = error::str_field(latest_intraday_data, "Date", "nasdaq")?.to_string();
 
     Ok(StockPriceDTO {
         h: high,
@@ -808,7 +1619,7 @@ async fn get_open_price_from_nasdaq(
     Ok(open)
 }
 
-async fn get_current_price_from_nasdaq(
+pub(crate) async fn get_current_price_from_nasdaq(
     exchange: &Exchange,
     stock: &stock_model::Model,
 ) -> Result<StockPriceDTO, Box<dyn Error>> {
@@ -816,7 +1627,7 @@ async fn get_current_price_from_nasdaq(
     let environment = application_context.get_environment().await;
     let base_url = environment
         .get_property::<String>("stock.api.nasdaq.baseurl")
-        .unwrap();
+        .ok_or_else(|| AppError::missing_property("stock.api.nasdaq.baseurl"))?;
     let url = if stock.stock_type == "Index" {
         format!(
             "{}/api/quote/{}/info?assetclass=index",
@@ -847,31 +1658,28 @@ async fn get_current_price_from_nasdaq(
     let client = reqwest::Client::builder().cookie_store(true).build()?;
     let response = client.get(&url).headers(headers).send().await?;
     let text: Value = response.json().await?;
-    let data = text.get("data").unwrap();
-    let market_status = data.get("marketStatus").unwrap().as_str().unwrap();
+    let data = error::field(&text, "data", "nasdaq")?;
+    let market_status = error::str_field(data, "marketStatus", "nasdaq")?;
     match market_status {
         "Closed" | "After-Hours" | "Pre-Market" => {
             get_latest_intraday_data_from_nasdaq(exchange, stock).await
         }
         _ => {
-            let primary_data = data.get("primaryData").unwrap();
-            let key_stats = data.get("keyStats").unwrap();
+            let primary_data = error::field(data, "primaryData", "nasdaq")?;
+            let key_stats = error::field(data, "keyStats", "nasdaq")?;
+            let primary = |name: &str| {
+                error::str_field(primary_data, name, "nasdaq").map(|value| value.to_string())
+            };
             let mut price: String;
             let mut v: String;
             let mut pc: String;
             let mut ud: String;
             let update_time: String;
-            price = primary_data["lastSalePrice"].as_str().unwrap().to_string();
-            v = primary_data["volume"].as_str().unwrap().to_string();
-            pc = primary_data["percentageChange"]
-                .as_str()
-                .unwrap()
-                .to_string();
-            ud = primary_data["netChange"].as_str().unwrap().to_string();
-            update_time = primary_data["lastTradeTimestamp"]
-                .as_str()
-                .unwrap()
-                .to_string();
+            price = primary("lastSalePrice")?;
+            v = primary("volume")?;
+            pc = primary("percentageChange")?;
+            ud = primary("netChange")?;
+            update_time = primary("lastTradeTimestamp")?;
             price = price.replace("$", "").replace(",", "");
             pc = pc.replace("%", "").replace("+", "");
             v = v.replace(",", "");
@@ -895,12 +1703,15 @@ async fn get_current_price_from_nasdaq(
                 v = dto.v;
             }
 
-            let t = NaiveDateTime::parse_from_str(
-                &update_time[..update_time.len() - 3],
-                "%b %d, %Y %I:%M %p",
-            )?
-            .format("%Y-%m-%d %H:%M:%S")
-            .to_string();
+            // 去掉末尾的时区，如 "Jan 02, 2024 4:00 PM ET"
+            let update_time = update_time
+                .len()
+                .checked_sub(3)
+                .and_then(|end| update_time.get(..end))
+                .ok_or_else(|| AppError::parse("nasdaq", "lastTradeTimestamp"))?;
+            let t = NaiveDateTime::parse_from_str(update_time, "%b %d, %Y %I:%M %p")?
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string();
             Ok(StockPriceDTO {
                 h: high,
                 l: low,
@@ -935,48 +1746,45 @@ fn parse_dayrange(key_stats: &Value) -> (String, String) {
     (String::new(), String::new())
 }
 
-fn cal_value(val: &str, unit: &str) -> BigDecimal {
+fn cal_value(val: &str, unit: &str) -> Option<BigDecimal> {
     if val.is_empty() {
-        return BigDecimal::from(0);
+        return Some(BigDecimal::from(0));
     }
-    let val = BigDecimal::from_str(val).unwrap();
+    let val = BigDecimal::from_str(val).ok()?;
     let unit = match unit {
         "B" => BigDecimal::from(1000000000),
         "M" => BigDecimal::from(1000000),
         "K" => BigDecimal::from(1000),
         _ => BigDecimal::from(1),
     };
-    val * unit
+    Some(val * unit)
 }
 
-pub fn remove_jquery_wrapping_fn_call(data: &str) -> Value {
-    // Remove the wrapping function call
-    if let (Some(start_idx), Some(end_idx)) = (data.find('('), data.rfind(')')) {
-        if end_idx > start_idx {
-            let json_str = &data[start_idx + 1..end_idx];
-            serde_json::from_str::<Value>(json_str)
-                .unwrap_or_else(|_| serde_json::from_str::<Value>(data).unwrap())
-        } else {
-            serde_json::from_str::<Value>(data).unwrap()
-        }
-    } else {
-        serde_json::from_str::<Value>(data).unwrap()
-    }
+/// 去掉 JSONP 的回调函数包装并解析为 JSON，没有包装时直接解析
+pub fn remove_jquery_wrapping_fn_call(data: &str, upstream: &str) -> Result<Value, AppError> {
+    let json_str = match (data.find('('), data.rfind(')')) {
+        (Some(start_idx), Some(end_idx)) if end_idx > start_idx => &data[start_idx + 1..end_idx],
+        _ => data,
+    };
+    serde_json::from_str::<Value>(json_str)
+        .or_else(|_| serde_json::from_str::<Value>(data))
+        .map_err(|_| AppError::parse(upstream, "body"))
 }
 
-async fn get_stock_daily_price_from_akshare(
+/// 获取美股不复权日线，复权由公司行动在本地计算
+pub(crate) async fn get_stock_daily_price_from_akshare(
     _exchange: &Exchange,
     stock: &stock_model::Model,
 ) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
     let base_url = get_akshare_base_url().await?;
     let url = format!(
-        "{}/api/public/stock_us_daily?symbol={}&adjust=qfq",
+        "{}/api/public/stock_us_daily?symbol={}&adjust=",
         base_url, stock.stock_code
     );
     parse_akshare_kline(&url).await
 }
 
-async fn get_current_price_from_yahoo(
+pub(crate) async fn get_current_price_from_yahoo(
     yahoo_symbol: &str,
     exchange: &Exchange,
 ) -> Result<StockPriceDTO, Box<dyn Error>> {
@@ -985,34 +1793,27 @@ async fn get_current_price_from_yahoo(
         yahoo_symbol
     );
 
-    let provider = yahoo::YahooConnector::new()?;
-
     let market_status = exchange_svc::get_exchange_market_status(exchange.as_ref()).await?;
 
     let (open, high, low, price, volume, t) = if market_status == "MarketClosed" {
-        let daily_resp = provider.get_quote_range(yahoo_symbol, "1d", "1mo").await?;
-        let daily_quotes: Vec<yahoo_finance_api::Quote> = daily_resp.quotes()?;
-        let last = daily_quotes.last().ok_or("No daily quotes available")?;
-        let t = DateTime::from_timestamp(last.timestamp as i64, 0)
-            .unwrap_or_default()
-            .with_timezone(&exchange.time_zone())
-            .format("%Y-%m-%d %H:%M:%S")
-            .to_string();
+        let chart = get_yahoo_chart(yahoo_symbol, "1d", "1mo").await?;
+        let last = chart
+            .quotes
+            .last()
+            .ok_or_else(|| AppError::parse("yahoo", "quote"))?;
+        let t = format_yahoo_timestamp(last.timestamp, exchange, "%Y-%m-%d %H:%M:%S");
         (last.open, last.high, last.low, last.close, last.volume, t)
     } else {
-        let intraday = provider.get_quote_range(yahoo_symbol, "1m", "1d").await?;
-        let quotes = intraday.quotes()?;
+        let quotes = get_yahoo_chart(yahoo_symbol, "1m", "1d").await?.quotes;
+        let last = quotes
+            .last()
+            .ok_or_else(|| AppError::parse("yahoo", "quote"))?;
         let open = quotes.first().map(|q| q.open).unwrap_or(0.0);
         let high = quotes.iter().map(|q| q.high).fold(f64::MIN, f64::max);
         let low = quotes.iter().map(|q| q.low).fold(f64::MAX, f64::min);
-        let last = quotes.last().unwrap();
         let price = last.close;
         let volume: u64 = quotes.iter().map(|q| q.volume).sum();
-        let t = DateTime::from_timestamp(last.timestamp as i64, 0)
-            .unwrap_or_default()
-            .with_timezone(&exchange.time_zone())
-            .format("%Y-%m-%d %H:%M:%S")
-            .to_string();
+        let t = format_yahoo_timestamp(last.timestamp, exchange, "%Y-%m-%d %H:%M:%S");
         (open, high, low, price, volume, t)
     };
 
@@ -1029,3 +1830,303 @@ async fn get_current_price_from_yahoo(
         t,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+
+    fn json(text: &str) -> Value {
+        serde_json::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn test_parse_akshare_daily_prices() {
+        let data = json(include_str!(
+            "../../tests/fixtures/akshare/stock_zh_a_daily.json"
+        ));
+        let prices = parse_akshare_daily_prices(&data).unwrap();
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices[0].time, 20240102093000);
+        assert_eq!(prices[0].open, decimal("6.62"));
+        assert_eq!(prices[0].high, decimal("6.68"));
+        assert_eq!(prices[0].low, decimal("6.58"));
+        assert_eq!(prices[0].close, decimal("6.63"));
+        assert_eq!(prices[1].volume, Some(decimal("21087754")));
+        assert_eq!(prices[1].amount, None);
+    }
+
+    #[test]
+    fn test_parse_yahoo_chart_daily() {
+        let data = json(include_str!("../../tests/fixtures/yahoo/chart_daily.json"));
+        let chart = parse_yahoo_chart(&data).unwrap();
+        // 最后一根 K 线价格为 null，跳过
+        assert_eq!(chart.quotes.len(), 4);
+        assert_eq!(chart.quotes[0].timestamp, 1598535000);
+        assert_eq!(chart.quotes[2].close, 129.039993);
+        assert_eq!(chart.quotes[2].volume, 225702700);
+        assert_eq!(
+            chart.dividends,
+            vec![YahooDividend {
+                date: 1596807000,
+                amount: 0.205
+            }]
+        );
+        assert_eq!(
+            chart.splits,
+            vec![YahooSplit {
+                date: 1598880600,
+                numerator: 4.0,
+                denominator: 1.0
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unadjust_yahoo_splits() {
+        let data = json(include_str!("../../tests/fixtures/yahoo/chart_daily.json"));
+        let chart = parse_yahoo_chart(&data).unwrap();
+        let quotes = unadjust_yahoo_splits(chart.quotes.clone(), &chart.splits);
+        // 2020-08-31 起 4 拆 1，之前的价格乘以 4、成交量除以 4
+        assert_eq!(format!("{:.2}", quotes[1].close), "499.23");
+        assert_eq!(format!("{:.2}", quotes[0].high), "509.94");
+        assert_eq!(quotes[1].volume, 46907500);
+        assert_eq!(quotes[2], chart.quotes[2]);
+        assert_eq!(quotes[3], chart.quotes[3]);
+    }
+
+    #[test]
+    fn test_parse_yahoo_chart_intraday() {
+        let data = json(include_str!(
+            "../../tests/fixtures/yahoo/chart_intraday.json"
+        ));
+        let chart = parse_yahoo_chart(&data).unwrap();
+        assert_eq!(chart.quotes.len(), 2);
+        assert_eq!(chart.quotes[1].open, 186.520004);
+        assert!(chart.dividends.is_empty());
+        assert!(chart.splits.is_empty());
+        let time =
+            format_yahoo_timestamp(chart.quotes[0].timestamp, &Exchange::NASDAQ, "%Y%m%d%H%M%S");
+        assert_eq!(time, "20240102093000");
+    }
+
+    #[test]
+    fn test_parse_akshare_bid_ask() {
+        let data = json(include_str!(
+            "../../tests/fixtures/akshare/stock_bid_ask_em.json"
+        ));
+        let price = parse_akshare_bid_ask(&data, "2024-01-05 10:15:00".to_string()).unwrap();
+        assert_eq!(price.p, "15.81");
+        assert_eq!(price.o, "15.5");
+        assert_eq!(price.h, "15.99");
+        assert_eq!(price.l, "15.42");
+        assert_eq!(price.yc, "15.45");
+        assert_eq!(price.pc, "2.33");
+        assert_eq!(price.ud, "0.36");
+        assert_eq!(price.v, "2561200");
+        assert_eq!(price.cje, "40312345");
+        assert_eq!(price.t, "2024-01-05 10:15:00");
+    }
+
+    #[test]
+    fn test_parse_akshare_bid_ask_missing_field() {
+        let data = json(r#"[{"item":"最新","value":15.81}]"#);
+        let err = parse_akshare_bid_ask(&data, String::new()).unwrap_err();
+        assert_eq!(err, AppError::parse("akshare", "最高"));
+    }
+
+    #[test]
+    fn test_parse_sse_snap() {
+        let data = json(include_str!("../../tests/fixtures/sse/snap.json"));
+        let price = parse_sse_snap(&data).unwrap();
+        assert_eq!(price.t, "2024-01-05 09:35:12");
+        assert_eq!(price.yc, "6.65");
+        assert_eq!(price.o, "6.64");
+        assert_eq!(price.h, "6.71");
+        assert_eq!(price.l, "6.62");
+        assert_eq!(price.p, "6.69");
+        assert_eq!(price.pc, "0.6");
+        assert_eq!(price.ud, "0.04");
+        assert_eq!(price.v, "12987654");
+        assert_eq!(price.cje, "86543210.5");
+    }
+
+    #[test]
+    fn test_parse_sse_snap_missing_field() {
+        let data = json(r#"{"code":"600000","date":20240105,"time":93512}"#);
+        let err = parse_sse_snap(&data).unwrap_err();
+        assert_eq!(err, AppError::parse("sse", "snap"));
+    }
+
+    #[test]
+    fn test_parse_sse_daily_prices() {
+        let data = json(include_str!("../../tests/fixtures/sse/dayk.json"));
+        let prices = parse_sse_daily_prices(&data).unwrap();
+        assert_eq!(prices.len(), 3);
+        assert_eq!(prices[0].time, 20240103093000);
+        assert_eq!(prices[0].open, decimal("6.63"));
+        assert_eq!(prices[0].high, decimal("6.66"));
+        assert_eq!(prices[0].low, decimal("6.6"));
+        assert_eq!(prices[0].close, decimal("6.65"));
+        assert_eq!(prices[0].volume, Some(decimal("21087754")));
+        assert_eq!(prices[0].amount, Some(decimal("139963140")));
+        assert_eq!(prices[2].time, 20240105093000);
+    }
+
+    #[test]
+    fn test_sse_dayk_url() {
+        let base_url = "https://yunhq.sse.com.cn:32042";
+        assert_eq!(
+            sse_dayk_url(base_url, "600000", 0, 1704441600000),
+            "https://yunhq.sse.com.cn:32042/v1/sh1/dayk/600000?begin=-1000&end=-1&period=day&_=1704441600000"
+        );
+        assert_eq!(
+            sse_dayk_url(base_url, "600000", 2, 1704441600000),
+            "https://yunhq.sse.com.cn:32042/v1/sh1/dayk/600000?begin=-3000&end=-2001&period=day&_=1704441600000"
+        );
+    }
+
+    #[test]
+    fn test_parse_szse_time_data() {
+        let data = json(include_str!("../../tests/fixtures/szse/time_data.json"));
+        let price = parse_szse_time_data(&data).unwrap();
+        assert_eq!(price.t, "2024-01-05 15:00:00");
+        assert_eq!(price.o, "9.36");
+        assert_eq!(price.h, "9.45");
+        assert_eq!(price.l, "9.31");
+        assert_eq!(price.p, "9.41");
+        assert_eq!(price.pc, "0.53");
+        assert_eq!(price.ud, "0.05");
+        assert_eq!(price.v, "108965400");
+        assert_eq!(price.cje, "1021987654.32");
+    }
+
+    #[test]
+    fn test_parse_sse_intraday() {
+        let data = json(include_str!("../../tests/fixtures/sse/line.json"));
+        let prices = parse_sse_intraday(&data).unwrap();
+        assert_eq!(prices.len(), 3);
+        assert_eq!(prices[0].time, 20240105093000);
+        assert_eq!(prices[0].open, decimal("6.61"));
+        assert_eq!(prices[0].close, decimal("6.61"));
+        assert_eq!(prices[0].volume, Some(decimal("1234500")));
+        assert_eq!(prices[0].amount, Some(decimal("8160045")));
+        assert_eq!(prices[2].time, 20240105093200);
+        assert_eq!(prices[2].high, decimal("6.64"));
+    }
+
+    #[test]
+    fn test_parse_sse_intraday_missing_field() {
+        let data = json(r#"{"date":20240105,"line":[[93000]]}"#);
+        let err = parse_sse_intraday(&data).unwrap_err();
+        assert_eq!(err, AppError::parse("sse", "line[1]"));
+    }
+
+    #[test]
+    fn test_parse_szse_intraday() {
+        let data = json(include_str!("../../tests/fixtures/szse/time_data.json"));
+        let prices = parse_szse_intraday(&data).unwrap();
+        assert_eq!(prices.len(), 3);
+        assert_eq!(prices[0].time, 20240105093100);
+        assert_eq!(prices[0].open, decimal("9.38"));
+        assert_eq!(prices[0].low, decimal("9.38"));
+        // 成交量由手换算为股
+        assert_eq!(prices[0].volume, Some(decimal("1234500")));
+        assert_eq!(prices[0].amount, Some(decimal("11579610")));
+        assert_eq!(prices[2].time, 20240105093300);
+    }
+
+    #[test]
+    fn test_parse_szse_daily_prices() {
+        let data = json(include_str!("../../tests/fixtures/szse/history_data.json"));
+        let prices = parse_szse_daily_prices(&data).unwrap();
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices[0].time, 20240103093000);
+        assert_eq!(prices[0].open, decimal("9.30"));
+        assert_eq!(prices[0].close, decimal("9.33"));
+        assert_eq!(prices[0].low, decimal("9.27"));
+        assert_eq!(prices[0].high, decimal("9.38"));
+        assert_eq!(prices[0].volume, Some(decimal("1123456")));
+        assert_eq!(prices[0].amount, Some(decimal("1047654321.18")));
+    }
+
+    #[test]
+    fn test_parse_hkex_daily_prices() {
+        let text = include_str!("../../tests/fixtures/hkex/chart_data.jsonp");
+        let data = remove_jquery_wrapping_fn_call(text, "hkex").unwrap();
+        let prices = parse_hkex_daily_prices(&data, &Exchange::HKEX).unwrap();
+        // 停牌（开盘价为空或为负数）的 K 线被跳过
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices[0].time, 20240102093000);
+        assert_eq!(prices[0].open, decimal("297.4"));
+        assert_eq!(prices[0].high, decimal("300"));
+        assert_eq!(prices[0].low, decimal("292.6"));
+        assert_eq!(prices[0].close, decimal("294.2"));
+        assert_eq!(prices[0].volume, Some(decimal("21584421")));
+        assert_eq!(prices[0].amount, Some(decimal("6380123456")));
+        assert_eq!(prices[1].time, 20240105093000);
+    }
+
+    #[test]
+    fn test_parse_hkex_intraday() {
+        let text = include_str!("../../tests/fixtures/hkex/chart_intraday.jsonp");
+        let data = remove_jquery_wrapping_fn_call(text, "hkex").unwrap();
+        let prices = parse_hkex_intraday(&data, &Exchange::HKEX).unwrap();
+        // 尚未成交（开盘价为空）的分钟被跳过
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices[0].time, 20240105093000);
+        assert_eq!(prices[0].open, decimal("288.0"));
+        assert_eq!(prices[0].high, decimal("288.6"));
+        assert_eq!(prices[0].low, decimal("287.8"));
+        assert_eq!(prices[0].close, decimal("288.4"));
+        assert_eq!(prices[0].volume, Some(decimal("812300")));
+        assert_eq!(prices[0].amount, Some(decimal("234212345")));
+        assert_eq!(prices[1].time, 20240105093100);
+    }
+
+    #[test]
+    fn test_parse_hkex_equity_quote() {
+        let text = include_str!("../../tests/fixtures/hkex/equity_quote.jsonp");
+        let data = remove_jquery_wrapping_fn_call(text, "hkex").unwrap();
+        let price = parse_hkex_equity_quote(&data).unwrap();
+        assert_eq!(price.t, "2024-01-05 16:08:00");
+        assert_eq!(price.o, "288.000");
+        assert_eq!(price.h, "291.800");
+        assert_eq!(price.l, "286.200");
+        assert_eq!(price.p, "290.400");
+        assert_eq!(price.yc, "294.200");
+        assert_eq!(price.pc, "-1.29");
+        assert_eq!(price.ud, "-3.800");
+        assert_eq!(decimal(&price.v), decimal("18650000"));
+        assert_eq!(decimal(&price.cje), decimal("5400000000"));
+    }
+
+    #[test]
+    fn test_remove_jquery_wrapping_fn_call() {
+        let data = remove_jquery_wrapping_fn_call(r#"jQuery_1({"data":{}})"#, "hkex").unwrap();
+        assert!(data.get("data").is_some());
+        let data = remove_jquery_wrapping_fn_call(r#"{"data":{}}"#, "hkex").unwrap();
+        assert!(data.get("data").is_some());
+        let err = remove_jquery_wrapping_fn_call("<html>token expired</html>", "hkex").unwrap_err();
+        assert_eq!(err, AppError::parse("hkex", "body"));
+    }
+
+    #[test]
+    fn test_parse_nasdaq_daily_prices() {
+        let data = json(include_str!(
+            "../../tests/fixtures/nasdaq/charting_historical.json"
+        ));
+        let prices = parse_nasdaq_daily_prices(&data).unwrap();
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices[0].time, 20240102093000);
+        assert_eq!(prices[0].open, decimal("187.15"));
+        assert_eq!(prices[0].high, decimal("188.44"));
+        assert_eq!(prices[0].low, decimal("183.885"));
+        assert_eq!(prices[0].close, decimal("185.64"));
+        assert_eq!(prices[0].volume, Some(decimal("82488700")));
+        assert_eq!(prices[1].time, 20240103093000);
+    }
+}
@@ -0,0 +1,538 @@
+use crate::error::AppError;
+use crate::exchange::exchange_model::Exchange;
+use crate::stock::stock_model::Model as Stock;
+use crate::stock::stock_price_api;
+use crate::stock::stock_price_api::{StockDailyPrice, StockMinutePrice, StockPriceDTO};
+use application_context::context::application_context::APPLICATION_CONTEXT;
+use application_core::env::property_resolver::PropertyResolver;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use tracing::{error, info};
+
+/// 行情数据源
+///
+/// 每个数据源声明自己支持的股票，不支持的操作返回 `AppError::UnsupportedExchange`，
+/// 由数据源链继续尝试下一个数据源。
+#[async_trait]
+pub trait PriceProvider: Send + Sync {
+    /// 数据源名称，与 `config.toml` 中的配置一致，同时作为日线入库时的来源
+    fn name(&self) -> &'static str;
+
+    /// 是否支持该股票
+    fn supports(&self, stock: &Stock) -> bool;
+
+    /// 获取不复权的日线，`since` 为空时获取全部历史，否则至少包含 `since` 之后的日线
+    async fn get_daily_prices(
+        &self,
+        stock: &Stock,
+        _since: Option<NaiveDate>,
+    ) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
+        Err(AppError::UnsupportedExchange(format!(
+            "{} does not provide daily prices of {}",
+            self.name(),
+            stock.code
+        ))
+        .into())
+    }
+
+    /// 获取全部历史日线时是否能返回上市以来的全部日线
+    fn full_history(&self) -> bool {
+        true
+    }
+
+    /// 获取当前价格
+    async fn get_latest_price(&self, stock: &Stock) -> Result<StockPriceDTO, Box<dyn Error>> {
+        Err(AppError::UnsupportedExchange(format!(
+            "{} does not provide latest price of {}",
+            self.name(),
+            stock.code
+        ))
+        .into())
+    }
+
+    /// 获取当日 1 分钟线
+    async fn get_intraday_prices(
+        &self,
+        stock: &Stock,
+    ) -> Result<Vec<StockMinutePrice>, Box<dyn Error>> {
+        Err(AppError::UnsupportedExchange(format!(
+            "{} does not provide intraday prices of {}",
+            self.name(),
+            stock.code
+        ))
+        .into())
+    }
+}
+
+fn is_exchange(stock: &Stock, exchange: Exchange) -> bool {
+    stock.exchange == exchange.as_ref()
+}
+
+/// 是否为纳斯达克、纽交所或美交所的股票
+fn is_us_exchange(stock: &Stock) -> bool {
+    Exchange::from_str(&stock.exchange).is_ok_and(|exchange| exchange.is_us())
+}
+
+/// 美股代码中带有类别后缀的股票，如 `BRK.B`，nasdaq.com 接口无数据
+fn is_class_share(stock: &Stock) -> bool {
+    regex::Regex::new(r"^[A-Z]+\.[A-Z]+$").is_ok_and(|re| re.is_match(&stock.stock_code))
+}
+
+/// akshare 美股指数代码
+fn to_akshare_us_index_symbol(stock: &Stock) -> Option<&'static str> {
+    match stock.code.as_str() {
+        "SPX.NS" => Some(".INX"),
+        "NDX.NS" => Some(".NDX"),
+        "IXIC.NS" => Some(".IXIC"),
+        _ => None,
+    }
+}
+
+/// 上交所行情接口
+pub struct SseProvider;
+
+#[async_trait]
+impl PriceProvider for SseProvider {
+    fn name(&self) -> &'static str {
+        "sse"
+    }
+
+    fn supports(&self, stock: &Stock) -> bool {
+        is_exchange(stock, Exchange::SSE)
+    }
+
+    async fn get_daily_prices(
+        &self,
+        stock: &Stock,
+        since: Option<NaiveDate>,
+    ) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
+        stock_price_api::get_stock_daily_price_from_sse(stock, since).await
+    }
+
+    async fn get_latest_price(&self, stock: &Stock) -> Result<StockPriceDTO, Box<dyn Error>> {
+        stock_price_api::get_current_price_from_sse(&stock.stock_code).await
+    }
+
+    async fn get_intraday_prices(
+        &self,
+        stock: &Stock,
+    ) -> Result<Vec<StockMinutePrice>, Box<dyn Error>> {
+        stock_price_api::get_stock_intraday_price_from_sse(stock).await
+    }
+}
+
+/// 深交所行情接口
+pub struct SzseProvider;
+
+#[async_trait]
+impl PriceProvider for SzseProvider {
+    fn name(&self) -> &'static str {
+        "szse"
+    }
+
+    fn supports(&self, stock: &Stock) -> bool {
+        is_exchange(stock, Exchange::SZSE)
+    }
+
+    async fn get_daily_prices(
+        &self,
+        stock: &Stock,
+        _since: Option<NaiveDate>,
+    ) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
+        stock_price_api::get_stock_daily_price_from_szse(stock).await
+    }
+
+    async fn get_latest_price(&self, stock: &Stock) -> Result<StockPriceDTO, Box<dyn Error>> {
+        stock_price_api::get_current_price_from_szse(&stock.stock_code).await
+    }
+
+    async fn get_intraday_prices(
+        &self,
+        stock: &Stock,
+    ) -> Result<Vec<StockMinutePrice>, Box<dyn Error>> {
+        stock_price_api::get_stock_intraday_price_from_szse(stock).await
+    }
+}
+
+/// 港交所行情接口
+pub struct HkexProvider;
+
+#[async_trait]
+impl PriceProvider for HkexProvider {
+    fn name(&self) -> &'static str {
+        "hkex"
+    }
+
+    fn supports(&self, stock: &Stock) -> bool {
+        is_exchange(stock, Exchange::HKEX)
+    }
+
+    async fn get_daily_prices(
+        &self,
+        stock: &Stock,
+        _since: Option<NaiveDate>,
+    ) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
+        stock_price_api::get_stock_daily_price_from_hkex(stock).await
+    }
+
+    fn full_history(&self) -> bool {
+        false
+    }
+
+    async fn get_latest_price(&self, stock: &Stock) -> Result<StockPriceDTO, Box<dyn Error>> {
+        if stock.stock_type == "Index" {
+            stock_price_api::get_current_index_price_from_hk(&Exchange::HKEX, &stock.stock_code)
+                .await
+        } else {
+            stock_price_api::get_current_stock_price_from_hk(&Exchange::HKEX, &stock.stock_code)
+                .await
+        }
+    }
+
+    async fn get_intraday_prices(
+        &self,
+        stock: &Stock,
+    ) -> Result<Vec<StockMinutePrice>, Box<dyn Error>> {
+        stock_price_api::get_stock_intraday_price_from_hkex(&Exchange::HKEX, stock).await
+    }
+}
+
+/// nasdaq.com 行情接口，支持纳斯达克、纽交所和美交所的股票，只提供当日汇总数据，没有分钟线
+pub struct NasdaqProvider;
+
+#[async_trait]
+impl PriceProvider for NasdaqProvider {
+    fn name(&self) -> &'static str {
+        "nasdaq"
+    }
+
+    fn supports(&self, stock: &Stock) -> bool {
+        // SPX 指数和带类别后缀的股票在 nasdaq.com 接口中没有数据
+        is_us_exchange(stock) && stock.code != "SPX.NS" && !is_class_share(stock)
+    }
+
+    async fn get_daily_prices(
+        &self,
+        stock: &Stock,
+        _since: Option<NaiveDate>,
+    ) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
+        let exchange = Exchange::from_str(&stock.exchange)?;
+        stock_price_api::get_stock_daily_price_from_nasdaq(&exchange, stock).await
+    }
+
+    async fn get_latest_price(&self, stock: &Stock) -> Result<StockPriceDTO, Box<dyn Error>> {
+        let exchange = Exchange::from_str(&stock.exchange)?;
+        stock_price_api::get_current_price_from_nasdaq(&exchange, stock).await
+    }
+}
+
+/// akshare 接口，提供 A 股个股、美股个股和美股指数的不复权日线，以及北交所股票的当前价格
+pub struct AkshareProvider;
+
+#[async_trait]
+impl PriceProvider for AkshareProvider {
+    fn name(&self) -> &'static str {
+        "akshare"
+    }
+
+    fn supports(&self, stock: &Stock) -> bool {
+        match Exchange::from_str(&stock.exchange) {
+            Ok(Exchange::SSE) | Ok(Exchange::SZSE) | Ok(Exchange::BSE) => {
+                stock.stock_type == "Stock"
+            }
+            Ok(exchange) if exchange.is_us() => {
+                stock.stock_type == "Stock" || to_akshare_us_index_symbol(stock).is_some()
+            }
+            _ => false,
+        }
+    }
+
+    async fn get_daily_prices(
+        &self,
+        stock: &Stock,
+        _since: Option<NaiveDate>,
+    ) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
+        let exchange = Exchange::from_str(&stock.exchange)?;
+        match exchange {
+            Exchange::SSE | Exchange::SZSE | Exchange::BSE => {
+                stock_price_api::get_stock_daily_price_from_akshare_zh_a(&exchange, stock).await
+            }
+            _ => match to_akshare_us_index_symbol(stock) {
+                Some(symbol) => {
+                    stock_price_api::get_index_stock_daily_price_from_akshare(&exchange, symbol)
+                        .await
+                }
+                None => stock_price_api::get_stock_daily_price_from_akshare(&exchange, stock).await,
+            },
+        }
+    }
+
+    async fn get_latest_price(&self, stock: &Stock) -> Result<StockPriceDTO, Box<dyn Error>> {
+        // 沪深两市和美股使用交易所自己的行情接口，只有北交所从 akshare 获取当前价格
+        if !is_exchange(stock, Exchange::BSE) {
+            return Err(format!("akshare does not provide latest price of {}", stock.code).into());
+        }
+        stock_price_api::get_current_price_from_akshare(&Exchange::BSE, &stock.stock_code).await
+    }
+}
+
+/// Yahoo Finance 接口，支持港股和美股
+pub struct YahooProvider;
+
+#[async_trait]
+impl PriceProvider for YahooProvider {
+    fn name(&self) -> &'static str {
+        "yahoo"
+    }
+
+    fn supports(&self, stock: &Stock) -> bool {
+        is_exchange(stock, Exchange::HKEX) || is_us_exchange(stock)
+    }
+
+    async fn get_daily_prices(
+        &self,
+        stock: &Stock,
+        _since: Option<NaiveDate>,
+    ) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
+        let exchange = Exchange::from_str(&stock.exchange)?;
+        let symbol = stock_price_api::to_yahoo_symbol(stock);
+        stock_price_api::get_stock_daily_price_from_yahoo(&symbol, &exchange).await
+    }
+
+    async fn get_latest_price(&self, stock: &Stock) -> Result<StockPriceDTO, Box<dyn Error>> {
+        let exchange = Exchange::from_str(&stock.exchange)?;
+        let symbol = stock_price_api::to_yahoo_symbol(stock);
+        stock_price_api::get_current_price_from_yahoo(&symbol, &exchange).await
+    }
+
+    async fn get_intraday_prices(
+        &self,
+        stock: &Stock,
+    ) -> Result<Vec<StockMinutePrice>, Box<dyn Error>> {
+        let exchange = Exchange::from_str(&stock.exchange)?;
+        let symbol = stock_price_api::to_yahoo_symbol(stock);
+        stock_price_api::get_stock_intraday_price_from_yahoo(&symbol, &exchange).await
+    }
+}
+
+/// 按名称获取数据源
+pub fn get_provider(name: &str) -> Option<&'static dyn PriceProvider> {
+    let provider: &'static dyn PriceProvider = match name.trim().to_lowercase().as_str() {
+        "sse" => &SseProvider,
+        "szse" => &SzseProvider,
+        "hkex" => &HkexProvider,
+        "nasdaq" => &NasdaqProvider,
+        "akshare" => &AkshareProvider,
+        "yahoo" => &YahooProvider,
+        _ => return None,
+    };
+    Some(provider)
+}
+
+/// 未配置数据源链时使用的默认顺序
+fn default_provider_names(exchange: &Exchange, stock_type: &str) -> Vec<&'static str> {
+    match (exchange, stock_type) {
+        (Exchange::SSE, "Stock") => vec!["akshare", "sse"],
+        (Exchange::SSE, _) => vec!["sse"],
+        (Exchange::SZSE, "Stock") => vec!["akshare", "szse"],
+        (Exchange::SZSE, _) => vec!["szse"],
+        (Exchange::HKEX, _) => vec!["hkex", "yahoo"],
+        (Exchange::NASDAQ | Exchange::NYSE | Exchange::AMEX, "Index") => {
+            vec!["akshare", "nasdaq", "yahoo"]
+        }
+        (Exchange::NASDAQ | Exchange::NYSE | Exchange::AMEX, _) => {
+            vec!["nasdaq", "akshare", "yahoo"]
+        }
+        (Exchange::BSE, _) => vec!["akshare"],
+    }
+}
+
+/// 获取股票的数据源链
+///
+/// 从配置项 `stock.price.providers.<交易所>.<证券类型>` 读取数据源名称列表，
+/// 未配置时使用默认顺序，无法识别的名称会被忽略。
+pub async fn get_providers(
+    stock: &Stock,
+) -> Result<Vec<&'static dyn PriceProvider>, Box<dyn Error>> {
+    let exchange = Exchange::from_str(&stock.exchange)?;
+    let key = format!(
+        "stock.price.providers.{}.{}",
+        exchange.as_ref(),
+        stock.stock_type
+    );
+    let names = {
+        let application_context = APPLICATION_CONTEXT.read().await;
+        let environment = application_context.get_environment().await;
+        environment.get_property::<Vec<String>>(&key)
+    };
+    let names = names.unwrap_or_else(|| {
+        default_provider_names(&exchange, &stock.stock_type)
+            .into_iter()
+            .map(String::from)
+            .collect()
+    });
+
+    let mut providers = Vec::new();
+    for name in names {
+        match get_provider(&name) {
+            Some(provider) => providers.push(provider),
+            None => info!("Unknown price provider {} in {}", name, key),
+        }
+    }
+    Ok(providers)
+}
+
+type ProviderFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, Box<dyn Error>>> + Send + 'a>>;
+
+/// 按数据源链依次获取数据，数据源出错或返回空数据时尝试下一个数据源
+///
+/// 成功时返回数据和实际使用的数据源名称，全部失败或没有可用的数据源时返回 `AppError::Upstream`，
+/// 其中包含各数据源的失败原因。
+async fn fetch_with_fallback<T, F, E>(
+    stock: &Stock,
+    operation: &str,
+    fetch: F,
+    is_empty: E,
+) -> Result<(&'static str, T), Box<dyn Error>>
+where
+    F: for<'a> Fn(&'a dyn PriceProvider, &'a Stock) -> ProviderFuture<'a, T>,
+    E: Fn(&T) -> bool,
+{
+    let providers = get_providers(stock).await?;
+    let mut names = Vec::new();
+    let mut errors = Vec::new();
+    for provider in providers.into_iter().filter(|p| p.supports(stock)) {
+        info!(
+            "Get {} of {} from {}",
+            operation,
+            stock.code,
+            provider.name()
+        );
+        names.push(provider.name());
+        match fetch(provider, stock).await {
+            Ok(data) if !is_empty(&data) => return Ok((provider.name(), data)),
+            Ok(_) => errors.push(format!("{}: empty result", provider.name())),
+            Err(e) => errors.push(format!("{}: {}", provider.name(), e)),
+        }
+        info!(
+            "Get {} of {} from {} failed, try next provider",
+            operation,
+            stock.code,
+            provider.name()
+        );
+    }
+    if names.is_empty() {
+        return Err(AppError::Upstream {
+            upstream: stock.exchange.clone(),
+            message: format!("No price provider supports {} of {}", operation, stock.code),
+        }
+        .into());
+    }
+    Err(AppError::Upstream {
+        upstream: names.join(","),
+        message: format!(
+            "Get {} of {} failed: {}",
+            operation,
+            stock.code,
+            errors.join("; ")
+        ),
+    }
+    .into())
+}
+
+/// 按数据源链获取不复权的日线，返回实际使用的数据源名称和日线，`since` 为空时获取全部历史
+///
+/// 获取全部历史时，若使用的数据源只能返回最近数年的日线，再由数据源链中后续能返回全部历史的
+/// 数据源补齐更早的日线，补齐失败时只记录日志。每个数据源的日线分别返回，以便记录来源。
+pub async fn get_daily_prices(
+    stock: &Stock,
+    since: Option<NaiveDate>,
+) -> Result<Vec<(&'static str, Vec<StockDailyPrice>)>, Box<dyn Error>> {
+    let (source, prices) = fetch_with_fallback(
+        stock,
+        "daily prices",
+        |provider, stock| provider.get_daily_prices(stock, since),
+        |prices| prices.is_empty(),
+    )
+    .await?;
+    let earliest = prices.iter().map(|price| price.time).min();
+    let mut batches = vec![(source, prices)];
+    let limited = get_provider(source).is_some_and(|provider| !provider.full_history());
+    if let (None, Some(earliest), true) = (since, earliest, limited) {
+        match get_earlier_daily_prices(stock, source, earliest).await {
+            Ok(Some(batch)) => batches.insert(0, batch),
+            Ok(None) => info!("No daily prices of {} before {}", stock.code, earliest),
+            Err(e) => error!(
+                "Get daily prices of {} before {} error {}",
+                stock.code, earliest, e
+            ),
+        }
+    }
+    Ok(batches)
+}
+
+/// 从数据源链中排在 `source` 之后、能返回全部历史的数据源获取早于 `before` 的日线
+async fn get_earlier_daily_prices(
+    stock: &Stock,
+    source: &str,
+    before: u64,
+) -> Result<Option<(&'static str, Vec<StockDailyPrice>)>, Box<dyn Error>> {
+    let providers = get_providers(stock).await?;
+    let providers = providers
+        .into_iter()
+        .skip_while(|provider| provider.name() != source)
+        .skip(1)
+        .filter(|provider| provider.supports(stock) && provider.full_history());
+    for provider in providers {
+        info!(
+            "Get daily prices of {} before {} from {}",
+            stock.code,
+            before,
+            provider.name()
+        );
+        match provider.get_daily_prices(stock, None).await {
+            Ok(prices) => {
+                let prices = prices
+                    .into_iter()
+                    .filter(|price| price.time < before)
+                    .collect::<Vec<_>>();
+                return Ok((!prices.is_empty()).then_some((provider.name(), prices)));
+            }
+            Err(e) => error!(
+                "Get daily prices of {} from {} error {}",
+                stock.code,
+                provider.name(),
+                e
+            ),
+        }
+    }
+    Ok(None)
+}
+
+/// 按数据源链获取当前价格
+pub async fn get_latest_price(stock: &Stock) -> Result<StockPriceDTO, Box<dyn Error>> {
+    let (_, price) = fetch_with_fallback(
+        stock,
+        "latest price",
+        |provider, stock| provider.get_latest_price(stock),
+        |price: &StockPriceDTO| price.p.is_empty(),
+    )
+    .await?;
+    Ok(price)
+}
+
+/// 按数据源链获取当日 1 分钟线
+pub async fn get_intraday_prices(stock: &Stock) -> Result<Vec<StockMinutePrice>, Box<dyn Error>> {
+    let (_, prices) = fetch_with_fallback(
+        stock,
+        "intraday prices",
+        |provider, stock| provider.get_intraday_prices(stock),
+        |prices: &Vec<StockMinutePrice>| prices.is_empty(),
+    )
+    .await?;
+    Ok(prices)
+}
@@ -0,0 +1,221 @@
+use crate::error::AppError;
+use crate::stock::stock_daily_price_model::time_to_date;
+use crate::stock::stock_price_api::{StockDailyPrice, StockMinutePrice};
+use bigdecimal::BigDecimal;
+use chrono::{Datelike, NaiveDate, NaiveTime, Timelike};
+use std::error::Error;
+use std::str::FromStr;
+
+/// 分钟线周期
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinuteInterval {
+    M1,
+    M5,
+    M15,
+    M60,
+}
+
+impl MinuteInterval {
+    /// 周期包含的分钟数
+    pub fn minutes(&self) -> u64 {
+        match self {
+            MinuteInterval::M1 => 1,
+            MinuteInterval::M5 => 5,
+            MinuteInterval::M15 => 15,
+            MinuteInterval::M60 => 60,
+        }
+    }
+}
+
+impl FromStr for MinuteInterval {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "1m" => Ok(MinuteInterval::M1),
+            "5m" => Ok(MinuteInterval::M5),
+            "15m" => Ok(MinuteInterval::M15),
+            "60m" => Ok(MinuteInterval::M60),
+            _ => Err(AppError::InvalidParam(format!(
+                "Invalid interval: {}, expect 1m, 5m, 15m or 60m",
+                s
+            ))
+            .into()),
+        }
+    }
+}
+
+/// K 线周期
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KlinePeriod {
+    /// 周线
+    Week,
+    /// 月线
+    Month,
+    /// 季线
+    Quarter,
+    /// 年线
+    Year,
+}
+
+impl FromStr for KlinePeriod {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "W" => Ok(KlinePeriod::Week),
+            "M" => Ok(KlinePeriod::Month),
+            "Q" => Ok(KlinePeriod::Quarter),
+            "Y" => Ok(KlinePeriod::Year),
+            _ => Err(
+                AppError::InvalidParam(format!("Invalid period: {}, expect W, M, Q or Y", s))
+                    .into(),
+            ),
+        }
+    }
+}
+
+impl KlinePeriod {
+    /// 日期所属周期的标识，同一周期内的日期标识相同
+    fn key(&self, date: &NaiveDate) -> (i32, u32) {
+        match self {
+            KlinePeriod::Week => {
+                let week = date.iso_week();
+                (week.year(), week.week())
+            }
+            KlinePeriod::Month => (date.year(), date.month()),
+            KlinePeriod::Quarter => (date.year(), (date.month() - 1) / 3 + 1),
+            KlinePeriod::Year => (date.year(), 0),
+        }
+    }
+}
+
+/// 将日线合并为周线、月线、季线或年线
+///
+/// 只合并交易日的日线，落在非交易日的数据会被跳过，因此周期的开盘价取自周期内
+/// 第一个真实交易日。合并后的 K 线时间取周期内最后一个交易日。
+///
+/// # Arguments
+///
+/// * `prices` - 按时间升序排列的日线
+/// * `period` - 目标周期
+/// * `is_trade_date` - 判断日期是否为交易日
+pub fn resample_daily_prices<F>(
+    prices: &[StockDailyPrice],
+    period: KlinePeriod,
+    is_trade_date: F,
+) -> Vec<StockDailyPrice>
+where
+    F: Fn(&NaiveDate) -> bool,
+{
+    let mut bars: Vec<StockDailyPrice> = Vec::new();
+    let mut last_key = None;
+    for price in prices {
+        let date = match time_to_date(price.time) {
+            Ok(date) => date,
+            Err(_) => continue,
+        };
+        if !is_trade_date(&date) {
+            continue;
+        }
+
+        let key = period.key(&date);
+        match bars.last_mut() {
+            Some(bar) if last_key == Some(key) => {
+                if price.high > bar.high {
+                    bar.high = price.high.clone();
+                }
+                if price.low < bar.low {
+                    bar.low = price.low.clone();
+                }
+                bar.close = price.close.clone();
+                bar.volume = add_optional(&bar.volume, &price.volume);
+                bar.amount = add_optional(&bar.amount, &price.amount);
+                bar.time = price.time;
+            }
+            _ => {
+                bars.push(price.clone());
+                last_key = Some(key);
+            }
+        }
+    }
+    bars
+}
+
+/// 将 1 分钟线合并为指定周期的分钟线
+///
+/// 以交易时段的开始时间为锚点切分周期，时段收盘那一分钟并入时段的最后一个周期；
+/// 不在任何交易时段内的数据按自然时间对齐。合并后的分钟线时间取周期的开始时间。
+///
+/// # Arguments
+///
+/// * `prices` - 按时间升序排列的 1 分钟线
+/// * `interval` - 目标周期
+/// * `sessions` - 交易所的交易时段（开始时间，结束时间）
+pub fn resample_minute_prices(
+    prices: &[StockMinutePrice],
+    interval: MinuteInterval,
+    sessions: &[(NaiveTime, NaiveTime)],
+) -> Vec<StockMinutePrice> {
+    if interval == MinuteInterval::M1 {
+        return prices.to_vec();
+    }
+
+    let mut bars: Vec<StockMinutePrice> = Vec::new();
+    for price in prices {
+        let time = bucket_time(price.time, interval.minutes(), sessions);
+        match bars.last_mut() {
+            Some(bar) if bar.time == time => merge_minute_price(bar, price),
+            _ => bars.push(StockMinutePrice {
+                time,
+                ..price.clone()
+            }),
+        }
+    }
+    bars
+}
+
+/// 计算分钟线所属周期的开始时间，时间格式为 yyyyMMddHHmmss
+fn bucket_time(time: u64, interval: u64, sessions: &[(NaiveTime, NaiveTime)]) -> u64 {
+    let date = time / 1_000_000;
+    let hour_minute = (time % 1_000_000) / 100;
+    let minute_of_day = (hour_minute / 100) * 60 + hour_minute % 100;
+
+    let bucket_minute = sessions
+        .iter()
+        .find_map(|(start, end)| {
+            let start = (start.hour() * 60 + start.minute()) as u64;
+            let end = (end.hour() * 60 + end.minute()) as u64;
+            if minute_of_day < start || minute_of_day > end {
+                return None;
+            }
+            let last_index = (end - start).saturating_sub(1) / interval;
+            let index = ((minute_of_day - start) / interval).min(last_index);
+            Some(start + index * interval)
+        })
+        .unwrap_or(minute_of_day / interval * interval);
+
+    date * 1_000_000 + (bucket_minute / 60) * 10_000 + (bucket_minute % 60) * 100
+}
+
+fn merge_minute_price(bar: &mut StockMinutePrice, price: &StockMinutePrice) {
+    if price.high > bar.high {
+        bar.high = price.high.clone();
+    }
+    if price.low < bar.low {
+        bar.low = price.low.clone();
+    }
+    bar.close = price.close.clone();
+    bar.volume = add_optional(&bar.volume, &price.volume);
+    bar.amount = add_optional(&bar.amount, &price.amount);
+}
+
+/// 累加可能为空的数值，两者都为空时结果为空
+pub(crate) fn add_optional(a: &Option<BigDecimal>, b: &Option<BigDecimal>) -> Option<BigDecimal> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        (Some(a), None) => Some(a.clone()),
+        (None, Some(b)) => Some(b.clone()),
+        (None, None) => None,
+    }
+}
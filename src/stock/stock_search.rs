@@ -0,0 +1,302 @@
+use crate::error::AppError;
+use crate::fund::fund_svc;
+use crate::index::index_svc;
+use crate::stock::stock_dao;
+use lazy_static::lazy_static;
+use pinyin::ToPinyin;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::error::Error;
+use std::sync::RwLock;
+use tracing::info;
+
+/// 默认返回的结果数
+const DEFAULT_LIMIT: usize = 20;
+
+/// 最多返回的结果数
+const MAX_LIMIT: usize = 100;
+
+/// 股票搜索结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    /// 股票代码
+    pub code: String,
+    /// 股票名称
+    pub name: String,
+    /// 交易所
+    pub exchange: String,
+    /// 股票类型：Stock/Index/Fund
+    pub stock_type: String,
+}
+
+/// 搜索索引中的一项，代码、名称和拼音都预先转为去掉空白的小写
+struct SearchEntry {
+    result: SearchResult,
+    /// 带交易所后缀的代码，如 `600519.sh`
+    code: String,
+    /// 交易所内的代码，如 `600519`、`700`、`aapl`
+    bare_code: String,
+    name: String,
+    /// 名称的全拼，如 `guizhoumaotai`
+    pinyin: String,
+    /// 名称的拼音首字母，如 `gzmt`
+    initials: String,
+}
+
+lazy_static! {
+    static ref SEARCH_INDEX: RwLock<Option<Vec<SearchEntry>>> = RwLock::new(None);
+}
+
+impl SearchEntry {
+    fn new(result: SearchResult, bare_code: &str) -> Self {
+        let (pinyin, initials) = to_pinyin(&result.name);
+        SearchEntry {
+            code: normalize(&result.code),
+            bare_code: normalize(bare_code),
+            name: normalize(&result.name),
+            pinyin,
+            initials,
+            result,
+        }
+    }
+
+    /// 匹配得分，越小越靠前，不匹配时返回 `None`
+    ///
+    /// 依次为：代码完全相同、数字代码忽略前导 0 后相同、代码前缀、名称完全相同、名称前缀、
+    /// 拼音首字母前缀、全拼前缀、名称包含、拼音首字母包含、全拼包含、代码包含。
+    fn score(&self, query: &str) -> Option<u8> {
+        if self.code == query || self.bare_code == query {
+            return Some(0);
+        }
+        if self.is_same_number(query) {
+            return Some(1);
+        }
+        let rules: [(&str, fn(&str, &str) -> bool); 9] = [
+            (&self.bare_code, |text, query| text.starts_with(query)),
+            (&self.name, |text, query| text == query),
+            (&self.name, |text, query| text.starts_with(query)),
+            (&self.initials, |text, query| text.starts_with(query)),
+            (&self.pinyin, |text, query| text.starts_with(query)),
+            (&self.name, |text, query| text.contains(query)),
+            (&self.initials, |text, query| text.contains(query)),
+            (&self.pinyin, |text, query| text.contains(query)),
+            (&self.bare_code, |text, query| text.contains(query)),
+        ];
+        rules
+            .iter()
+            .position(|(text, matches)| matches(text, query))
+            .map(|position| position as u8 + 2)
+    }
+
+    /// 数字代码忽略前导 0 比较，`00700` 和 `700` 视为相同
+    fn is_same_number(&self, query: &str) -> bool {
+        let is_number = |code: &str| !code.is_empty() && code.chars().all(|c| c.is_ascii_digit());
+        is_number(query)
+            && is_number(&self.bare_code)
+            && query.trim_start_matches('0') == self.bare_code.trim_start_matches('0')
+    }
+
+    /// 同分时个股排在指数和基金前面
+    fn type_rank(&self) -> u8 {
+        match self.result.stock_type.as_str() {
+            "Stock" => 0,
+            "Index" => 1,
+            _ => 2,
+        }
+    }
+}
+
+/// 去掉空白并转为小写
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// 返回名称的全拼和拼音首字母，名称中的字母和数字原样保留
+fn to_pinyin(name: &str) -> (String, String) {
+    let mut pinyin = String::new();
+    let mut initials = String::new();
+    for (c, p) in name.chars().zip(name.to_pinyin()) {
+        match p {
+            Some(p) => {
+                pinyin.push_str(p.plain());
+                initials.push_str(p.first_letter());
+            }
+            None if c.is_ascii_alphanumeric() => {
+                let c = c.to_ascii_lowercase();
+                pinyin.push(c);
+                initials.push(c);
+            }
+            None => {}
+        }
+    }
+    (pinyin, initials)
+}
+
+/// 由 `stock`、`stock_index` 和 `fund` 表重建搜索索引，同一代码只保留第一次出现的记录
+pub async fn rebuild() -> Result<usize, Box<dyn Error>> {
+    let mut codes = HashSet::new();
+    let mut entries = Vec::new();
+    let mut add = |code: String, name: String, exchange: String, stock_type: &str, bare: &str| {
+        if codes.insert(code.clone()) {
+            let result = SearchResult {
+                code,
+                name,
+                exchange,
+                stock_type: stock_type.to_string(),
+            };
+            entries.push(SearchEntry::new(result, bare));
+        }
+    };
+
+    for stock in stock_dao::find_all_stocks().await? {
+        add(
+            stock.code,
+            stock.name,
+            stock.exchange,
+            &stock.stock_type,
+            &stock.stock_code,
+        );
+    }
+    for index in index_svc::find_all_stock_index().await? {
+        add(
+            index.code,
+            index.name,
+            index.exchange,
+            "Index",
+            &index.index_code,
+        );
+    }
+    for fund in fund_svc::find_all().await? {
+        let bare = fund.code.split('.').next().unwrap_or_default().to_string();
+        add(fund.code, fund.name, fund.exchange, "Fund", &bare);
+    }
+
+    let count = entries.len();
+    *SEARCH_INDEX.write().unwrap() = Some(entries);
+    info!("Rebuild stock search index, {} entries", count);
+    Ok(count)
+}
+
+/// 按代码、名称和拼音搜索股票、指数和基金
+///
+/// 搜索索引在第一次搜索时构建，同步股票和基金后重建。
+///
+/// # Arguments
+///
+/// * `query` - 关键字，如 `茅台`、`maotai`、`GZMT`、`700`、`aapl`
+/// * `limit` - 最多返回的结果数，默认 20，最大 100
+pub async fn search(
+    query: &str,
+    limit: Option<usize>,
+) -> Result<Vec<SearchResult>, Box<dyn Error>> {
+    let query = normalize(query);
+    if query.is_empty() {
+        return Err(AppError::InvalidParam("Missing search query".to_string()).into());
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let built = SEARCH_INDEX.read().unwrap().is_some();
+    if !built {
+        rebuild().await?;
+    }
+
+    let index = SEARCH_INDEX.read().unwrap();
+    Ok(rank(index.iter().flatten(), &query, limit))
+}
+
+/// 按得分、类型和代码排序匹配的项，返回前 `limit` 项
+///
+/// `query` 需已去掉空白并转为小写。
+fn rank<'a>(
+    entries: impl Iterator<Item = &'a SearchEntry>,
+    query: &str,
+    limit: usize,
+) -> Vec<SearchResult> {
+    let mut matches = entries
+        .filter_map(|entry| entry.score(query).map(|score| (score, entry)))
+        .collect::<Vec<_>>();
+    matches.sort_by(|(a_score, a), (b_score, b)| {
+        a_score
+            .cmp(b_score)
+            .then(a.type_rank().cmp(&b.type_rank()))
+            .then(a.result.code.cmp(&b.result.code))
+    });
+    matches
+        .into_iter()
+        .take(limit)
+        .map(|(_, entry)| entry.result.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(code: &str, name: &str, stock_type: &str, bare_code: &str) -> SearchEntry {
+        let result = SearchResult {
+            code: code.to_string(),
+            name: name.to_string(),
+            exchange: "SSE".to_string(),
+            stock_type: stock_type.to_string(),
+        };
+        SearchEntry::new(result, bare_code)
+    }
+
+    fn entries() -> Vec<SearchEntry> {
+        vec![
+            entry("600519.SH", "贵州茅台", "Stock", "600519"),
+            entry("MTGN.SH", "茅台概念", "Index", "MTGN"),
+            entry("00700.HK", "腾讯控股", "Stock", "00700"),
+            entry("300700.SZ", "岱勒新材", "Stock", "300700"),
+            entry("AAPL.NS", "Apple Inc", "Stock", "AAPL"),
+            entry("AAPU.NS", "Direxion Daily AAPL Bull 2X", "Fund", "AAPU"),
+        ]
+    }
+
+    fn search_codes(query: &str) -> Vec<String> {
+        let entries = entries();
+        rank(entries.iter(), &normalize(query), DEFAULT_LIMIT)
+            .into_iter()
+            .map(|result| result.code)
+            .collect()
+    }
+
+    #[test]
+    fn test_search_by_name() {
+        // 名称前缀排在名称包含前面
+        assert_eq!(search_codes("茅台"), vec!["MTGN.SH", "600519.SH"]);
+    }
+
+    #[test]
+    fn test_search_by_pinyin() {
+        assert_eq!(search_codes("maotai"), vec!["MTGN.SH", "600519.SH"]);
+        assert_eq!(search_codes("GZMT"), vec!["600519.SH"]);
+        assert_eq!(search_codes("gz mt"), vec!["600519.SH"]);
+    }
+
+    #[test]
+    fn test_search_by_number_code() {
+        // 忽略前导 0 相同的代码排在代码包含前面
+        assert_eq!(search_codes("700"), vec!["00700.HK", "300700.SZ"]);
+        assert_eq!(search_codes("00700.hk"), vec!["00700.HK"]);
+    }
+
+    #[test]
+    fn test_search_by_symbol() {
+        // 代码完全相同的排在名称包含前面
+        assert_eq!(search_codes("aapl"), vec!["AAPL.NS", "AAPU.NS"]);
+        assert_eq!(search_codes("AAPL.ns"), vec!["AAPL.NS"]);
+        // 同为代码前缀时个股排在基金前面
+        assert_eq!(search_codes("aap"), vec!["AAPL.NS", "AAPU.NS"]);
+    }
+
+    #[test]
+    fn test_search_limit() {
+        let entries = entries();
+        let results = rank(entries.iter(), "a", 1);
+        assert_eq!(results.len(), 1);
+    }
+}
@@ -0,0 +1,300 @@
+use crate::error::AppError;
+use crate::exchange::exchange_model::Exchange;
+use crate::exchange::exchange_svc;
+use crate::stock::stock_model::{Model as Stock, StockPrice};
+use crate::stock::stock_price_api::StockPriceApi;
+use crate::stock::stock_svc;
+use application_context::context::application_context::APPLICATION_CONTEXT;
+use application_core::env::property_resolver::PropertyResolver;
+use futures::future::join_all;
+use futures::{Stream, StreamExt, stream};
+use lazy_static::lazy_static;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+
+/// 默认轮询间隔（秒）
+const DEFAULT_INTERVAL_SECONDS: u64 = 3;
+
+/// 单个连接最多订阅的股票数
+const MAX_SUBSCRIBE_CODES: usize = 100;
+
+/// 推送通道容量，订阅者处理过慢时丢弃最旧的行情
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// 行情订阅中心
+struct QuoteHub {
+    /// 已订阅的股票及订阅者数量
+    subscriptions: HashMap<String, (Stock, usize)>,
+    /// 每只股票最近一次推送的价格
+    latest: HashMap<String, StockPrice>,
+    /// 轮询任务是否在运行
+    polling: bool,
+}
+
+lazy_static! {
+    static ref QUOTE_HUB: Mutex<QuoteHub> = Mutex::new(QuoteHub {
+        subscriptions: HashMap::new(),
+        latest: HashMap::new(),
+        polling: false,
+    });
+    static ref QUOTE_SENDER: broadcast::Sender<StockPrice> = broadcast::channel(CHANNEL_CAPACITY).0;
+}
+
+/// 行情订阅，释放时自动取消订阅
+pub struct QuoteSubscription {
+    /// 订阅股票的标准代码，与推送价格中的代码一致
+    codes: HashSet<String>,
+    receiver: broadcast::Receiver<StockPrice>,
+}
+
+impl QuoteSubscription {
+    /// 订阅股票最近一次推送的价格
+    pub fn snapshot(&self) -> Vec<StockPrice> {
+        let hub = QUOTE_HUB.lock().unwrap();
+        self.codes
+            .iter()
+            .filter_map(|code| hub.latest.get(code).cloned())
+            .collect()
+    }
+
+    /// 等待下一条订阅股票的行情，推送通道关闭时返回 `None`
+    pub async fn next(&mut self) -> Option<StockPrice> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(price) if self.codes.contains(&price.code) => return Some(price),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Quote subscriber lagged, skipped {} prices", skipped);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// 转换为行情流，先返回最近一次的价格，再返回后续变化的价格
+    pub fn into_stream(self) -> impl Stream<Item = StockPrice> + Send {
+        let snapshot = self.snapshot();
+        stream::iter(snapshot).chain(stream::unfold(self, |mut subscription| async move {
+            subscription.next().await.map(|price| (price, subscription))
+        }))
+    }
+}
+
+impl Drop for QuoteSubscription {
+    fn drop(&mut self) {
+        let mut hub = QUOTE_HUB.lock().unwrap();
+        for code in &self.codes {
+            let remove = match hub.subscriptions.get_mut(code) {
+                Some((_, count)) => {
+                    *count -= 1;
+                    *count == 0
+                }
+                None => false,
+            };
+            if remove {
+                hub.subscriptions.remove(code);
+                hub.latest.remove(code);
+            }
+        }
+    }
+}
+
+/// 订阅股票实时行情
+///
+/// 第一个订阅者加入时启动轮询任务，轮询任务按配置的间隔批量查询所有已订阅股票的价格，
+/// 只有处于交易中的交易所才会查询，价格变化时推送给订阅了该股票的所有订阅者；
+/// 没有订阅者时轮询任务自动退出。
+///
+/// # Arguments
+///
+/// * `codes` - 股票代码列表，忽略大小写
+pub async fn subscribe(codes: &[String]) -> Result<QuoteSubscription, Box<dyn Error>> {
+    let codes: HashSet<String> = codes.iter().cloned().collect();
+    if codes.is_empty() {
+        return Err(AppError::InvalidParam("Missing codes".to_string()).into());
+    }
+    if codes.len() > MAX_SUBSCRIBE_CODES {
+        return Err(AppError::InvalidParam(format!(
+            "Too many codes, at most {} codes per subscription",
+            MAX_SUBSCRIBE_CODES
+        ))
+        .into());
+    }
+
+    // 按查到的标准代码订阅，大小写不同的同一只股票只订阅一次
+    let mut stocks = HashMap::new();
+    for code in &codes {
+        let stock = stock_svc::get_stock(code).await?;
+        stocks.insert(stock.code.clone(), stock);
+    }
+    let codes: HashSet<String> = stocks.keys().cloned().collect();
+
+    let receiver = QUOTE_SENDER.subscribe();
+    let start = {
+        let mut hub = QUOTE_HUB.lock().unwrap();
+        for stock in stocks.into_values() {
+            hub.subscriptions
+                .entry(stock.code.clone())
+                .or_insert((stock, 0))
+                .1 += 1;
+        }
+        let start = !hub.polling;
+        hub.polling = true;
+        start
+    };
+    if start {
+        tokio::spawn(poll());
+    }
+
+    Ok(QuoteSubscription { codes, receiver })
+}
+
+async fn get_interval() -> u64 {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let environment = application_context.get_environment().await;
+    environment
+        .get_property::<u64>("stock.stream.interval")
+        .filter(|interval| *interval > 0)
+        .unwrap_or(DEFAULT_INTERVAL_SECONDS)
+}
+
+/// 轮询已订阅股票的价格，没有订阅者时退出
+async fn poll() {
+    let interval = get_interval().await;
+    info!("Start polling quotes, interval = {}s", interval);
+    loop {
+        let stocks = {
+            let mut hub = QUOTE_HUB.lock().unwrap();
+            if hub.subscriptions.is_empty() {
+                hub.polling = false;
+                break;
+            }
+            hub.subscriptions
+                .values()
+                .map(|(stock, _)| stock.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let mut groups: HashMap<Exchange, Vec<Stock>> = HashMap::new();
+        for stock in stocks {
+            if let Ok(exchange) = Exchange::from_str(&stock.exchange) {
+                groups.entry(exchange).or_default().push(stock);
+            }
+        }
+        let prices = join_all(
+            groups
+                .into_iter()
+                .map(|(exchange, stocks)| poll_exchange(exchange, stocks)),
+        )
+        .await;
+        for price in prices.into_iter().flatten() {
+            publish(price);
+        }
+
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+    }
+    info!("Stop polling quotes, no subscribers");
+}
+
+/// 批量查询交易所下已订阅股票的价格，交易所不在交易时段时跳过
+async fn poll_exchange(exchange: Exchange, stocks: Vec<Stock>) -> Vec<StockPrice> {
+    let trading = match exchange_svc::get_exchange_market_status(exchange.as_ref()).await {
+        Ok(status) => status == "MarketTrading",
+        Err(e) => {
+            warn!("Get {} market status failed, {}", exchange.as_ref(), e);
+            false
+        }
+    };
+    if !trading {
+        return Vec::new();
+    }
+
+    let prices = exchange.get_stock_prices(&stocks).await;
+    stocks
+        .iter()
+        .zip(prices)
+        .filter_map(|(stock, price)| {
+            match price.and_then(|dto| {
+                stock_svc::create_stock_price(stock, &dto).map_err(|e| e.to_string())
+            }) {
+                Ok(price) => Some(price),
+                Err(e) => {
+                    warn!("Poll {} price failed, {}", stock.code, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// 价格或成交量与上次推送的不同时推送给订阅者
+fn publish(price: StockPrice) {
+    {
+        let mut hub = QUOTE_HUB.lock().unwrap();
+        if !hub.subscriptions.contains_key(&price.code) {
+            return;
+        }
+        if hub
+            .latest
+            .get(&price.code)
+            .is_some_and(|latest| same_quote(latest, &price))
+        {
+            return;
+        }
+        hub.latest.insert(price.code.clone(), price.clone());
+    }
+    // 没有订阅者时发送失败，忽略即可
+    let _ = QUOTE_SENDER.send(price);
+}
+
+/// 两次行情的价格和成交量是否相同，不比较行情时间
+///
+/// 休市或停牌时上游返回的行情时间仍可能变化，只有价格或成交量变化才需要推送。
+fn same_quote(a: &StockPrice, b: &StockPrice) -> bool {
+    a.open == b.open
+        && a.close == b.close
+        && a.low == b.low
+        && a.high == b.high
+        && a.volume == b.volume
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bigdecimal::BigDecimal;
+
+    fn price(close: &str, volume: &str, time: &str) -> StockPrice {
+        StockPrice {
+            code: "AAPL.NS".to_string(),
+            open: BigDecimal::from_str("180").ok(),
+            close: BigDecimal::from_str(close).unwrap(),
+            low: BigDecimal::from_str("179").ok(),
+            high: BigDecimal::from_str("182").ok(),
+            volume: BigDecimal::from_str(volume).ok(),
+            time: time.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_same_quote_ignores_time() {
+        let latest = price("181.5", "1000", "2024-01-02 10:00:00");
+        assert!(same_quote(
+            &latest,
+            &price("181.5", "1000", "2024-01-02 10:00:03")
+        ));
+        assert!(!same_quote(
+            &latest,
+            &price("181.6", "1000", "2024-01-02 10:00:03")
+        ));
+        assert!(!same_quote(
+            &latest,
+            &price("181.5", "1200", "2024-01-02 10:00:03")
+        ));
+    }
+}
@@ -1,165 +1,277 @@
+use crate::corporate_action::corporate_action_svc;
+use crate::error::AppError;
 use crate::exchange::exchange_model::Exchange;
 use crate::exchange::exchange_svc;
+use crate::export::EXPORT_BATCH_ROWS;
 use crate::fund::fund_api::FundApi;
 use crate::fund::{fund_dao, fund_model};
 use crate::holiday::holiday_svc;
 use crate::index::index_job::SyncIndexStocksJob;
+use crate::notifier;
 use crate::stock::stock_api::StockApi;
-use crate::stock::stock_model::{Model as Stock, StockKind, StockPrice};
-use crate::stock::stock_price_api::{StockDailyPrice, StockPriceApi};
-use crate::stock::{stock_api, stock_cache, stock_dao, stock_model, stock_price_api};
-use application_beans::factory::bean_factory::BeanFactory;
-use application_context::context::application_context::APPLICATION_CONTEXT;
+use crate::stock::stock_daily_price_model::{date_to_time, time_to_date};
+use crate::stock::stock_listing_model::{
+    StockListingChanges, StockListingSyncResult, StockRelocation,
+};
+use crate::stock::stock_model::{
+    AdjustedDailyPrices, DailyPriceQuery, Model as Stock, PriceAdjust, SortOrder, StockKind,
+    StockPrice, StockPriceResult,
+};
+use crate::stock::stock_price_api::{
+    StockDailyPrice, StockMinutePrice, StockPriceApi, StockPriceDTO,
+};
+use crate::stock::stock_resample::{KlinePeriod, MinuteInterval};
+use crate::stock::{
+    stock_adjust, stock_api, stock_cache, stock_daily_price_model, stock_dao, stock_model,
+    stock_price_provider, stock_resample, stock_search,
+};
 use application_core::lang::runnable::Runnable;
 use bigdecimal::BigDecimal;
-use chrono::Local;
-use database_mysql_seaorm::Dao;
-use sea_orm::ActiveValue::Set;
-use sea_orm::EntityTrait;
-use sea_orm::IntoActiveModel;
+use chrono::{Datelike, Local, NaiveDate};
+use futures::future::join_all;
+use futures::stream::BoxStream;
+use futures::{StreamExt, stream};
+use sea_orm::prelude::DateTime;
+use sea_orm::{DatabaseTransaction, DbErr, TransactionTrait};
+use std::collections::HashMap;
 use std::error::Error;
-use std::ops::Not;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::u64;
 use tokio::spawn;
-use tracing::info;
+use tracing::{error, info};
 
 /// 异步同步指定交易所的证券和基金信息
 ///
 /// # Arguments
 /// * `exchange` - 一个字符串切片，表示要同步的交易所名称
+/// * `dry_run` - 为 `true` 时只返回与已保存数据的差异，不提交任何变更
 ///
 /// # Returns
-/// * `Result<(), Box<dyn Error>>` - 返回一个结果类型，表示操作成功或携带一个错误类型
+/// * `Result<StockListingSyncResult, Box<dyn Error>>` - 返回股票和基金列表的变化，或携带一个错误类型
 ///
 /// # Remarks
-/// 该函数首先会根据传入的交易所名称创建一个 Exchange 实例，然后同步该交易所的股票和基金信息
-pub async fn sync(exchange: &str) -> Result<(), Box<dyn Error>> {
-    let exchange_str = exchange.to_string();
+/// 该函数先从交易所获取最新的股票和基金列表，再在同一个数据库事务中对比并更新
+/// `stock`、`fund` 表和上市退市历史，任何一步失败都整体回滚，并发读取不会看到更新了一半的数据。
+/// 提交成功后才发送通知、重建搜索索引并在后台同步指数成分股。
+/// `exchange` 表中停用的交易所不同步。
+///
+/// 美股三个交易所的股票来自同一份上市地映射和成分股列表，同步任一美股交易所时一次同步全部
+/// 启用的美股交易所，并先把上市地变化的股票改为新代码，避免被当作退市和新上市。
+pub async fn sync(exchange: &str, dry_run: bool) -> Result<StockListingSyncResult, Box<dyn Error>> {
     let exchange = Exchange::from_str(exchange)?;
+    if !exchange.is_enabled() {
+        return Err(Box::new(AppError::InvalidParam(format!(
+            "Exchange {} is disabled",
+            exchange
+        ))));
+    }
 
-    // 同步股票信息
-    sync_stocks(&exchange).await?;
+    // 网络请求放在事务之外，避免长时间占用事务
+    let mut listings = Vec::new();
+    let mut us_stocks = Vec::new();
+    if exchange.is_us() {
+        us_stocks = stock_api::get_us_stocks().await?;
+        for exchange in Exchange::US_VALUES.into_iter().filter(Exchange::is_enabled) {
+            let stocks = us_stocks
+                .iter()
+                .filter(|stock| stock.exchange == exchange.as_ref())
+                .cloned()
+                .collect::<Vec<_>>();
+            listings.push((exchange, stocks, exchange.get_funds().await?));
+        }
+    } else {
+        listings.push((
+            exchange,
+            exchange.get_stocks().await?,
+            exchange.get_funds().await?,
+        ));
+    }
 
-    // 同步基金信息
-    sync_funds(&exchange).await?;
+    let txn = stock_dao::begin().await?;
+    let relocated = relocate_us_stocks(&txn, &us_stocks).await?;
+    let mut changes = Vec::new();
+    for (exchange, stocks, funds) in &listings {
+        changes.push((
+            *exchange,
+            diff_listing(&txn, exchange, &StockKind::Stock, stocks).await?,
+            diff_listing(&txn, exchange, &StockKind::Fund, funds).await?,
+        ));
+    }
+    let result = StockListingSyncResult {
+        dry_run,
+        relocated,
+        stocks: StockListingChanges::merge(changes.iter().map(|(_, stocks, _)| stocks)),
+        funds: StockListingChanges::merge(changes.iter().map(|(_, _, funds)| funds)),
+    };
+    if dry_run {
+        txn.rollback().await?;
+        return Ok(result);
+    }
 
-    spawn(async {
-        let job = SyncIndexStocksJob {
-            exchange: Some(exchange_str),
-        };
-        job.run().await;
-    });
+    let now = Local::now().naive_local();
+    for ((exchange, stocks, funds), (_, _, fund_list)) in changes.iter().zip(&listings) {
+        apply_listing_changes(&txn, stocks, &now).await?;
+        apply_listing_changes(&txn, funds, &now).await?;
+        save_funds(&txn, exchange, fund_list).await?;
+    }
+    txn.commit().await?;
+
+    for relocation in &result.relocated {
+        info!(
+            "Relocate stock {} to {} {}",
+            relocation.old_code, relocation.exchange, relocation.code
+        );
+        // 缓存中的股票信息和日线仍是旧代码的交易所，新旧代码的缓存都删除
+        for code in [&relocation.old_code, &relocation.code] {
+            if let Err(e) = stock_cache::evict_stock(code).await {
+                error!("Evict stock {} cache error {}", code, e);
+            }
+        }
+    }
+    for (exchange, stocks, funds) in &changes {
+        for (stock_kind, listing) in [(StockKind::Stock, stocks), (StockKind::Fund, funds)] {
+            info!(
+                "Sync {} {}: {} listed, {} delisted, {} renamed",
+                exchange.as_ref(),
+                stock_kind,
+                listing.listed.len(),
+                listing.delisted.len(),
+                listing.renamed.len()
+            );
+            if !listing.first_sync && !listing.is_empty() {
+                spawn(notification_listing_changed(
+                    exchange.as_ref().to_string(),
+                    listing.clone(),
+                ));
+            }
+        }
+    }
+    rebuild_search_index().await;
+
+    for (exchange, _, _) in changes {
+        spawn(async move {
+            let job = SyncIndexStocksJob {
+                exchange: Some(exchange.as_ref().to_string()),
+            };
+            job.run().await;
+        });
+    }
 
-    Ok(())
+    Ok(result)
 }
 
-/// 同步股票信息
-///
-/// 该函数旨在从指定的交易所获取最新的股票信息，然后删除现有的股票信息，
-/// 并保存新的股票信息。这一过程确保了股票数据的最新状态。
-///
-/// # 参数
-///
-/// * `exchange` - 一个引用，指向要从中同步股票信息的交易所。
-///
-/// # 返回值
-///
-/// 该函数返回一个 `Result` 类型，表示操作是否成功。
-/// 如果操作成功，返回 `Ok(())`；如果发生错误，返回一个实现了 `Error` trait 的类型。
-pub async fn sync_stocks(exchange: &Exchange) -> Result<(), Box<dyn Error>> {
-    let stocks = exchange.get_stocks().await?;
-    if stocks.is_empty() {
-        return Ok(());
+/// 股票列表变化后重建搜索索引，重建失败时保留旧的索引
+async fn rebuild_search_index() {
+    if let Err(e) = stock_search::rebuild().await {
+        error!("Rebuild stock search index error {}", e);
     }
+}
 
-    // 删除现有的股票信息，为保存最新的股票信息做准备
-    delete_stocks(exchange).await?;
-
-    // 保存从交易所获取的最新股票信息
-    save_stocks(&stocks).await?;
-
-    Ok(())
+/// 在事务中把上市地变化的美股改为新代码，最新列表为空时不修改
+async fn relocate_us_stocks(
+    txn: &DatabaseTransaction,
+    latest: &[Stock],
+) -> Result<Vec<StockRelocation>, DbErr> {
+    if latest.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut current = Vec::new();
+    for exchange in Exchange::US_VALUES {
+        current.extend(
+            stock_dao::find_stocks_by_exchange_stock_kind(txn, &exchange, &StockKind::Stock)
+                .await?,
+        );
+    }
+    let relocations = StockRelocation::find(&current, latest);
+    stock_dao::relocate_stocks(txn, &relocations).await?;
+    Ok(relocations)
 }
 
-pub async fn sync_funds(exchange: &Exchange) -> Result<(), Box<dyn Error>> {
-    let stocks = exchange.get_funds().await?;
+/// 在事务中对比交易所最新的股票列表和已保存的股票（包括已退市的）
+///
+/// 最新列表为空时不查询已保存的股票，见 `StockListingChanges::diff`。
+async fn diff_listing(
+    txn: &DatabaseTransaction,
+    exchange: &Exchange,
+    stock_kind: &StockKind,
+    stocks: &[Stock],
+) -> Result<StockListingChanges, DbErr> {
     if stocks.is_empty() {
-        return Ok(());
+        return Ok(StockListingChanges::default());
     }
-    delete_funds(exchange).await?;
-    save_stocks(&stocks).await?;
-    save_funds(&stocks).await?;
-    Ok(())
+    let current = stock_dao::find_stocks_by_exchange_stock_kind(txn, exchange, stock_kind).await?;
+    Ok(StockListingChanges::diff(&current, stocks))
 }
 
-/// 保存或更新股票列表
+/// 在事务中更新变化的股票
 ///
-/// # Arguments
-///
-/// * `stocks`:
-///
-/// returns: Result<(), Box<dyn Error, Global>>
-///
-/// # Examples
-///
-/// ```
-///
-/// ```
-async fn save_stocks(stocks: &[Stock]) -> Result<(), Box<dyn Error>> {
-    let application_context = APPLICATION_CONTEXT.read().await;
-    let dao = application_context.get_bean_factory().get::<Dao>();
-
-    let stocks: Vec<stock_model::ActiveModel> = stocks
-        .iter()
-        .map(|e| e.clone().into_active_model())
-        .collect();
-    if stocks.is_empty().not() {
-        stock_model::Entity::insert_many(stocks)
-            .on_empty_do_nothing()
-            .exec(&dao.connection)
-            .await?;
+/// 新上市和更名的股票更新入库，不在最新列表中的股票标记为退市而不删除，
+/// 每次上市、退市和更名都记录到上市退市历史。
+async fn apply_listing_changes(
+    txn: &DatabaseTransaction,
+    changes: &StockListingChanges,
+    now: &DateTime,
+) -> Result<(), DbErr> {
+    if changes.is_empty() {
+        return Ok(());
     }
-    Ok(())
+    stock_dao::upsert_stocks(txn, &changes.upserts()).await?;
+    let delisted = changes
+        .delisted
+        .iter()
+        .map(|stock| stock.code.clone())
+        .collect::<Vec<_>>();
+    stock_dao::delist_stocks(txn, &delisted, now).await?;
+    stock_dao::save_listing_histories(txn, changes.to_histories(now)).await
 }
 
-async fn save_funds(stocks: &Vec<Stock>) -> Result<(), Box<dyn Error>> {
-    let application_context = APPLICATION_CONTEXT.read().await;
-    let dao = application_context.get_bean_factory().get::<Dao>();
-
-    let mut funds = Vec::new();
-    for stock in stocks {
-        funds.push(fund_model::ActiveModel {
-            code: Set(stock.code.clone()),
-            name: Set(stock.name.clone()),
-            exchange: Set(stock.exchange.clone()),
-        });
+/// 分批发送上市、退市和更名通知，每条通知最多 10 只股票
+async fn notification_listing_changed(exchange: String, changes: StockListingChanges) {
+    let title = format!("上市退市关注-{}", exchange);
+    let mut lines = Vec::new();
+    for stock in &changes.listed {
+        lines.push(format!("上市 {:<5} {}\n", stock.name, stock.code));
     }
-
-    if funds.is_empty().not() {
-        fund_model::Entity::insert_many(funds)
-            .on_empty_do_nothing()
-            .exec(&dao.connection)
-            .await?;
+    for stock in &changes.delisted {
+        lines.push(format!("退市 {:<5} {}\n", stock.name, stock.code));
+    }
+    for (old_name, stock) in &changes.renamed {
+        lines.push(format!(
+            "更名 {} -> {:<5} {}\n",
+            old_name, stock.name, stock.code
+        ));
+    }
+    for batch in lines.chunks(10) {
+        notifier::send(&title, &batch.concat()).await;
     }
-
-    Ok(())
-}
-
-pub async fn delete_stocks(exchange: &Exchange) -> Result<(), Box<dyn Error>> {
-    stock_dao::delete_stocks_by_exchange_stock_kind(exchange, &StockKind::Stock).await?;
-    Ok(())
 }
 
-pub async fn delete_funds(exchange: &Exchange) -> Result<(), Box<dyn Error>> {
-    fund_dao::delete_funds_by_exchange(exchange).await?;
-    stock_dao::delete_stocks_by_exchange_stock_kind(exchange, &StockKind::Fund).await?;
-    Ok(())
+/// 在事务中更新交易所的基金列表，不在最新列表中的基金从 `fund` 表删除，退市记录保留在股票表中
+async fn save_funds(
+    txn: &DatabaseTransaction,
+    exchange: &Exchange,
+    stocks: &[Stock],
+) -> Result<(), DbErr> {
+    if stocks.is_empty() {
+        return Ok(());
+    }
+    let funds = stocks
+        .iter()
+        .map(|stock| fund_model::Model {
+            code: stock.code.clone(),
+            name: stock.name.clone(),
+            exchange: stock.exchange.clone(),
+        })
+        .collect::<Vec<_>>();
+    fund_dao::upsert_funds(txn, &funds).await?;
+    let codes = funds.into_iter().map(|fund| fund.code).collect::<Vec<_>>();
+    fund_dao::delete_funds_not_in(txn, exchange.as_ref(), &codes).await
 }
 
 /// 获取股票日线价格数据
 ///
-/// 首先尝试从缓存获取，如果缓存未命中则从 API 获取。
+/// 首先尝试从缓存获取，如果缓存未命中则先增量补齐数据库中的日线，再从数据库读取。
 /// 当市场收盘且非节假日时，会尝试补充最新价格数据。
 ///
 /// # Arguments
@@ -179,11 +291,16 @@ pub async fn get_stock_daily_price(code: &str) -> Result<Vec<StockDailyPrice>, B
         return Ok(daily_prices);
     }
 
-    // 缓存未命中，从 API 获取
-    let mut daily_prices = stock_price_api::get_stock_daily_price(&stock).await?;
+    // 缓存未命中，补齐缺失的日线后从数据库获取
+    let exchange = Exchange::from_str(stock.exchange.as_str())?;
+    sync_stock_daily_price(&stock, &exchange).await?;
+    let mut daily_prices = stock_dao::find_daily_prices(&stock.code)
+        .await?
+        .iter()
+        .map(|price| price.to_stock_daily_price())
+        .collect::<Vec<_>>();
 
     // 并行检查市场状态
-    let exchange = Exchange::from_str(stock.exchange.as_str())?;
     let is_holiday = holiday_svc::is_holiday(exchange.as_ref()).await?;
     let market_closed = exchange_svc::is_market_closed(&exchange).await?;
 
@@ -200,14 +317,23 @@ pub async fn get_stock_daily_price(code: &str) -> Result<Vec<StockDailyPrice>, B
                 if let (Some(open), Some(high), Some(low)) =
                     (latest_price.open, latest_price.high, latest_price.low)
                 {
-                    daily_prices.push(StockDailyPrice {
+                    let daily_price = StockDailyPrice {
                         open,
                         close: latest_price.close,
                         high,
                         low,
                         volume: latest_price.volume,
+                        amount: None,
                         time: current_date,
-                    });
+                    };
+                    // 收盘后的行情即为当日日线，一并入库
+                    let model = stock_daily_price_model::Model::from_stock_daily_price(
+                        &stock.code,
+                        &daily_price,
+                        "quote",
+                    )?;
+                    stock_dao::save_daily_prices(&[model]).await?;
+                    daily_prices.push(daily_price);
                 }
             }
         }
@@ -223,9 +349,297 @@ pub async fn get_stock_daily_price(code: &str) -> Result<Vec<StockDailyPrice>, B
     Ok(daily_prices)
 }
 
-pub async fn get_stock_prices(code: &str) -> Result<Vec<StockDailyPrice>, Box<dyn Error>> {
+/// 增量补齐数据库中的股票日线
+///
+/// 数据库中没有该股票的日线时，从 API 获取全部历史数据入库；
+/// 否则仅当最后一个已收盘交易日的日线缺失时才请求 API，并只保存缺失的交易日。
+async fn sync_stock_daily_price(stock: &Stock, exchange: &Exchange) -> Result<(), Box<dyn Error>> {
+    let last_date = stock_dao::get_last_daily_price_date(&stock.code).await?;
+    let latest_trade_date = get_latest_closed_trade_date(exchange).await?;
+    if last_date.is_some_and(|last_date| last_date >= latest_trade_date) {
+        return Ok(());
+    }
+
+    let batches = stock_price_provider::get_daily_prices(stock, last_date).await?;
+    let mut daily_prices = Vec::new();
+    for (source, prices) in batches {
+        let count = daily_prices.len();
+        for price in prices {
+            let daily_price = stock_daily_price_model::Model::from_stock_daily_price(
+                &stock.code,
+                &price,
+                source,
+            )?;
+            // 已入库的交易日不重复保存，未收盘交易日的数据不入库
+            if last_date.is_some_and(|last_date| daily_price.date <= last_date)
+                || daily_price.date > latest_trade_date
+            {
+                continue;
+            }
+            daily_prices.push(daily_price);
+        }
+        info!(
+            "Save {} daily prices of {} from {}",
+            daily_prices.len() - count,
+            stock.code,
+            source
+        );
+    }
+    stock_dao::save_daily_prices(&daily_prices).await?;
+    Ok(())
+}
+
+/// 获取交易所最近一个已收盘的交易日
+async fn get_latest_closed_trade_date(exchange: &Exchange) -> Result<NaiveDate, Box<dyn Error>> {
+    let now = Local::now().with_timezone(&exchange.time_zone());
+    let mut date = now.date_naive();
+    if !exchange_svc::is_market_closed(exchange).await? {
+        date = date.pred_opt().ok_or("Invalid trade date")?;
+    }
+    // 向前查找交易日，最多回溯一个月
+    for _ in 0..31 {
+        if holiday_svc::is_trade_date(exchange, &date).await? {
+            break;
+        }
+        date = date.pred_opt().ok_or("Invalid trade date")?;
+    }
+    Ok(date)
+}
+
+pub async fn get_stock_prices(
+    code: &str,
+    query: &DailyPriceQuery,
+) -> Result<AdjustedDailyPrices, Box<dyn Error>> {
+    let adjusted = get_stock_adjusted_daily_price(code, query.adjust).await?;
+    Ok(AdjustedDailyPrices {
+        adjust: adjusted.adjust,
+        prices: filter_daily_prices(adjusted.prices, query),
+    })
+}
+
+/// 按查询条件逐批获取股票日线，供导出文件使用
+///
+/// 查询条件与 [`get_stock_prices`] 相同，但不一次查询出全部日线：先补齐数据库中的日线，
+/// 复权时只查询交易日和收盘价计算复权因子，再按日期分页查询，每批 `EXPORT_BATCH_ROWS` 条。
+/// 收盘后尚未入库的当日行情不包含在内。
+pub async fn stream_stock_prices(
+    code: &str,
+    query: &DailyPriceQuery,
+) -> Result<BoxStream<'static, Result<Vec<StockDailyPrice>, AppError>>, Box<dyn Error>> {
+    let stock = get_stock(code).await?;
+    let exchange = Exchange::from_str(&stock.exchange)?;
+    sync_stock_daily_price(&stock, &exchange).await?;
+    let factors = get_adjust_factors(&stock, query.adjust).await?;
+
+    let end = query.end.map(time_to_date).transpose()?;
+    let mut start = query.start.map(time_to_date).transpose()?;
+    if let Some(limit) = query.limit {
+        if limit == 0 {
+            return Ok(stream::empty().boxed());
+        }
+        // 只保留最近的 limit 条，换算为开始日期，不足 limit 条时保留原来的开始日期
+        let nth =
+            stock_dao::find_nth_latest_daily_price_date(&stock.code, start, end, limit as u64)
+                .await?;
+        start = nth.or(start);
+    }
+
+    let order = query.order;
+    let prices = stream::unfold(Some(None), move |after: Option<Option<NaiveDate>>| {
+        let code = stock.code.clone();
+        let factors = factors.clone();
+        async move {
+            let after = after?;
+            let page = stock_dao::find_daily_prices_page(
+                &code,
+                start,
+                end,
+                after,
+                order,
+                EXPORT_BATCH_ROWS,
+            )
+            .await;
+            let page = match page {
+                Ok(page) if page.is_empty() => return None,
+                Ok(page) => page,
+                Err(e) => return Some((Err(AppError::from(e)), None)),
+            };
+            let next = (page.len() as u64 == EXPORT_BATCH_ROWS)
+                .then(|| page.last().map(|price| price.date));
+            let prices = page
+                .iter()
+                .map(|price| {
+                    let price = price.to_stock_daily_price();
+                    match factors
+                        .as_ref()
+                        .and_then(|factors| factors.get(&price.time))
+                    {
+                        Some(factor) => stock_adjust::adjust_price(&price, factor),
+                        None => price,
+                    }
+                })
+                .collect::<Vec<_>>();
+            Some((Ok(prices), next))
+        }
+    });
+    Ok(prices.boxed())
+}
+
+/// 计算逐批导出时的复权因子，以交易时间为键，已除以复权基准；不复权时返回 `None`
+async fn get_adjust_factors(
+    stock: &Stock,
+    adjust: PriceAdjust,
+) -> Result<Option<Arc<HashMap<u64, BigDecimal>>>, Box<dyn Error>> {
+    if adjust == PriceAdjust::None || stock.stock_type != "Stock" {
+        return Ok(None);
+    }
+    let actions = corporate_action_svc::get_corporate_actions(&stock.code).await?;
+    if actions.is_empty() {
+        return Ok(None);
+    }
+    let closes = stock_dao::find_daily_closes(&stock.code)
+        .await?
+        .into_iter()
+        .map(|(date, close)| (date_to_time(&date), close))
+        .collect::<Vec<_>>();
+    let factors = stock_adjust::compute_adjust_factors_by_close(&closes, &actions);
+    let base = match stock_adjust::adjust_base(&factors, adjust) {
+        Some(base) => base,
+        None => return Ok(None),
+    };
+    Ok(Some(Arc::new(
+        closes
+            .iter()
+            .zip(&factors)
+            .map(|((time, _), factor)| (*time, factor / &base))
+            .collect(),
+    )))
+}
+
+/// 获取股票日线并按指定方式复权
+///
+/// 数据库中保存的是不复权日线，复权时由已同步的公司行动计算每日的复权因子，
+/// 再换算为前复权或后复权价格。指数、基金等品种不支持复权，始终返回不复权日线；
+/// 股票还没有同步到任何公司行动时无法复权，同样返回不复权日线，`adjust` 为 `None`。
+///
+/// # Arguments
+///
+/// * `code` - 股票代码
+/// * `adjust` - 复权方式
+pub async fn get_stock_adjusted_daily_price(
+    code: &str,
+    adjust: PriceAdjust,
+) -> Result<AdjustedDailyPrices, Box<dyn Error>> {
     let prices = get_stock_daily_price(code).await?;
-    Ok(prices)
+    let stock = get_stock(code).await?;
+    if adjust == PriceAdjust::None || stock.stock_type != "Stock" {
+        return Ok(AdjustedDailyPrices {
+            adjust: PriceAdjust::None,
+            prices,
+        });
+    }
+
+    let actions = corporate_action_svc::get_corporate_actions(code).await?;
+    if actions.is_empty() {
+        return Ok(AdjustedDailyPrices {
+            adjust: PriceAdjust::None,
+            prices,
+        });
+    }
+    let factors = stock_adjust::compute_adjust_factors(&prices, &actions);
+    Ok(AdjustedDailyPrices {
+        adjust,
+        prices: stock_adjust::apply_adjust_factors(&prices, &factors, adjust),
+    })
+}
+
+/// 按查询条件过滤日线数据
+///
+/// 先按时间范围过滤，再保留最近的 `limit` 条，最后按 `order` 排序。
+pub fn filter_daily_prices(
+    prices: Vec<StockDailyPrice>,
+    query: &DailyPriceQuery,
+) -> Vec<StockDailyPrice> {
+    let mut prices = prices
+        .into_iter()
+        .filter(|price| query.start.is_none_or(|start| price.time >= start))
+        .filter(|price| query.end.is_none_or(|end| price.time <= end))
+        .collect::<Vec<_>>();
+    let skip = query
+        .limit
+        .map_or(0, |limit| prices.len().saturating_sub(limit));
+    prices.drain(..skip);
+    if query.order == SortOrder::Desc {
+        prices.reverse();
+    }
+    prices
+}
+
+/// 获取股票周线、月线、季线或年线
+///
+/// 由复权后的日线按交易日历合并得到，合并后再按查询条件过滤。
+///
+/// # Arguments
+///
+/// * `code` - 股票代码
+/// * `period` - 周期：W/M/Q/Y
+/// * `query` - 查询条件
+pub async fn get_stock_kline(
+    code: &str,
+    period: &str,
+    query: &DailyPriceQuery,
+) -> Result<AdjustedDailyPrices, Box<dyn Error>> {
+    let period = KlinePeriod::from_str(period)?;
+    let stock = get_stock(code).await?;
+    let exchange = Exchange::from_str(stock.exchange.as_str())?;
+    let adjusted = get_stock_adjusted_daily_price(code, query.adjust).await?;
+    let holidays = holiday_svc::get_holiday_dates(&exchange).await?;
+    let klines = stock_resample::resample_daily_prices(&adjusted.prices, period, |date| {
+        date.weekday().number_from_monday() < 6 && !holidays.contains(date)
+    });
+    Ok(AdjustedDailyPrices {
+        adjust: adjusted.adjust,
+        prices: filter_daily_prices(klines, query),
+    })
+}
+
+/// 获取股票当日分钟线
+///
+/// 从交易所获取 1 分钟线并缓存，再按 `interval` 合并为对应周期的分钟线。
+///
+/// # Arguments
+///
+/// * `code` - 股票代码
+/// * `interval` - 周期：1m/5m/15m/60m
+pub async fn get_stock_intraday_prices(
+    code: &str,
+    interval: &str,
+) -> Result<Vec<StockMinutePrice>, Box<dyn Error>> {
+    let interval = MinuteInterval::from_str(interval)?;
+    let stock = get_stock(code).await?;
+    let exchange = Exchange::from_str(stock.exchange.as_str())?;
+
+    let mut prices = stock_cache::get_stock_intraday_prices(&stock).await?;
+    if prices.is_empty() {
+        prices = exchange.get_stock_intraday_prices(&stock).await?;
+        // 交易时段内缓存 1 分钟，其余时间缓存 30 分钟
+        let market_status = exchange_svc::get_exchange_market_status(exchange.as_ref()).await?;
+        let seconds = if market_status == "MarketTrading" {
+            60
+        } else {
+            60 * 30
+        };
+        stock_cache::set_stock_intraday_prices(&stock, &prices, seconds).await?;
+    }
+
+    let sessions = exchange_svc::get_market_times(&exchange)
+        .await?
+        .iter()
+        .map(|market_time| (market_time.start_time, market_time.end_time))
+        .collect::<Vec<_>>();
+    Ok(stock_resample::resample_minute_prices(
+        &prices, interval, &sessions,
+    ))
 }
 
 pub async fn get_stock_price(code: &str) -> Result<StockPrice, Box<dyn Error>> {
@@ -237,6 +651,13 @@ pub async fn get_stock_price(code: &str) -> Result<StockPrice, Box<dyn Error>> {
 pub async fn get_latest_price(stock: &Stock) -> Result<StockPrice, Box<dyn Error>> {
     let exchange = Exchange::from_str(&stock.exchange)?;
     let price_dto = exchange.get_stock_price(&stock).await?;
+    create_stock_price(stock, &price_dto)
+}
+
+pub(crate) fn create_stock_price(
+    stock: &Stock,
+    price_dto: &StockPriceDTO,
+) -> Result<StockPrice, Box<dyn Error>> {
     let price = StockPrice {
         code: stock.code.to_string(),
         high: if price_dto.h.is_empty() {
@@ -266,22 +687,112 @@ pub async fn get_latest_price(stock: &Stock) -> Result<StockPrice, Box<dyn Error
     Ok(price)
 }
 
+/// 批量查询的最大股票数
+const MAX_BATCH_CODES: usize = 500;
+
+/// 批量获取股票当前价格
+///
+/// 并行查询股票信息后按交易所分组，各交易所并行查询价格，结果按请求顺序返回，
+/// 重复的代码只返回一次；大小写不同但指向同一只股票的代码只查询一次价格。
+/// 结果的 `code` 为请求中的代码，`price.code` 为股票的标准代码。
+/// 单只股票查询失败不影响其他股票，失败原因记录在结果的 `error` 中。
+///
+/// # Arguments
+///
+/// * `codes` - 股票代码列表
+pub async fn get_stock_prices_batch(
+    codes: &[String],
+) -> Result<Vec<StockPriceResult>, Box<dyn Error>> {
+    let mut unique_codes = Vec::new();
+    for code in codes {
+        if !unique_codes.contains(code) {
+            unique_codes.push(code.clone());
+        }
+    }
+    if unique_codes.len() > MAX_BATCH_CODES {
+        return Err(AppError::InvalidParam(format!(
+            "Too many codes, at most {} codes per request",
+            MAX_BATCH_CODES
+        ))
+        .into());
+    }
+
+    // 请求代码对应的结果，以及标准代码对应的请求代码
+    let mut results: HashMap<String, StockPriceResult> = HashMap::new();
+    let mut requested: HashMap<String, Vec<String>> = HashMap::new();
+    let mut groups: HashMap<Exchange, Vec<Stock>> = HashMap::new();
+    let stocks = join_all(unique_codes.iter().map(|code| get_stock(code))).await;
+    for (code, stock) in unique_codes.iter().zip(stocks) {
+        let stock = match stock {
+            Ok(stock) => stock,
+            Err(e) => {
+                results.insert(code.clone(), StockPriceResult::error(code, e.to_string()));
+                continue;
+            }
+        };
+        let exchange = match Exchange::from_str(&stock.exchange) {
+            Ok(exchange) => exchange,
+            Err(e) => {
+                results.insert(code.clone(), StockPriceResult::error(code, e.to_string()));
+                continue;
+            }
+        };
+        let codes = requested.entry(stock.code.clone()).or_default();
+        if codes.is_empty() {
+            groups.entry(exchange).or_default().push(stock);
+        }
+        codes.push(code.clone());
+    }
+
+    let group_prices = join_all(groups.iter().map(|(exchange, stocks)| async move {
+        (stocks, exchange.get_stock_prices(stocks).await)
+    }))
+    .await;
+    for (stocks, prices) in group_prices {
+        for (stock, price) in stocks.iter().zip(prices) {
+            let price =
+                price.and_then(|dto| create_stock_price(stock, &dto).map_err(|e| e.to_string()));
+            for code in requested.remove(&stock.code).unwrap_or_default() {
+                let result = match &price {
+                    Ok(price) => StockPriceResult {
+                        code: code.clone(),
+                        price: Some(price.clone()),
+                        error: None,
+                    },
+                    Err(e) => StockPriceResult::error(&code, e.clone()),
+                };
+                results.insert(code, result);
+            }
+        }
+    }
+
+    Ok(unique_codes
+        .iter()
+        .filter_map(|code| results.remove(code))
+        .collect())
+}
+
+/// 获取指定类型的全部股票
+pub async fn get_stocks_by_kind(stock_kind: &StockKind) -> Result<Vec<Stock>, Box<dyn Error>> {
+    Ok(stock_dao::find_stocks_by_kind(stock_kind).await?)
+}
+
 pub async fn get_stock(code: &str) -> Result<Stock, Box<dyn Error>> {
     stock_cache::get_stock(code).await
 }
 
-/// Get earnings surprise data for NASDAQ stocks
+/// Get earnings surprise data for US stocks
 pub async fn get_earnings_surprise(
     code: &str,
 ) -> Result<Vec<stock_api::EarningsSurpriseRow>, Box<dyn Error>> {
     let stock = get_stock(code).await?;
 
-    // Only NASDAQ stocks have earnings surprise data
-    if stock.exchange != Exchange::NASDAQ.as_ref() {
-        return Err(format!(
-            "Earnings surprise data is only available for NASDAQ stocks, got {}",
+    // Only US stocks have earnings surprise data
+    if !Exchange::from_str(&stock.exchange)?.is_us() {
+        return Err(AppError::UnsupportedExchange(format!(
+            "Earnings surprise data is only available for US stocks, got {}",
             stock.exchange
-        )
+        ))
         .into());
     }
 
@@ -290,20 +801,73 @@ pub async fn get_earnings_surprise(
     Ok(earnings_data)
 }
 
-/// Get upper limit stocks for a given exchange
-pub async fn get_uppper_limit_stocks(
+/// 校验涨跌停股池的交易所和交易日
+///
+/// 只支持 SSE 和 SZSE；未指定日期时为交易所当地的当天，指定日期时必须是已过去或当天的交易日。
+pub(crate) async fn get_limit_pool_date(
     exchange: &str,
-) -> Result<Vec<stock_api::UpperLimitStock>, Box<dyn Error>> {
-    // 只支持 SSE 和 SZSE
+    date: Option<&str>,
+) -> Result<(Exchange, NaiveDate), Box<dyn Error>> {
     let exchange_enum = Exchange::from_str(exchange)?;
     match exchange_enum {
         Exchange::SSE | Exchange::SZSE => {}
         _ => {
-            return Err(format!("Only support SSE and SZSE, got {}", exchange).into());
+            return Err(AppError::UnsupportedExchange(format!(
+                "Only support SSE and SZSE, got {}",
+                exchange
+            ))
+            .into());
         }
     }
 
-    // 调用 stock_api 中的实现
-    let stocks = exchange_enum.get_upper_limit_stocks(exchange).await?;
-    Ok(stocks)
+    let today = Local::now()
+        .with_timezone(&exchange_enum.time_zone())
+        .date_naive();
+    let date = match date {
+        None => return Ok((exchange_enum, today)),
+        Some(date) => stock_model::parse_query_date(date)?,
+    };
+    if date > today {
+        return Err(AppError::InvalidParam(format!("Date {} is in the future", date)).into());
+    }
+    if !holiday_svc::is_trade_date(&exchange_enum, &date).await? {
+        return Err(AppError::InvalidParam(format!("{} is not a trade date", date)).into());
+    }
+    Ok((exchange_enum, date))
+}
+
+/// 获取交易所的涨停池
+pub async fn get_uppper_limit_stocks(
+    exchange: &str,
+    date: Option<&str>,
+) -> Result<Vec<stock_api::UpperLimitStock>, Box<dyn Error>> {
+    let (exchange, date) = get_limit_pool_date(exchange, date).await?;
+    exchange.get_upper_limit_stocks(&date).await
+}
+
+/// 获取交易所的跌停池
+pub async fn get_lower_limit_stocks(
+    exchange: &str,
+    date: Option<&str>,
+) -> Result<Vec<stock_api::LowerLimitStock>, Box<dyn Error>> {
+    let (exchange, date) = get_limit_pool_date(exchange, date).await?;
+    exchange.get_lower_limit_stocks(&date).await
+}
+
+/// 获取交易所的炸板池
+pub async fn get_broken_limit_stocks(
+    exchange: &str,
+    date: Option<&str>,
+) -> Result<Vec<stock_api::BrokenLimitStock>, Box<dyn Error>> {
+    let (exchange, date) = get_limit_pool_date(exchange, date).await?;
+    exchange.get_broken_limit_stocks(&date).await
+}
+
+/// 获取交易所的昨日涨停股池，即上一交易日涨停的股票在指定交易日的表现
+pub async fn get_previous_upper_limit_stocks(
+    exchange: &str,
+    date: Option<&str>,
+) -> Result<Vec<stock_api::PreviousUpperLimitStock>, Box<dyn Error>> {
+    let (exchange, date) = get_limit_pool_date(exchange, date).await?;
+    exchange.get_previous_upper_limit_stocks(&date).await
 }
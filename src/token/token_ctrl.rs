@@ -1,3 +1,4 @@
+use crate::error;
 use crate::token::token_svc;
 use application_web::response::RespBody;
 use application_web_macros::get;
@@ -7,5 +8,5 @@ use axum::response::IntoResponse;
 async fn sync() -> impl IntoResponse {
     let result = token_svc::get_hkex_token_from_website().await;
 
-    RespBody::result(&result).response()
+    error::with_status(&result, RespBody::result(&result).response())
 }
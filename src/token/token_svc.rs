@@ -1,3 +1,4 @@
+use crate::upstream;
 use lazy_static::lazy_static;
 use std::error::Error;
 use std::sync::Arc;
@@ -9,7 +10,11 @@ lazy_static! {
     static ref HKEX_TOKEN: Arc<RwLock<String>> = Arc::new(RwLock::new("".to_string()));
 }
 pub async fn get_hkex_token_from_website() -> Result<String, Box<dyn Error>> {
-    let res = Request::get_content("https://www.hkex.com.hk/Market-Data/Securities-Prices/Equities/Equities-Quote?sym=700&sc_lang=zh-HK").await?;
+    let url = format!(
+        "{}/Market-Data/Securities-Prices/Equities/Equities-Quote?sym=700&sc_lang=zh-HK",
+        upstream::get_url("stock.api.hk.website").await?
+    );
+    let res = Request::get_content(&url).await?;
     let idx = res.find("\"Base64-AES-Encrypted-Token\";").unwrap();
     let token = &res[idx..];
     let idx = token.find("return").unwrap();
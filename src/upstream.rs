@@ -0,0 +1,13 @@
+use crate::error::AppError;
+use application_context::context::application_context::APPLICATION_CONTEXT;
+use application_core::env::property_resolver::PropertyResolver;
+
+/// 读取上游接口地址，所有上游地址都配置在 `stock.api.*` 下，如 `stock.api.hsi.baseurl`，
+/// 离线开发时可以统一指向模拟上游服务
+pub async fn get_url(key: &str) -> Result<String, AppError> {
+    let application_context = APPLICATION_CONTEXT.read().await;
+    let environment = application_context.get_environment().await;
+    environment
+        .get_property::<String>(key)
+        .ok_or_else(|| AppError::missing_property(key))
+}